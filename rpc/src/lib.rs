@@ -0,0 +1,270 @@
+//  Copyright 2022 Open Rollup Lab
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+//! RPC interface for the Open Rollup pallet.
+//!
+//! The zkapps, accounts and operations are returned SCALE-encoded, the same as the pallet's
+//! storage, so off-chain provers decode them with the types of the runtime.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Codec, Decode, Encode};
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::error::{CallError, ErrorObject},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_open_rollup_runtime_api::OpenRollupApi as OpenRollupRuntimeApi;
+
+/// The encoded `ProofOutput` of a batch and its commitment.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedProofOutput {
+	/// The SCALE encoding of the `ProofOutput`.
+	pub encoded: Bytes,
+	/// The `blake2_256` hash of `encoded`.
+	pub commitment: H256,
+}
+
+/// Open Rollup RPC methods.
+#[rpc(client, server)]
+pub trait OpenRollupApi<BlockHash, ZkappId, AccountId, StateRoot> {
+	/// The SCALE-encoded zkapp of `zkapp_id`.
+	#[method(name = "openRollup_zkapp")]
+	fn zkapp(&self, zkapp_id: ZkappId, at: Option<BlockHash>) -> RpcResult<Option<Bytes>>;
+
+	/// The SCALE-encoded account of `who` in the zkapp of `zkapp_id`, with the `page`th
+	/// `AssetsLimit` assets of `who`, `None` if `who` has no assets in the page.
+	#[method(name = "openRollup_account")]
+	fn account(
+		&self,
+		zkapp_id: ZkappId,
+		who: AccountId,
+		page: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<Bytes>>;
+
+	/// At most `limit` SCALE-encoded L1 operations in the queue of the zkapp, from the position
+	/// `from`.
+	#[method(name = "openRollup_pendingL1Operations")]
+	fn pending_l1_operations(
+		&self,
+		zkapp_id: ZkappId,
+		from: u32,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<Bytes>>;
+
+	/// Whether `who` has exited from the zkapp of `zkapp_id`.
+	#[method(name = "openRollup_hasExited")]
+	fn has_exited(
+		&self,
+		zkapp_id: ZkappId,
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<bool>;
+
+	/// The hash chain of the first `pos` L1 operations in the queue of the zkapp, the
+	/// `l1_operations_commitment` public input of a batch including `pos` L1 operations, `None`
+	/// if the queue has less than `pos` L1 operations.
+	#[method(name = "openRollup_l1OperationsHash")]
+	fn l1_operations_hash(
+		&self,
+		zkapp_id: ZkappId,
+		pos: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<H256>>;
+
+	/// The block number of the zkapp's last batch, the `block_number` public input of its next
+	/// batch, `None` if the zkapp is not registered or inactive.
+	#[method(name = "openRollup_lastBatchAt")]
+	fn last_batch_at(&self, zkapp_id: ZkappId, at: Option<BlockHash>) -> RpcResult<Option<u64>>;
+
+	/// The encoded `ProofOutput` of a batch and its commitment, `operations` is the SCALE
+	/// encoding of the batch's operations.
+	#[method(name = "openRollup_encodeProofOutput")]
+	fn encode_proof_output(
+		&self,
+		operations: Bytes,
+		state_root: StateRoot,
+		l1_operations_pos: u32,
+		at: Option<BlockHash>,
+	) -> RpcResult<EncodedProofOutput>;
+}
+
+/// Error codes of the Open Rollup RPC methods.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+	/// The SCALE-encoded parameter can't be decoded.
+	DecodeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::DecodeError => 2,
+		}
+	}
+}
+
+/// Provides the Open Rollup RPC methods with the runtime API.
+///
+/// `Zkapp`, `Account` and `Operation` are the types of the runtime's `OpenRollupApi`.
+pub struct OpenRollup<C, Block, Zkapp, Account, Operation> {
+	client: Arc<C>,
+	_marker: PhantomData<(Block, Zkapp, Account, Operation)>,
+}
+
+impl<C, Block, Zkapp, Account, Operation> OpenRollup<C, Block, Zkapp, Account, Operation> {
+	/// Create new `OpenRollup` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Convert an error of the runtime API into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		Error::RuntimeError.into(),
+		"Unable to query the Open Rollup runtime API.",
+		Some(format!("{:?}", err)),
+	))
+	.into()
+}
+
+#[async_trait]
+impl<C, Block, ZkappId, AccountId, StateRoot, Zkapp, Account, Operation>
+	OpenRollupApiServer<<Block as BlockT>::Hash, ZkappId, AccountId, StateRoot>
+	for OpenRollup<C, Block, Zkapp, Account, Operation>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: OpenRollupRuntimeApi<Block, ZkappId, AccountId, StateRoot, Zkapp, Account, Operation>,
+	ZkappId: Codec + DeserializeOwned + Send + Sync + 'static,
+	AccountId: Codec + DeserializeOwned + Send + Sync + 'static,
+	StateRoot: Codec + DeserializeOwned + Send + Sync + 'static,
+	Zkapp: Codec + Send + Sync + 'static,
+	Account: Codec + Send + Sync + 'static,
+	Operation: Codec + Send + Sync + 'static,
+{
+	fn zkapp(
+		&self,
+		zkapp_id: ZkappId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<Bytes>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let zkapp = api.zkapp(&at, zkapp_id).map_err(runtime_error_into_rpc_err)?;
+		Ok(zkapp.map(|zkapp| zkapp.encode().into()))
+	}
+
+	fn account(
+		&self,
+		zkapp_id: ZkappId,
+		who: AccountId,
+		page: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<Bytes>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let account = api.account(&at, zkapp_id, who, page).map_err(runtime_error_into_rpc_err)?;
+		Ok(account.map(|account| account.encode().into()))
+	}
+
+	fn pending_l1_operations(
+		&self,
+		zkapp_id: ZkappId,
+		from: u32,
+		limit: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Vec<Bytes>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let operations = api
+			.pending_l1_operations(&at, zkapp_id, from, limit)
+			.map_err(runtime_error_into_rpc_err)?;
+		Ok(operations.iter().map(|operation| operation.encode().into()).collect())
+	}
+
+	fn has_exited(
+		&self,
+		zkapp_id: ZkappId,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<bool> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.has_exited(&at, zkapp_id, who).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn l1_operations_hash(
+		&self,
+		zkapp_id: ZkappId,
+		pos: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<H256>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let hash =
+			api.l1_operations_hash(&at, zkapp_id, pos).map_err(runtime_error_into_rpc_err)?;
+		Ok(hash.map(Into::into))
+	}
+
+	fn last_batch_at(
+		&self,
+		zkapp_id: ZkappId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<Option<u64>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.last_batch_at(&at, zkapp_id).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn encode_proof_output(
+		&self,
+		operations: Bytes,
+		state_root: StateRoot,
+		l1_operations_pos: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> RpcResult<EncodedProofOutput> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let operations = Vec::<Operation>::decode(&mut &operations[..]).map_err(|e| {
+			CallError::Custom(ErrorObject::owned(
+				Error::DecodeError.into(),
+				"Unable to decode the operations.",
+				Some(format!("{:?}", e)),
+			))
+		})?;
+		let (encoded, commitment) = api
+			.encode_proof_output(&at, operations, state_root, l1_operations_pos)
+			.map_err(runtime_error_into_rpc_err)?;
+		Ok(EncodedProofOutput { encoded: encoded.into(), commitment: commitment.into() })
+	}
+}
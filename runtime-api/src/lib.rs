@@ -0,0 +1,65 @@
+//  Copyright 2022 Open Rollup Lab
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+//! Runtime API definition for the Open Rollup pallet.
+//!
+//! The runtime implements it with the pallet's functions of the same names, e.g.
+//! `OpenRollup::zkapp(zkapp_id)`, where `Zkapp`, `Account` and `Operation` are the pallet's
+//! `ZkappOf`, `AccountOf` and `OperationOf` of the runtime.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// The API to query zkapps, accounts and L1 operations of the Open Rollup pallet.
+	pub trait OpenRollupApi<ZkappId, AccountId, StateRoot, Zkapp, Account, Operation> where
+		ZkappId: Codec,
+		AccountId: Codec,
+		StateRoot: Codec,
+		Zkapp: Codec,
+		Account: Codec,
+		Operation: Codec,
+	{
+		/// The zkapp of `zkapp_id`.
+		fn zkapp(zkapp_id: ZkappId) -> Option<Zkapp>;
+
+		/// The account of `who` in the zkapp of `zkapp_id`, with the `page`th `AssetsLimit`
+		/// assets of `who`, `None` if `who` has no assets in the page.
+		fn account(zkapp_id: ZkappId, who: AccountId, page: u32) -> Option<Account>;
+
+		/// At most `limit` L1 operations in the queue of the zkapp, from the position `from`.
+		fn pending_l1_operations(zkapp_id: ZkappId, from: u32, limit: u32) -> Vec<Operation>;
+
+		/// Whether `who` has exited from the zkapp of `zkapp_id`.
+		fn has_exited(zkapp_id: ZkappId, who: AccountId) -> bool;
+
+		/// The hash chain of the first `pos` L1 operations in the queue of the zkapp, the
+		/// `l1_operations_commitment` public input of a batch including `pos` L1 operations,
+		/// `None` if the queue has less than `pos` L1 operations.
+		fn l1_operations_hash(zkapp_id: ZkappId, pos: u32) -> Option<[u8; 32]>;
+
+		/// The block number of the zkapp's last batch, the `block_number` public input of its
+		/// next batch, `None` if the zkapp is not registered or inactive.
+		fn last_batch_at(zkapp_id: ZkappId) -> Option<u64>;
+
+		/// The SCALE encoding and the commitment of the `ProofOutput` of a batch.
+		fn encode_proof_output(
+			operations: Vec<Operation>,
+			state_root: StateRoot,
+			l1_operations_pos: u32,
+		) -> (Vec<u8>, [u8; 32]);
+	}
+}
@@ -23,6 +23,8 @@ use crate::Pallet as OpenRollup;
 
 const SEED: u32 = 0;
 const PROGRAM_HASH: [u8; 32] = *b"0000000000000001ad428e4906aE43D8";
+/// The id of the zkapp registered by `register_other_zkapp`, after the default one.
+const OTHER_ZKAPP_ID: ZkappId = 1;
 const STATE_ROOT_1: [u8; 32] = *b"00000000000000000000000000000000";
 const STATE_ROOT_2: [u8; 32] = *b"0059b62bc53ad4150a3e712d6273956f";
 
@@ -31,7 +33,7 @@ fn assert_last_event<T: Config<I>, I: 'static>(generic_event: <T as Config<I>>::
 	frame_system::Pallet::<T>::assert_last_event(generic_event.into());
 }
 
-/// Register one zkapp use default program hash.
+/// Register one zkapp use default program hash as the default zkapp id, and bond its submitter.
 fn register_default_zkapp<T: Config<I>, I: 'static>() -> (T::AccountId, AccountIdLookupOf<T>)
 where
 	CurrencyBalanceOf<T, I>: From<u64>,
@@ -59,10 +61,40 @@ where
 	)
 	.is_ok());
 
+	assert!(OpenRollup::<T, I>::bond_submitter(
+		SystemOrigin::Signed(caller.clone()).into(),
+		Default::default()
+	)
+	.is_ok());
+
 	(caller, caller_lookup)
 }
 
-/// Register one zkapp use another program hash.
+/// Add `s - 1` bonded submitters into the zkapp of default zkapp id, after the default one.
+fn add_default_submitters<T: Config<I>, I: 'static>(s: u32)
+where
+	CurrencyBalanceOf<T, I>: From<u64>,
+{
+	let caller: T::AccountId = whitelisted_caller();
+
+	for i in 1..s {
+		let submitter: T::AccountId = account("submitter", i, SEED);
+		T::Currency::make_free_balance_be(&submitter, 8888888888u64.into());
+		OpenRollup::<T, I>::add_submitter(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			T::Lookup::unlookup(submitter.clone()),
+		)
+		.unwrap();
+		OpenRollup::<T, I>::bond_submitter(
+			SystemOrigin::Signed(submitter).into(),
+			Default::default(),
+		)
+		.unwrap();
+	}
+}
+
+/// Register one zkapp use another program hash as `OTHER_ZKAPP_ID`.
 fn register_other_zkapp<T: Config<I>, I: 'static>() -> (T::AccountId, AccountIdLookupOf<T>) {
 	let caller: T::AccountId = whitelisted_caller();
 	let caller_lookup = T::Lookup::unlookup(caller.clone());
@@ -79,7 +111,7 @@ fn register_other_zkapp<T: Config<I>, I: 'static>() -> (T::AccountId, AccountIdL
 	(caller, caller_lookup)
 }
 
-/// Add assets support for zkapp of `PROGRAM_HASH` program hash
+/// Add assets support for zkapp of `OTHER_ZKAPP_ID`
 fn add_other_assets_support<T: Config<I>, I: 'static>() -> (T::AccountId, AccountIdLookupOf<T>) {
 	let caller: T::AccountId = whitelisted_caller();
 	let caller_lookup = T::Lookup::unlookup(caller.clone());
@@ -89,14 +121,14 @@ fn add_other_assets_support<T: Config<I>, I: 'static>() -> (T::AccountId, Accoun
 
 	assert!(OpenRollup::<T, I>::add_asset_support(
 		SystemOrigin::Signed(caller.clone()).into(),
-		T::Helper::program_hash(PROGRAM_HASH),
+		OTHER_ZKAPP_ID,
 		Asset::Fungible(asset_id),
 	)
 	.is_ok());
 
 	assert!(OpenRollup::<T, I>::add_asset_support(
 		SystemOrigin::Signed(caller.clone()).into(),
-		T::Helper::program_hash(PROGRAM_HASH),
+		OTHER_ZKAPP_ID,
 		Asset::Nonfungible(collection_id),
 	)
 	.is_ok());
@@ -104,7 +136,7 @@ fn add_other_assets_support<T: Config<I>, I: 'static>() -> (T::AccountId, Accoun
 	(caller, caller_lookup)
 }
 
-/// Add assets support for zkapp of default program hash
+/// Add assets support for zkapp of default zkapp id
 fn add_default_assets_support<T: Config<I>, I: 'static>() -> (T::AccountId, AccountIdLookupOf<T>) {
 	let caller: T::AccountId = whitelisted_caller();
 	let caller_lookup = T::Lookup::unlookup(caller.clone());
@@ -140,7 +172,7 @@ benchmarks_instance_pallet! {
 		let caller_lookup = T::Lookup::unlookup(caller.clone());
 	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), ZkvmType::Fake, caller_lookup, Default::default())
 	verify {
-		assert_last_event::<T, I>(Event::ZkappRegister(ZkvmType::Fake, Default::default()).into());
+		assert_last_event::<T, I>(Event::ZkappRegister(Default::default(), ZkvmType::Fake, Default::default()).into());
 	}
 
 	add_asset_support {
@@ -151,20 +183,255 @@ benchmarks_instance_pallet! {
 		assert_last_event::<T, I>(Event::AddAssetSupport(Default::default(), asset).into());
 	}
 
+	set_fee_policy {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let fee = AssetValueOf::<T, I>::Fungible(T::Helper::asset(11), 10u32.into());
+		let fee_policy = Some(FeePolicy::PerOperation(fee.clone(), fee.clone(), fee));
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), fee_policy.clone())
+	verify {
+		assert_last_event::<T, I>(Event::FeePolicySet(Default::default(), fee_policy).into());
+	}
+
+	propose_upgrade {
+		let k in 0 .. T::MaxVerifyingKeyLen::get();
+
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let new_program_hash = T::ProgramHash::default();
+		let new_verifying_key = vec![1u8; k as usize];
+		let enact_at = frame_system::Pallet::<T>::block_number() + T::UpgradeDelay::get();
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), new_program_hash, ZkvmType::Fake, Some(new_verifying_key))
+	verify {
+		assert_last_event::<T, I>(Event::UpgradeProposed(
+			Default::default(),
+			new_program_hash,
+			ZkvmType::Fake,
+			enact_at,
+		).into());
+	}
+
+	enact_upgrade {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		OpenRollup::<T, I>::propose_upgrade(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			Default::default(),
+			ZkvmType::Fake,
+			Some(vec![1u8; T::MaxVerifyingKeyLen::get() as usize]),
+		).unwrap();
+		frame_system::Pallet::<T>::set_block_number(
+			frame_system::Pallet::<T>::block_number() + T::UpgradeDelay::get(),
+		);
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::UpgradeEnacted(
+			Default::default(),
+			Default::default(),
+			ZkvmType::Fake,
+		).into());
+	}
+
+	set_metadata {
+		let s in 0 .. 3 * T::StringLimit::get();
+
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let limit = T::StringLimit::get();
+		let name = vec![b'n'; s.min(limit) as usize];
+		let description = vec![b'd'; s.saturating_sub(limit).min(limit) as usize];
+		let source_url = vec![b'u'; s.saturating_sub(2 * limit) as usize];
+
+		// The metadata replaced is unreserved.
+		OpenRollup::<T, I>::set_metadata(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			vec![],
+			vec![],
+			vec![],
+			[0u8; 32],
+		).unwrap();
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), name.clone(), description.clone(), source_url.clone(), [1u8; 32])
+	verify {
+		assert_last_event::<T, I>(Event::Metadata(
+			Default::default(),
+			name.try_into().unwrap(),
+			description.try_into().unwrap(),
+			source_url.try_into().unwrap(),
+			[1u8; 32],
+		).into());
+	}
+
+	clear_metadata {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		OpenRollup::<T, I>::set_metadata(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			vec![],
+			vec![],
+			vec![],
+			[0u8; 32],
+		).unwrap();
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::MetadataCleared(Default::default()).into());
+	}
+
+	set_verifying_key {
+		let k in 1 .. T::MaxVerifyingKeyLen::get();
+
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let verifying_key = vec![1u8; k as usize];
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), verifying_key)
+	verify {
+		assert_last_event::<T, I>(Event::SetVerifyingKey(Default::default()).into());
+	}
+
 	change_submitter {
+		let s in 1 .. T::MaxSubmitters::get();
+
 		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		add_default_submitters::<T, I>(s);
 	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), caller_lookup)
 	verify {
 		assert_last_event::<T, I>(Event::ChangeSubmitter(Default::default(), caller).into());
 	}
 
+	add_submitter {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let submitter: T::AccountId = account("submitter", 0, SEED);
+		let submitter_lookup = T::Lookup::unlookup(submitter.clone());
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), submitter_lookup)
+	verify {
+		assert_last_event::<T, I>(Event::SubmitterAdded(Default::default(), submitter).into());
+	}
+
+	remove_submitter {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		add_default_submitters::<T, I>(2);
+		let submitter: T::AccountId = account("submitter", 1, SEED);
+		let submitter_lookup = T::Lookup::unlookup(submitter.clone());
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), submitter_lookup)
+	verify {
+		assert_last_event::<T, I>(Event::SubmitterRemoved(Default::default(), submitter).into());
+	}
+
+	set_submitter_rotation {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let rotation = SubmitterRotation { slot_length: 10u32.into(), grace_period: 100u32.into() };
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), Some(rotation.clone()))
+	verify {
+		assert_last_event::<T, I>(Event::SubmitterRotationSet(Default::default(), Some(rotation)).into());
+	}
+
 	set_inactive {
+		let s in 1 .. T::MaxSubmitters::get();
+
 		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		add_default_submitters::<T, I>(s);
 	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
 	verify {
 		assert_last_event::<T, I>(Event::SetInactive(Default::default()).into());
 	}
 
+	transfer_ownership {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let new_owner: T::AccountId = account("owner", 0, SEED);
+		let new_owner_lookup = T::Lookup::unlookup(new_owner.clone());
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), new_owner_lookup)
+	verify {
+		assert_last_event::<T, I>(Event::OwnershipTransferProposed(Default::default(), caller, new_owner).into());
+	}
+
+	accept_ownership {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let new_owner: T::AccountId = account("owner", 0, SEED);
+		let new_owner_lookup = T::Lookup::unlookup(new_owner.clone());
+		OpenRollup::<T, I>::transfer_ownership(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			new_owner_lookup,
+		).unwrap();
+	}: _(SystemOrigin::Signed(new_owner.clone()), Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::OwnershipTransferred(Default::default(), caller, new_owner).into());
+	}
+
+	force_set_inactive {
+		let s in 1 .. T::MaxSubmitters::get();
+
+		register_default_zkapp::<T, I>();
+		add_default_submitters::<T, I>(s);
+		let origin = T::ForceOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, Default::default(), true)
+	verify {
+		assert_last_event::<T, I>(Event::ForcedInactive(Default::default(), InactiveReason::Forced).into());
+	}
+
+	force_change_submitter {
+		let s in 1 .. T::MaxSubmitters::get();
+
+		register_default_zkapp::<T, I>();
+		add_default_submitters::<T, I>(s);
+		let submitter: T::AccountId = account("submitter", 0, SEED);
+		let submitter_lookup = T::Lookup::unlookup(submitter.clone());
+		let origin = T::ForceOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, Default::default(), submitter_lookup)
+	verify {
+		assert_last_event::<T, I>(Event::ChangeSubmitter(Default::default(), submitter).into());
+	}
+
+	remove_asset_support {
+		register_default_zkapp::<T, I>();
+		add_default_assets_support::<T, I>();
+		let asset = Asset::Nonfungible(T::Helper::collection(11));
+		let origin = T::ForceOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, Default::default(), asset.clone())
+	verify {
+		assert_last_event::<T, I>(Event::RemoveAssetSupport(Default::default(), asset).into());
+	}
+
+	force_transfer_ownership {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let new_owner: T::AccountId = account("owner", 0, SEED);
+		let new_owner_lookup = T::Lookup::unlookup(new_owner.clone());
+		let origin = T::ForceOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, Default::default(), new_owner_lookup)
+	verify {
+		assert_last_event::<T, I>(Event::OwnershipTransferred(Default::default(), caller, new_owner).into());
+	}
+
+	force_clear_metadata {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		OpenRollup::<T, I>::set_metadata(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			vec![],
+			vec![],
+			vec![],
+			[0u8; 32],
+		).unwrap();
+		let origin = T::ForceOrigin::successful_origin();
+	}: _<T::RuntimeOrigin>(origin, Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::MetadataCleared(Default::default()).into());
+	}
+
+	bond_submitter {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		OpenRollup::<T, I>::unbond_submitter(Default::default(), &caller);
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::SubmitterBonded(Default::default(), caller, T::SubmitterBond::get()).into());
+	}
+
+	withdraw_unbonded {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		OpenRollup::<T, I>::change_submitter(SystemOrigin::Signed(caller.clone()).into(), Default::default(), caller_lookup).unwrap();
+		let unbonding = Unbondings::<T, I>::get::<ZkappId, T::AccountId>(Default::default(), caller.clone()).unwrap();
+		frame_system::Pallet::<T>::set_block_number(unbonding.unlock_at);
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::SubmitterUnbonded(Default::default(), caller, unbonding.amount).into());
+	}
+
 	deposit {
 		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
 		add_default_assets_support::<T, I>();
@@ -185,6 +452,17 @@ benchmarks_instance_pallet! {
 		assert_last_event::<T, I>(Event::Withdrawed(Default::default(), caller, asset_value).into());
 	}
 
+	force_withdraw {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		add_default_assets_support::<T, I>();
+		let asset_value = AssetValueOf::<T, I>::Fungible(T::Helper::asset(11), 10u32.into());
+		OpenRollup::<T, I>::add_zkapp_user_asset(Default::default(), caller.clone(), &asset_value).unwrap();
+		let deadline = frame_system::Pallet::<T>::block_number().saturating_add(T::ForcedWithdrawalDelay::get());
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), asset_value.clone())
+	verify {
+		assert_last_event::<T, I>(Event::ForcedWithdrawal(Default::default(), caller, asset_value, deadline).into());
+	}
+
 	move_asset {
 		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
 		add_default_assets_support::<T, I>();
@@ -192,21 +470,26 @@ benchmarks_instance_pallet! {
 		add_other_assets_support::<T, I>();
 		let asset_value = AssetValueOf::<T, I>::Fungible(T::Helper::asset(11), 10u32.into());
 		OpenRollup::<T, I>::add_zkapp_user_asset(Default::default(), caller.clone(), &asset_value).unwrap();
-	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), T::Helper::program_hash(PROGRAM_HASH), asset_value.clone())
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), OTHER_ZKAPP_ID, asset_value.clone())
 	verify {
-		assert_last_event::<T, I>(Event::MoveAsset(Default::default(), T::Helper::program_hash(PROGRAM_HASH), caller, asset_value).into());
+		assert_last_event::<T, I>(Event::MoveAsset(Default::default(), OTHER_ZKAPP_ID, caller, asset_value).into());
 	}
 
 	exit {
+		let l in 1 .. T::L1OperationsPageSize::get();
+
 		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
 		add_default_assets_support::<T, I>();
 
-		<<T as Config<I>>::Fungibles as fungibles::Mutate<T::AccountId>>::mint_into(T::Helper::asset(11), &caller, 10000u32.into()).unwrap();
+		<<T as Config<I>>::Fungibles as fungibles::Mutate<T::AccountId>>::mint_into(T::Helper::asset(11), &caller, (10 * l).into()).unwrap();
 
 		let origin = SystemOrigin::Signed(caller.clone());
 		let asset_value = AssetValueOf::<T, I>::Fungible(T::Helper::asset(11), 10u32.into());
 
-		OpenRollup::<T, I>::deposit(origin.clone().into(), Default::default(), asset_value.clone()).unwrap();
+		// Every L1 operation visited is a deposit of the user.
+		for _ in 0..l {
+			OpenRollup::<T, I>::deposit(origin.clone().into(), Default::default(), asset_value.clone()).unwrap();
+		}
 		OpenRollup::<T, I>::set_inactive(origin.clone().into(), Default::default()).unwrap();
 
 	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
@@ -214,6 +497,73 @@ benchmarks_instance_pallet! {
 		assert_last_event::<T, I>(Event::Exit(Default::default(), caller).into());
 	}
 
+	exit_with_proof {
+		let p in 1 .. 64;
+		let l in 1 .. T::L1OperationsPageSize::get();
+
+		let caller: T::AccountId = whitelisted_caller();
+		let caller_lookup = T::Lookup::unlookup(caller.clone());
+		let origin = SystemOrigin::Signed(caller.clone());
+
+		// The pallet account holds the assets of the leaf.
+		T::Currency::make_free_balance_be(&OpenRollup::<T, I>::account_id(), 8888888888u64.into());
+
+		let mut assets = BoundedVec::default();
+		assets.try_push(AssetValueOf::<T, I>::Currency(1000u64.into())).unwrap();
+		let account = Account { user: caller.clone(), assets };
+
+		// Register a zkapp whose state tree includes the leaf.
+		let proof = vec![[1u8; 32]; p as usize];
+		let state_root = T::StateTree::compute_root(&account.encode(), 0, &proof);
+		OpenRollup::<T, I>::zkapp_register(
+			origin.clone().into(),
+			Default::default(),
+			ZkvmType::Fake,
+			caller_lookup,
+			T::Helper::state_root(state_root),
+		).unwrap();
+		// The zkapp holds the assets of the leaf.
+		OpenRollup::<T, I>::add_user_asset(
+			Default::default(),
+			&account("other", 0, SEED),
+			&AssetValueOf::<T, I>::Currency(1000u64.into()),
+		).unwrap();
+
+		// Every L1 operation visited is a deposit of the user.
+		let deposit = Operation::Deposit(caller.clone(), AssetValueOf::<T, I>::Currency(10u64.into()));
+		for _ in 0..l {
+			OpenRollup::<T, I>::push_l1_operation(Default::default(), deposit.clone()).unwrap();
+		}
+		OpenRollup::<T, I>::set_inactive(origin.into(), Default::default()).unwrap();
+
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), account, 0, proof)
+	verify {
+		assert_last_event::<T, I>(Event::Exit(Default::default(), caller).into());
+	}
+
+	refund_l1_deposits {
+		let l in 1 .. T::L1OperationsPageSize::get();
+
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let origin = SystemOrigin::Signed(caller.clone());
+
+		// The pallet account holds the assets of the deposits.
+		T::Currency::make_free_balance_be(&OpenRollup::<T, I>::account_id(), 8888888888u64.into());
+
+		// The first page is refunded by `exit`, the next `l` deposits are left.
+		let page = T::L1OperationsPageSize::get();
+		let deposit = Operation::Deposit(caller.clone(), AssetValueOf::<T, I>::Currency(10u64.into()));
+		for _ in 0..page + l {
+			OpenRollup::<T, I>::push_l1_operation(Default::default(), deposit.clone()).unwrap();
+		}
+		OpenRollup::<T, I>::set_inactive(origin.clone().into(), Default::default()).unwrap();
+		OpenRollup::<T, I>::exit(origin.into(), Default::default()).unwrap();
+
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::L1DepositsRefunded(Default::default(), caller, (page + l).into()).into());
+	}
+
 	submit_batch {
 		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
 		add_default_assets_support::<T, I>();
@@ -266,17 +616,89 @@ benchmarks_instance_pallet! {
 		// Check SubmitBatch event.
 		assert_last_event::<T, I>(Event::SubmitBatch(Default::default(), old_state_root, new_state_root, operations).into());
 
-		let zkapp = Zkapps::<T, I>::try_get::<T::ProgramHash>(Default::default()).unwrap();
-		// Check `l1_operations`data.
-		assert_eq!(zkapp.l1_operations.len(), 0);
+		let zkapp = Zkapps::<T, I>::try_get::<ZkappId>(Default::default()).unwrap();
+		// Check the L1 operations queue is consumed.
+		assert!(L1OperationsQueue::<T, I>::get::<ZkappId>(Default::default()).is_empty());
 		// Check `new_state_root` has saved.
 		assert_eq!(zkapp.state_root, new_state_root);
 
 		// Check users's assets is correct.
-		let account_1 = ZkappsAccounts::<T, I>::try_get::<T::ProgramHash, T::AccountId>(Default::default(), user_1.clone()).unwrap();
-		assert_eq!(account_1.assets.last().unwrap(), &asset_value_2);
-		let account_2 = ZkappsAccounts::<T, I>::try_get::<T::ProgramHash, T::AccountId>(Default::default(), user_2.clone()).unwrap();
-		assert_eq!(account_2.assets.last().unwrap(), &asset_value_1);
+		assert!(OpenRollup::<T, I>::check_has_enough_asset(Default::default(), &user_1, &asset_value_2));
+		assert!(!OpenRollup::<T, I>::check_has_enough_asset(Default::default(), &user_1, &asset_value_1));
+		assert!(OpenRollup::<T, I>::check_has_enough_asset(Default::default(), &user_2, &asset_value_1));
+		assert!(!OpenRollup::<T, I>::check_has_enough_asset(Default::default(), &user_2, &asset_value_3));
+	}
+
+	commit_batch {
+		let n in 1 .. 100;
+
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let old_state_root = T::Helper::state_root(STATE_ROOT_1);
+		let new_state_root = T::Helper::state_root(STATE_ROOT_2);
+		let user_1: T::AccountId = account("user_1", 0, SEED);
+		let user_2: T::AccountId = account("user_2", 0, SEED);
+
+		// The operations are not executed, only committed.
+		let asset_value = AssetValueOf::<T, I>::Currency(10u64.into());
+		let operations = vec![Operation::Transfer(user_1, user_2, asset_value); n as usize];
+		let commitment = operations_commitment(&operations, Default::default());
+
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), old_state_root, new_state_root, 0, operations.clone(), vec![1, 2, 3], None)
+	verify {
+		assert_last_event::<T, I>(Event::BatchCommitted(Default::default(), old_state_root, new_state_root, operations, commitment).into());
+	}
+
+	execute_batch {
+		let n in 1 .. 100;
+
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let user_1: T::AccountId = account("user_1", 0, SEED);
+		let user_2: T::AccountId = account("user_2", 0, SEED);
+
+		// Commit a batch transferring the assets of user_1 to user_2.
+		let asset_value = AssetValueOf::<T, I>::Currency(10u64.into());
+		OpenRollup::<T, I>::add_zkapp_user_asset(Default::default(), user_1.clone(), &AssetValueOf::<T, I>::Currency((10 * n as u64).into())).unwrap();
+		let operations = vec![Operation::Transfer(user_1, user_2.clone(), asset_value.clone()); n as usize];
+		OpenRollup::<T, I>::commit_batch(
+			SystemOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			T::Helper::state_root(STATE_ROOT_1),
+			T::Helper::state_root(STATE_ROOT_2),
+			0,
+			operations.clone(),
+			vec![1, 2, 3],
+			None,
+		).unwrap();
+
+	}: _(SystemOrigin::Signed(caller.clone()), Default::default(), operations, Default::default())
+	verify {
+		assert_last_event::<T, I>(Event::BatchExecuted(Default::default(), n, n).into());
+		assert!(OpenRollup::<T, I>::check_has_enough_asset(Default::default(), &user_2, &AssetValueOf::<T, I>::Currency((10 * n as u64).into())));
+	}
+
+	claim_withdrawal {
+		let caller: T::AccountId = whitelisted_caller();
+		let user: T::AccountId = account("user", 0, SEED);
+		let user_lookup = T::Lookup::unlookup(user.clone());
+		let asset_value = AssetValueOf::<T, I>::Fungible(T::Helper::asset(11), 10u32.into());
+
+		// The pallet account holds the withdrawn assets.
+		<<T as Config<I>>::Fungibles as fungibles::Mutate<T::AccountId>>::mint_into(T::Helper::asset(11), &OpenRollup::<T, I>::account_id(), 100u32.into()).unwrap();
+		OpenRollup::<T, I>::add_pending_withdrawal(&user, &asset_value).unwrap();
+	}: _(SystemOrigin::Signed(caller), user_lookup, asset_value.clone())
+	verify {
+		assert_last_event::<T, I>(Event::WithdrawalClaimed(user, asset_value).into());
+	}
+
+	check_liveness {
+		let (caller, caller_lookup) = register_default_zkapp::<T, I>();
+		let last_batch_at = LastBatchAt::<T, I>::get::<ZkappId>(Default::default()).unwrap();
+		let now = last_batch_at.saturating_add(T::MaxBatchInterval::get()).saturating_add(1u32.into());
+	}: {
+		OpenRollup::<T, I>::check_zkapp_liveness(now, Default::default(), last_batch_at);
+	}
+	verify {
+		assert_last_event::<T, I>(Event::ForcedInactive(Default::default(), InactiveReason::BatchTimeout).into());
 	}
 
 
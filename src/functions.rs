@@ -23,6 +23,16 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		T::PalletId::get().into_account_truncating()
 	}
 
+	/// Whether the storage of the releases before the storage version exists, `Zkapps`,
+	/// `ZkappsAccounts` and `ZkappsExit` keyed by the program hash.
+	pub fn has_unversioned_storage() -> bool {
+		let pallet = <Self as PalletInfoAccess>::name().as_bytes();
+		[&b"Zkapps"[..], b"ZkappsAccounts", b"ZkappsExit"].iter().any(|name| {
+			let prefix = frame_support::storage::storage_prefix(pallet, name);
+			frame_support::storage::unhashed::contains_prefixed_key(&prefix)
+		})
+	}
+
 	/// Transfer asset from user to pallet account.
 	pub fn user_deposit(user: T::AccountId, asset_value: AssetValueOf<T, I>) -> DispatchResult {
 		let account_id = Self::account_id();
@@ -69,160 +79,958 @@ impl<T: Config<I>, I: 'static> Pallet<T, I> {
 		Ok(())
 	}
 
-	/// Add user's asset balance in a zkapp.
-	pub fn add_user_asset(
-		account: &mut AccountOf<T, I>,
-		asset_value: &AssetValueOf<T, I>,
-	) -> Result<(), Error<T, I>> {
-		let dest_asset: AssetOf<T, I> = <AssetOf<T, I>>::from(asset_value.clone());
-		let mut has_asset = false;
-		for exist_asset_value in &mut account.assets {
-			let asset = Asset::from(exist_asset_value.clone());
-			if asset == dest_asset {
-				match exist_asset_value {
-					AssetValue::Currency(ref mut value) => {
-						if let AssetValue::Currency(add_value) = asset_value {
-							*value += *add_value;
-						}
-					},
-					AssetValue::Fungible(_, ref mut value) => {
-						if let AssetValue::Fungible(_, add_value) = asset_value {
-							*value += *add_value;
+	/// Push an operation into the L1 operations queue of a zkapp, and return its index.
+	pub fn push_l1_operation(
+		zkapp_id: ZkappId,
+		operation: OperationOf<T, I>,
+	) -> Result<u64, Error<T, I>> {
+		let mut queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		ensure!(
+			queue.len() < u64::from(T::L1OperationLimit::get()),
+			Error::<T, I>::L1OperationLimitExceed
+		);
+
+		let hash = chain_l1_operation(&Self::l1_operations_hash_at(zkapp_id, queue), &operation);
+		let queued_at = frame_system::Pallet::<T>::block_number();
+		L1Operations::<T, I>::insert(
+			zkapp_id,
+			queue.tail,
+			QueuedL1Operation { operation, queued_at, hash },
+		);
+		let index = queue.tail;
+		queue.tail += 1;
+		L1OperationsQueue::<T, I>::insert(zkapp_id, queue);
+
+		Ok(index)
+	}
+
+	/// Push an operation of `user` into the L1 operations queue of a zkapp, and charge the fee
+	/// of the zkapp's `FeePolicies` from `user`.
+	pub fn push_user_l1_operation(
+		zkapp_id: ZkappId,
+		user: &T::AccountId,
+		operation: OperationOf<T, I>,
+	) -> Result<u64, DispatchError> {
+		let fee =
+			FeePolicies::<T, I>::get(zkapp_id).and_then(|policy| policy.fee(&operation).cloned());
+		let index = Self::push_l1_operation(zkapp_id, operation)?;
+		if let Some(fee) = fee {
+			Self::user_deposit(user.clone(), fee.clone())?;
+			L1OperationFees::<T, I>::insert(zkapp_id, index, fee);
+		}
+		Ok(index)
+	}
+
+	/// Pay the fees of the first `count` operations in the L1 operations queue of a zkapp to
+	/// the submitter, and the protocol's cut to `FeeDestination`.
+	///
+	/// The fees are credited to their pending withdrawals, and transferred by
+	/// `claim_withdrawal`, so a recipient can't block the batch.
+	pub fn pay_l1_operation_fees(
+		zkapp_id: ZkappId,
+		submitter: &T::AccountId,
+		count: u32,
+	) -> DispatchResult {
+		let queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		let end = queue.head.saturating_add(count.into()).min(queue.tail);
+		let cut = T::ProtocolFeeCut::get();
+		for index in queue.head..end {
+			let (protocol_fee, submitter_fee) = match L1OperationFees::<T, I>::take(zkapp_id, index)
+			{
+				Some(AssetValue::Currency(value)) => {
+					let protocol_fee = cut.mul_floor(value);
+					(
+						AssetValue::Currency(protocol_fee),
+						AssetValue::Currency(value.saturating_sub(protocol_fee)),
+					)
+				},
+				Some(AssetValue::Fungible(asset_id, value)) => {
+					let protocol_fee = cut.mul_floor(value);
+					(
+						AssetValue::Fungible(asset_id, protocol_fee),
+						AssetValue::Fungible(asset_id, value.saturating_sub(protocol_fee)),
+					)
+				},
+				// the fee policies have no nonfungible fees
+				_ => continue,
+			};
+			Self::add_pending_withdrawal(&T::FeeDestination::get(), &protocol_fee)?;
+			Self::add_pending_withdrawal(submitter, &submitter_fee)?;
+		}
+		Ok(())
+	}
+
+	/// Remove the first `count` operations from the L1 operations queue of a zkapp.
+	pub fn pop_l1_operations(zkapp_id: ZkappId, count: u32) {
+		let mut queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		let end = queue.head.saturating_add(count.into()).min(queue.tail);
+		for index in queue.head..end {
+			if let Some(queued) = L1Operations::<T, I>::take(zkapp_id, index) {
+				L1OperationsConsumedHash::<T, I>::insert(zkapp_id, queued.hash);
+			}
+		}
+		queue.head = end;
+		L1OperationsQueue::<T, I>::insert(zkapp_id, queue);
+	}
+
+	/// Propose an upgrade of a zkapp to the program of `program_hash` and `zkvm_type` with
+	/// `verifying_key`, which can be enacted after `UpgradeDelay`.
+	pub fn schedule_upgrade(
+		zkapp_id: ZkappId,
+		program_hash: T::ProgramHash,
+		zkvm_type: ZkvmType,
+		verifying_key: Option<BoundedVec<u8, T::MaxVerifyingKeyLen>>,
+	) {
+		let enact_at =
+			frame_system::Pallet::<T>::block_number().saturating_add(T::UpgradeDelay::get());
+		PendingUpgrades::<T, I>::insert(
+			zkapp_id,
+			PendingUpgrade { program_hash, zkvm_type: zkvm_type.clone(), verifying_key, enact_at },
+		);
+		Self::deposit_event(Event::UpgradeProposed(zkapp_id, program_hash, zkvm_type, enact_at));
+	}
+
+	/// Push a forced withdrawal into the forced withdrawals queue of a zkapp.
+	pub fn push_forced_withdrawal(zkapp_id: ZkappId, forced: ForcedWithdrawalOf<T, I>) {
+		let mut queue = ForcedWithdrawalsQueue::<T, I>::get(zkapp_id);
+		ForcedWithdrawals::<T, I>::insert(zkapp_id, queue.tail, forced);
+		queue.tail = queue.tail.saturating_add(1);
+		ForcedWithdrawalsQueue::<T, I>::insert(zkapp_id, queue);
+	}
+
+	/// Remove the forced withdrawals of a zkapp whose operations are before the index
+	/// `consumed` of the L1 operations queue.
+	pub fn pop_forced_withdrawals(zkapp_id: ZkappId, consumed: u64) {
+		let mut queue = ForcedWithdrawalsQueue::<T, I>::get(zkapp_id);
+		while let Some(forced) = ForcedWithdrawals::<T, I>::get(zkapp_id, queue.head) {
+			if forced.position >= consumed {
+				break
+			}
+			ForcedWithdrawals::<T, I>::remove(zkapp_id, queue.head);
+			queue.head = queue.head.saturating_add(1);
+		}
+		ForcedWithdrawalsQueue::<T, I>::insert(zkapp_id, queue);
+	}
+
+	/// Whether a forced withdrawal of a zkapp past its deadline is not before the index
+	/// `consumed` of the L1 operations queue.
+	///
+	/// The deadlines are in the order of the queue, so only the first one is checked.
+	pub fn missed_forced_withdrawal(zkapp_id: ZkappId, consumed: u64) -> bool {
+		let queue = ForcedWithdrawalsQueue::<T, I>::get(zkapp_id);
+		ForcedWithdrawals::<T, I>::get(zkapp_id, queue.head).map_or(false, |forced| {
+			forced.position >= consumed &&
+				forced.deadline < frame_system::Pallet::<T>::block_number()
+		})
+	}
+
+	/// The hash chain of the first `pos` L1 operations in the queue of a zkapp, including the
+	/// ones consumed by the previous batches, for `OpenRollupApi::l1_operations_hash`.
+	///
+	/// `None` if the queue has less than `pos` L1 operations.
+	pub fn l1_operations_hash(zkapp_id: ZkappId, pos: u32) -> Option<L1OperationsHash> {
+		let queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		let tail = queue.head.saturating_add(pos.into());
+		(tail <= queue.tail)
+			.then(|| Self::l1_operations_hash_at(zkapp_id, QueueIndices { head: queue.head, tail }))
+	}
+
+	/// The hash chain of the L1 operations before `queue.tail`.
+	fn l1_operations_hash_at(zkapp_id: ZkappId, queue: QueueIndices) -> L1OperationsHash {
+		if queue.is_empty() {
+			L1OperationsConsumedHash::<T, I>::get(zkapp_id)
+		} else {
+			L1Operations::<T, I>::get(zkapp_id, queue.tail - 1)
+				.map(|queued| queued.hash)
+				.unwrap_or_default()
+		}
+	}
+
+	/// Verify a batch of a zkapp and record its new state root, for `submit_batch` and
+	/// `commit_batch`.
+	///
+	/// The fees of the L1 operations consumed by the batch are paid, and the operations of the
+	/// batch are returned to execute. The L1 operations stay in the queue until they are
+	/// executed, see `execute_operations`.
+	///
+	/// Returns `None` without verifying the batch if it misses a forced withdrawal past the
+	/// deadline, the zkapp is forced inactive instead.
+	#[allow(clippy::too_many_arguments)]
+	pub fn commit(
+		submitter: T::AccountId,
+		zkapp_id: ZkappId,
+		old_state_root: StateRootOf<T, I>,
+		new_state_root: StateRootOf<T, I>,
+		l1_operations_pos: u32,
+		operations: Vec<OperationOf<T, I>>,
+		zk_proof: Vec<u8>,
+		zk_outputs: Option<Vec<u8>>,
+	) -> Result<Option<Vec<OperationOf<T, I>>>, DispatchError> {
+		let mut zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+		ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+		ensure!(zkapp.submitters.contains(&submitter), Error::<T, I>::NotSubmitter);
+		ensure!(
+			SubmitterBonds::<T, I>::contains_key(zkapp_id, &submitter),
+			Error::<T, I>::NotBonded
+		);
+		ensure!(
+			Self::slot_leader(zkapp_id, &zkapp.submitters)
+				.map_or(true, |leader| leader == submitter),
+			Error::<T, I>::NotSlotLeader
+		);
+		ensure!(zkapp.state_root == old_state_root, Error::<T, I>::InvalidStateRoot);
+		ensure!(!PendingBatches::<T, I>::contains_key(zkapp_id), Error::<T, I>::BatchPending);
+
+		// the batch after the deadline of a forced withdrawal must include it
+		let queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		let consumed = queue.head.saturating_add(l1_operations_pos.into());
+		if Self::missed_forced_withdrawal(zkapp_id, consumed) {
+			Self::force_inactive(zkapp_id, InactiveReason::ForcedWithdrawalMissed);
+			return Ok(None)
+		}
+
+		// the program of the zkapp must handle the l1_operations queue
+		// and the front operations should match the l1_operations queue
+		ensure!(
+			queue.len() >= u64::from(l1_operations_pos) &&
+				operations.len() >= l1_operations_pos as usize,
+			Error::<T, I>::InvalidBatchParams
+		);
+		for (index, op) in (queue.head..).zip(&operations[..l1_operations_pos as usize]) {
+			ensure!(
+				L1Operations::<T, I>::get(zkapp_id, index)
+					.map_or(false, |queued| queued.operation == *op),
+				Error::<T, I>::InvalidBatchParams
+			);
+		}
+
+		// verify the zk proof, the outputs must commit to the batch submitted
+		let zk_outputs = zk_outputs.unwrap_or_default();
+		let verifying_key = VerifyingKeys::<T, I>::get(zkapp_id).unwrap_or_default();
+		let proof_output =
+			ProofOutput { operations, state_root: new_state_root, l1_operations_pos };
+		let output_commitment = proof_output.commitment();
+		let zk_inputs = PublicInputs {
+			old_state_root: old_state_root.as_ref(),
+			l1_operations_commitment: Self::l1_operations_hash(zkapp_id, l1_operations_pos)
+				.ok_or(Error::<T, I>::InvalidBatchParams)?,
+			block_number: Self::last_batch_at(zkapp_id).unwrap_or_default(),
+		};
+
+		T::Verifiers::verify(
+			&zkapp.zkvm_type,
+			zkapp.program_hash.as_ref(),
+			&verifying_key,
+			&zk_inputs,
+			&zk_proof,
+			&zk_outputs,
+			&output_commitment,
+		)
+		.map_err(|e| match e {
+			VerifyError::NoVerifyingKey => Error::<T, I>::NoVerifyingKey,
+			_ => Error::<T, I>::InvalidProof,
+		})?;
+		let operations = proof_output.operations;
+
+		// pay the fees of the l1_operations included in the batch
+		Self::pay_l1_operation_fees(zkapp_id, &submitter, l1_operations_pos)?;
+		Self::pop_forced_withdrawals(zkapp_id, consumed);
+
+		// save zkapp's new_state_root
+		zkapp.state_root = new_state_root;
+		Zkapps::<T, I>::insert(zkapp_id, zkapp);
+		LastBatchAt::<T, I>::insert(zkapp_id, frame_system::Pallet::<T>::block_number());
+
+		Ok(Some(operations))
+	}
+
+	/// Execute the operations of a batch, `from` is the index of the first one in the batch.
+	///
+	/// The L1 operations executed are removed from the queue, so the ones of a batch abandoned
+	/// before they are executed stay queued, and are refunded when users exit.
+	///
+	/// An L1 `Withdraw` or `Move` of assets the user doesn't hold any more, or a `Move` to a zkapp
+	/// that can't receive them any more, is skipped instead of failing the batch, the zkapp's
+	/// program must treat it as a no-op too.
+	pub fn execute_operations(
+		zkapp_id: ZkappId,
+		from: u32,
+		l1_operations_pos: u32,
+		operations: &[OperationOf<T, I>],
+	) -> DispatchResult {
+		for (i, op) in (from as usize..).zip(operations) {
+			match op {
+				Operation::Deposit(user, asset_value) => {
+					// only execution of L1 Deposit transaction can Deposit Operation
+					ensure!(i < l1_operations_pos as usize, Error::<T, I>::InvalidBatchParams);
+
+					Self::add_user_asset(zkapp_id, user, asset_value)?;
+				},
+				Operation::Withdraw(user, asset_value) => {
+					if Self::skip_underfunded(zkapp_id, i < l1_operations_pos as usize, op) {
+						continue
+					}
+					Self::reduce_user_asset(zkapp_id, user, asset_value)?;
+					Self::add_pending_withdrawal(user, asset_value)?;
+				},
+				Operation::Move(user, to_zkapp_id, asset_value) => {
+					ensure!(*to_zkapp_id != zkapp_id, Error::<T, I>::SameZkapp);
+					let is_l1 = i < l1_operations_pos as usize;
+					if Self::skip_underfunded(zkapp_id, is_l1, op) {
+						continue
+					}
+
+					// the destination must be able to credit the assets, as `move_asset`; an L1
+					// move is skipped if the destination changed since it was queued
+					let can_receive = Self::ensure_can_receive(*to_zkapp_id, asset_value);
+					if is_l1 && can_receive.is_err() {
+						Self::deposit_event(Event::L1OperationSkipped(zkapp_id, op.clone()));
+						continue
+					}
+					can_receive?;
+
+					// reduce user asset_value
+					Self::reduce_user_asset(zkapp_id, user, asset_value)?;
+
+					// add deposit L1 operation and deposit event to to_program
+					Self::push_l1_operation(
+						*to_zkapp_id,
+						Operation::Deposit(user.clone(), asset_value.clone()),
+					)?;
+					Self::deposit_event(Event::Deposited(
+						*to_zkapp_id,
+						user.clone(),
+						asset_value.clone(),
+					));
+				},
+				Operation::Message(from_zkapp_id, to_zkapp_id, payload, assets) => {
+					ensure!(from_zkapp_id != to_zkapp_id, Error::<T, I>::SameZkapp);
+
+					if *to_zkapp_id == zkapp_id {
+						// an inbound message is queued by the batch of the sender zkapp
+						ensure!(i < l1_operations_pos as usize, Error::<T, I>::InvalidBatchParams);
+
+						if let Some((user, asset_value)) = assets {
+							Self::add_user_asset(zkapp_id, user, asset_value)?;
 						}
-					},
-					AssetValue::Nonfungible(_, ref mut items) => {
-						if let AssetValue::Nonfungible(_, add_items) = asset_value {
-							for item_id in add_items {
-								if !items.contains(item_id) {
-									items
-										.try_push(*item_id)
-										.map_err(|_| Error::<T, I>::InvalidAssets)?;
-								}
-							}
+					} else {
+						ensure!(*from_zkapp_id == zkapp_id, Error::<T, I>::InvalidBatchParams);
+						let to_zkapp = Zkapps::<T, I>::try_get(to_zkapp_id)
+							.map_err(|_| Error::<T, I>::NoProgram)?;
+						ensure!(!to_zkapp.is_inactive, Error::<T, I>::Inactive);
+
+						if let Some((user, asset_value)) = assets {
+							// the destination must be able to credit the assets, as `move_asset`
+							ensure!(
+								to_zkapp.supported_assets.contains(&asset_value.clone().into()),
+								Error::<T, I>::NotSupportAsset
+							);
+							Self::reduce_user_asset(zkapp_id, user, asset_value)?;
 						}
+						Self::push_l1_operation(*to_zkapp_id, op.clone())?;
+						Self::deposit_event(Event::MessageSent(
+							zkapp_id,
+							*to_zkapp_id,
+							payload.clone(),
+						));
+					}
+				},
+				Operation::Transfer(from_user, to_user, asset_value) => {
+					Self::reduce_user_asset(zkapp_id, from_user, asset_value)?;
+					Self::add_user_asset(zkapp_id, to_user, asset_value)?;
+				},
+				Operation::Swap(user_1, asset_value_1, user_2, asset_value_2) => {
+					// modify user_1 assets
+					Self::reduce_user_asset(zkapp_id, user_1, asset_value_1)?;
+					Self::add_user_asset(zkapp_id, user_1, asset_value_2)?;
+
+					// modify user_2 assets
+					Self::reduce_user_asset(zkapp_id, user_2, asset_value_2)?;
+					Self::add_user_asset(zkapp_id, user_2, asset_value_1)?;
+				},
+			}
+		}
+
+		let end = from.saturating_add(operations.len().saturated_into());
+		let executed_l1 = |index: u32| index.min(l1_operations_pos);
+		Self::pop_l1_operations(zkapp_id, executed_l1(end).saturating_sub(executed_l1(from)));
+		Ok(())
+	}
+
+	/// Whether an L1 `Withdraw` or `Move` operation is skipped, as the user doesn't hold the
+	/// assets any more, e.g. two withdrawals queued exceed the balance together.
+	fn skip_underfunded(zkapp_id: ZkappId, is_l1: bool, op: &OperationOf<T, I>) -> bool {
+		let (user, asset_value) = match op {
+			Operation::Withdraw(user, asset_value) | Operation::Move(user, _, asset_value) =>
+				(user, asset_value),
+			_ => return false,
+		};
+		if !is_l1 || Self::check_has_enough_asset(zkapp_id, user, asset_value) {
+			return false
+		}
+		Self::deposit_event(Event::L1OperationSkipped(zkapp_id, op.clone()));
+		true
+	}
+
+	/// Check the liveness of zkapps after `LivenessCursor`, as many as `remaining_weight` allows.
+	///
+	/// Returns the weight consumed.
+	pub fn check_liveness(now: T::BlockNumber, remaining_weight: Weight) -> Weight {
+		let check_weight = T::WeightInfo::check_liveness();
+		let mut consumed_weight = T::DbWeight::get().reads_writes(1, 1);
+		if remaining_weight.any_lt(consumed_weight.saturating_add(check_weight)) {
+			return Weight::zero()
+		}
+
+		let mut zkapps = match LivenessCursor::<T, I>::get() {
+			Some(cursor) =>
+				LastBatchAt::<T, I>::iter_from(LastBatchAt::<T, I>::hashed_key_for(cursor)),
+			None => LastBatchAt::<T, I>::iter(),
+		};
+		let mut cursor = None;
+		while !remaining_weight.any_lt(consumed_weight.saturating_add(check_weight)) {
+			match zkapps.next() {
+				Some((zkapp_id, last_batch_at)) => {
+					Self::check_zkapp_liveness(now, zkapp_id, last_batch_at);
+					consumed_weight = consumed_weight.saturating_add(check_weight);
+					cursor = Some(zkapp_id);
+				},
+				None => {
+					// all zkapps checked, start from the first one next time.
+					cursor = None;
+					break
+				},
+			}
+		}
+
+		match cursor {
+			Some(cursor) => LivenessCursor::<T, I>::put(cursor),
+			None => LivenessCursor::<T, I>::kill(),
+		}
+		consumed_weight
+	}
+
+	/// Check the liveness of one zkapp, set it inactive if the submitter stalls.
+	///
+	/// The submitter stalls if the last batch is older than `MaxBatchInterval`, or the oldest L1
+	/// operation is older than `MaxL1OperationAge`.
+	pub fn check_zkapp_liveness(
+		now: T::BlockNumber,
+		zkapp_id: ZkappId,
+		last_batch_at: T::BlockNumber,
+	) {
+		if let Some(reason) = Self::stall_reason(now, zkapp_id, last_batch_at) {
+			Self::force_inactive(zkapp_id, reason);
+		}
+	}
+
+	/// The reason to force a zkapp inactive if its submitters stall at `now`, `None` if not.
+	pub fn stall_reason(
+		now: T::BlockNumber,
+		zkapp_id: ZkappId,
+		last_batch_at: T::BlockNumber,
+	) -> Option<InactiveReason> {
+		if now.saturating_sub(last_batch_at) > T::MaxBatchInterval::get() {
+			Some(InactiveReason::BatchTimeout)
+		} else if L1Operations::<T, I>::get(zkapp_id, L1OperationsQueue::<T, I>::get(zkapp_id).head)
+			.map_or(false, |queued| {
+				now.saturating_sub(queued.queued_at) > T::MaxL1OperationAge::get()
+			}) {
+			Some(InactiveReason::L1OperationTimeout)
+		} else {
+			None
+		}
+	}
+
+	/// Ensure the submitters of a zkapp don't stall, so their bonds are not unbonded once they
+	/// should be slashed by the liveness watchdog.
+	pub fn ensure_not_stalled(zkapp_id: ZkappId) -> DispatchResult {
+		if let Some(last_batch_at) = LastBatchAt::<T, I>::get(zkapp_id) {
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				Self::stall_reason(now, zkapp_id, last_batch_at).is_none(),
+				Error::<T, I>::Stalled
+			);
+		}
+		Ok(())
+	}
+
+	/// Returns the leader of the current slot of the rotation of a zkapp's `submitters`.
+	///
+	/// Returns `None` if any submitter can submit batches, i.e. the zkapp has no rotation or no
+	/// batch has been accepted for the `grace_period` of the rotation.
+	pub fn slot_leader(zkapp_id: ZkappId, submitters: &[T::AccountId]) -> Option<T::AccountId> {
+		let rotation = SubmitterRotations::<T, I>::get(zkapp_id)?;
+		let now = frame_system::Pallet::<T>::block_number();
+		let last_batch_at = LastBatchAt::<T, I>::get(zkapp_id).unwrap_or_default();
+		if submitters.is_empty() || now.saturating_sub(last_batch_at) >= rotation.grace_period {
+			return None
+		}
+
+		let slot: u64 = (now / rotation.slot_length).saturated_into();
+		let index = slot % submitters.len() as u64;
+		submitters.get(index as usize).cloned()
+	}
+
+	/// Force a zkapp inactive and slash its submitters, for the `reason`.
+	///
+	/// The former submitters whose bonds are still unbonding are slashed too.
+	pub fn force_inactive(zkapp_id: ZkappId, reason: InactiveReason) {
+		let mut zkapp = match Zkapps::<T, I>::get(zkapp_id) {
+			Some(zkapp) if !zkapp.is_inactive => zkapp,
+			_ => return,
+		};
+		zkapp.is_inactive = true;
+		let unbonding: Vec<T::AccountId> = Unbondings::<T, I>::iter_key_prefix(zkapp_id).collect();
+		for submitter in zkapp.submitters.iter().chain(unbonding.iter()) {
+			Self::slash_submitter(zkapp_id, submitter);
+		}
+		Zkapps::<T, I>::insert(zkapp_id, zkapp);
+		Self::stop_batches(zkapp_id);
+
+		Self::deposit_event(Event::ForcedInactive(zkapp_id, reason));
+	}
+
+	/// Stop the batches of a zkapp set inactive, the liveness watchdog stops checking it and its
+	/// pending batch is abandoned.
+	pub fn stop_batches(zkapp_id: ZkappId) {
+		LastBatchAt::<T, I>::remove(zkapp_id);
+		Self::abandon_pending_batch(zkapp_id);
+	}
+
+	/// Remove the pending batch of a zkapp set inactive, its operations not executed yet are
+	/// never executed, so users can exit with the balances executed so far.
+	pub fn abandon_pending_batch(zkapp_id: ZkappId) {
+		if let Some(batch) = PendingBatches::<T, I>::take(zkapp_id) {
+			Self::deposit_event(Event::BatchAbandoned(zkapp_id, batch.next, batch.len));
+		}
+	}
+
+	/// Remove the metadata of a zkapp, and unreserve its deposit.
+	pub fn remove_metadata(zkapp_id: ZkappId) -> DispatchResult {
+		let metadata = Metadata::<T, I>::take(zkapp_id).ok_or(Error::<T, I>::NoMetadata)?;
+		T::Currency::unreserve(&metadata.depositor, metadata.deposit);
+
+		Self::deposit_event(Event::MetadataCleared(zkapp_id));
+		Ok(())
+	}
+
+	/// Start unbonding the bonds of a zkapp's `submitters`.
+	pub fn unbond_submitters(zkapp_id: ZkappId, submitters: &[T::AccountId]) {
+		for submitter in submitters {
+			Self::unbond_submitter(zkapp_id, submitter);
+		}
+	}
+
+	/// Start unbonding the bond of `who` as a submitter of a zkapp.
+	pub fn unbond_submitter(zkapp_id: ZkappId, who: &T::AccountId) {
+		if let Some(bond) = SubmitterBonds::<T, I>::take(zkapp_id, who) {
+			let unlock_at =
+				frame_system::Pallet::<T>::block_number().saturating_add(T::UnbondingPeriod::get());
+			Unbondings::<T, I>::mutate(zkapp_id, bond.who, |unbonding| {
+				let amount = unbonding
+					.as_ref()
+					.map_or(bond.amount, |unbonding| unbonding.amount.saturating_add(bond.amount));
+				*unbonding = Some(Unbonding { amount, unlock_at });
+			});
+		}
+	}
+
+	/// Slash the bonds of `who` as a submitter of a zkapp, including the bond being unbonded.
+	///
+	/// It's called when the zkapp is forced inactive. Fraud proofs are out of scope of this
+	/// pallet, other pallets proving a fraud of the submitter can call it. The slashed amount
+	/// is handled by `Slashed`.
+	///
+	/// Returns the amount slashed.
+	pub fn slash_submitter(zkapp_id: ZkappId, who: &T::AccountId) -> CurrencyBalanceOf<T, I> {
+		let mut amount = Zero::zero();
+		if let Some(bond) = SubmitterBonds::<T, I>::take(zkapp_id, who) {
+			amount = bond.amount;
+		}
+		if let Some(unbonding) = Unbondings::<T, I>::take(zkapp_id, who) {
+			amount = amount.saturating_add(unbonding.amount);
+		}
+		if amount.is_zero() {
+			return amount
+		}
+
+		let (imbalance, _) = T::Currency::slash_reserved(who, amount);
+		let slashed = imbalance.peek();
+		T::Slashed::on_unbalanced(imbalance);
+
+		Self::deposit_event(Event::SubmitterSlashed(zkapp_id, who.clone(), slashed));
+		slashed
+	}
+
+	/// Transfer the assets of user's `Deposit` operations (and the assets of user moved along the
+	/// inbound `Message` operations) in L1 operations queue to user, with the fees of user's
+	/// operations.
+	///
+	/// At most `L1OperationsPageSize` operations from the index `from` are visited, if there are
+	/// more, the index of the next one is saved in `ExitRefundCursors`.
+	pub fn refund_l1_deposits_from(
+		user: &T::AccountId,
+		zkapp_id: ZkappId,
+		from: u64,
+	) -> DispatchResult {
+		let queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		let from = from.max(queue.head);
+		let next = from.saturating_add(T::L1OperationsPageSize::get().into()).min(queue.tail);
+		for index in from..next {
+			let operation = match L1Operations::<T, I>::get(zkapp_id, index) {
+				Some(queued) => queued.operation,
+				None => continue,
+			};
+			// the fees of user's operations
+			match &operation {
+				Operation::Deposit(op_user, _) |
+				Operation::Withdraw(op_user, _) |
+				Operation::Move(op_user, ..)
+					if op_user == user =>
+					if let Some(fee) = L1OperationFees::<T, I>::take(zkapp_id, index) {
+						Self::user_withdraw(user.clone(), fee)?;
 					},
-				}
-				has_asset = true;
-				break
+				_ => (),
+			}
+			match operation {
+				Operation::Deposit(op_user, asset_value) |
+				Operation::Message(_, _, _, Some((op_user, asset_value)))
+					if op_user == *user =>
+				{
+					Self::user_withdraw(user.clone(), asset_value)?;
+				},
+				_ => (),
 			}
 		}
-		if !has_asset {
-			account
-				.assets
-				.try_push(asset_value.clone())
-				.map_err(|_| Error::<T, I>::InvalidAssets)?;
+
+		if next < queue.tail {
+			ExitRefundCursors::<T, I>::insert(zkapp_id, user.clone(), next);
+		} else {
+			ExitRefundCursors::<T, I>::remove(zkapp_id, user.clone());
 		}
 
+		Self::deposit_event(Event::L1DepositsRefunded(zkapp_id, user.clone(), next));
 		Ok(())
 	}
 
-	/// Reduce user's asset balance in a zkapp.
-	pub fn reduce_user_asset(
-		account: &mut AccountOf<T, I>,
+	/// Add user's asset balance in a zkapp.
+	pub fn add_user_asset(
+		zkapp_id: ZkappId,
+		user: &T::AccountId,
 		asset_value: &AssetValueOf<T, I>,
 	) -> Result<(), Error<T, I>> {
-		let dest_asset: AssetOf<T, I> = <AssetOf<T, I>>::from(asset_value.clone());
-		let mut has_asset = false;
-		for exist_asset_value in &mut account.assets {
-			let asset = Asset::from(exist_asset_value.clone());
-			if asset == dest_asset {
-				match exist_asset_value {
-					AssetValue::Currency(ref mut value) => {
-						if let AssetValue::Currency(reduce_value) = asset_value {
-							if reduce_value > value {
-								return Err(Error::<T, I>::InvalidAssets)
-							}
-							*value -= *reduce_value;
-						}
-					},
-					AssetValue::Fungible(_, ref mut value) => {
-						if let AssetValue::Fungible(_, reduce_value) = asset_value {
-							if reduce_value > value {
-								return Err(Error::<T, I>::InvalidAssets)
-							}
-							*value -= *reduce_value;
-						}
-					},
-					AssetValue::Nonfungible(_, ref mut items) => {
-						if let AssetValue::Nonfungible(_, reduce_items) = asset_value {
-							for item_id in reduce_items {
-								if !items.contains(item_id) {
-									return Err(Error::<T, I>::InvalidAssets)
-								}
-							}
-							items.retain(|item_id| !reduce_items.contains(item_id));
+		match asset_value {
+			AssetValue::Nonfungible(collection_id, add_items) => {
+				ZkappsItems::<T, I>::try_mutate((zkapp_id, user, collection_id), |items| {
+					let items = items.get_or_insert_with(Default::default);
+					for item_id in add_items {
+						if !items.contains(item_id) {
+							items.try_push(*item_id).map_err(|_| Error::<T, I>::InvalidAssets)?;
 						}
-					},
+					}
+					Ok::<_, Error<T, I>>(())
+				})?;
+				for item_id in add_items {
+					ZkappsCustodyItems::<T, I>::insert((zkapp_id, collection_id, item_id), ());
 				}
-				has_asset = true;
-				break
-			}
+				Ok(())
+			},
+			_ => {
+				let (asset, amount) = Self::split_balance(asset_value)?;
+				ZkappsBalances::<T, I>::try_mutate((zkapp_id, user, asset.clone()), |balance| {
+					*balance = balance.checked_add(amount).ok_or(Error::<T, I>::InvalidAssets)?;
+					Ok::<_, Error<T, I>>(())
+				})?;
+				ZkappsCustody::<T, I>::try_mutate(zkapp_id, asset, |custody| {
+					*custody = custody.checked_add(amount).ok_or(Error::<T, I>::InvalidAssets)?;
+					Ok(())
+				})
+			},
 		}
+	}
 
-		if !has_asset {
-			return Err(Error::<T, I>::InvalidAssets)
+	/// Reduce user's asset balance in a zkapp.
+	pub fn reduce_user_asset(
+		zkapp_id: ZkappId,
+		user: &T::AccountId,
+		asset_value: &AssetValueOf<T, I>,
+	) -> Result<(), Error<T, I>> {
+		match asset_value {
+			AssetValue::Nonfungible(collection_id, reduce_items) =>
+				ZkappsItems::<T, I>::try_mutate_exists((zkapp_id, user, collection_id), |items| {
+					let remaining = items.as_mut().ok_or(Error::<T, I>::InvalidAssets)?;
+					ensure!(
+						reduce_items.iter().all(|item_id| remaining.contains(item_id)),
+						Error::<T, I>::InvalidAssets
+					);
+					remaining.retain(|item_id| !reduce_items.contains(item_id));
+					if remaining.is_empty() {
+						*items = None;
+					}
+					Ok::<_, Error<T, I>>(())
+				})?,
+			_ => {
+				let (asset, amount) = Self::split_balance(asset_value)?;
+				ZkappsBalances::<T, I>::try_mutate_exists((zkapp_id, user, asset), |balance| {
+					let remaining = balance
+						.unwrap_or_default()
+						.checked_sub(amount)
+						.ok_or(Error::<T, I>::InvalidAssets)?;
+					*balance = Some(remaining).filter(|remaining| *remaining > 0);
+					Ok::<_, Error<T, I>>(())
+				})?;
+			},
 		}
+		Self::reduce_custody(zkapp_id, asset_value).map_err(|_| Error::<T, I>::InvalidAssets)
+	}
 
+	/// Reduce the assets held by the pallet for a zkapp's users, when they leave the zkapp's
+	/// balances or are paid to an exiting user.
+	pub fn reduce_custody(
+		zkapp_id: ZkappId,
+		asset_value: &AssetValueOf<T, I>,
+	) -> Result<(), Error<T, I>> {
+		match asset_value {
+			AssetValue::Nonfungible(collection_id, item_ids) => {
+				ensure!(
+					item_ids.iter().all(|item_id| {
+						ZkappsCustodyItems::<T, I>::contains_key((zkapp_id, collection_id, item_id))
+					}),
+					Error::<T, I>::NoEnoughCustody
+				);
+				for item_id in item_ids {
+					ZkappsCustodyItems::<T, I>::remove((zkapp_id, collection_id, item_id));
+				}
+				Ok(())
+			},
+			_ => {
+				let (asset, amount) = Self::split_balance(asset_value)?;
+				ZkappsCustody::<T, I>::try_mutate_exists(zkapp_id, asset, |custody| {
+					let remaining = custody
+						.unwrap_or_default()
+						.checked_sub(amount)
+						.ok_or(Error::<T, I>::NoEnoughCustody)?;
+					*custody = Some(remaining).filter(|remaining| *remaining > 0);
+					Ok(())
+				})
+			},
+		}
+	}
+
+	/// Check the zkapp `to_zkapp_id` is active and supports the asset moved to it.
+	pub fn ensure_can_receive(
+		to_zkapp_id: ZkappId,
+		asset_value: &AssetValueOf<T, I>,
+	) -> Result<(), Error<T, I>> {
+		let to_zkapp =
+			Zkapps::<T, I>::try_get(to_zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+		ensure!(!to_zkapp.is_inactive, Error::<T, I>::Inactive);
+		ensure!(
+			to_zkapp.supported_assets.contains(&asset_value.clone().into()),
+			Error::<T, I>::NotSupportAsset
+		);
 		Ok(())
 	}
 
 	/// Check whether user has enough asset for withdraw or move.
 	pub fn check_has_enough_asset(
-		account: &AccountOf<T, I>,
+		zkapp_id: ZkappId,
+		user: &T::AccountId,
 		asset_value: &AssetValueOf<T, I>,
 	) -> bool {
-		let dest_asset: AssetOf<T, I> = <AssetOf<T, I>>::from(asset_value.clone());
-		for exist_asset_value in &account.assets {
-			let asset = Asset::from(exist_asset_value.clone());
-			if asset == dest_asset {
-				match exist_asset_value {
-					AssetValue::Currency(value) => {
-						if let AssetValue::Currency(reduce_value) = asset_value {
-							if reduce_value > value {
-								return false
-							}
-							return true
-						}
-					},
-					AssetValue::Fungible(_, value) => {
-						if let AssetValue::Fungible(_, reduce_value) = asset_value {
-							if reduce_value > value {
-								return false
-							}
-							return true
-						}
-					},
-					AssetValue::Nonfungible(_, items) => {
-						if let AssetValue::Nonfungible(_, reduce_items) = asset_value {
-							for item_id in reduce_items {
-								if !items.contains(item_id) {
-									return false
-								}
-							}
-							return true
-						}
-					},
+		match asset_value {
+			AssetValue::Nonfungible(collection_id, reduce_items) =>
+				ZkappsItems::<T, I>::get((zkapp_id, user, collection_id)).map_or(false, |items| {
+					reduce_items.iter().all(|item_id| items.contains(item_id))
+				}),
+			_ => Self::split_balance(asset_value).map_or(false, |(asset, amount)| {
+				ZkappsBalances::<T, I>::get((zkapp_id, user, asset)) >= amount
+			}),
+		}
+	}
+
+	/// Credit a withdrawn asset value to the user's pending withdrawals.
+	pub fn add_pending_withdrawal(
+		user: &T::AccountId,
+		asset_value: &AssetValueOf<T, I>,
+	) -> Result<(), Error<T, I>> {
+		match asset_value {
+			AssetValue::Nonfungible(collection_id, item_ids) => {
+				for item_id in item_ids {
+					PendingItemWithdrawals::<T, I>::insert((user, collection_id, item_id), ());
 				}
-			}
+				Ok(())
+			},
+			_ => {
+				let (asset, amount) = Self::split_balance(asset_value)?;
+				PendingWithdrawals::<T, I>::try_mutate((user, asset), |balance| {
+					*balance = balance.checked_add(amount).ok_or(Error::<T, I>::InvalidAssets)?;
+					Ok(())
+				})
+			},
+		}
+	}
+
+	/// Reduce the user's pending withdrawals by a claimed asset value.
+	pub fn reduce_pending_withdrawal(
+		user: &T::AccountId,
+		asset_value: &AssetValueOf<T, I>,
+	) -> Result<(), Error<T, I>> {
+		match asset_value {
+			AssetValue::Nonfungible(collection_id, item_ids) => {
+				ensure!(
+					item_ids.iter().all(|item_id| {
+						PendingItemWithdrawals::<T, I>::contains_key((user, collection_id, item_id))
+					}),
+					Error::<T, I>::NoEnoughWithdrawals
+				);
+				for item_id in item_ids {
+					PendingItemWithdrawals::<T, I>::remove((user, collection_id, item_id));
+				}
+				Ok(())
+			},
+			_ => {
+				let (asset, amount) = Self::split_balance(asset_value)?;
+				PendingWithdrawals::<T, I>::try_mutate_exists((user, asset), |balance| {
+					let remaining = balance
+						.unwrap_or_default()
+						.checked_sub(amount)
+						.ok_or(Error::<T, I>::NoEnoughWithdrawals)?;
+					*balance = Some(remaining).filter(|remaining| *remaining > 0);
+					Ok(())
+				})
+			},
+		}
+	}
+
+	/// The key of `ZkappsBalances` and the amount of a currency or fungible asset value.
+	///
+	/// `InvalidAssets` for nonfungible ones, or if the amount doesn't fit in `u128`.
+	fn split_balance(
+		asset_value: &AssetValueOf<T, I>,
+	) -> Result<(AssetOf<T, I>, u128), Error<T, I>> {
+		match asset_value {
+			AssetValue::Currency(value) => (*value)
+				.try_into()
+				.map(|amount| (Asset::Currency, amount))
+				.map_err(|_| Error::<T, I>::InvalidAssets),
+			AssetValue::Fungible(asset_id, value) => (*value)
+				.try_into()
+				.map(|amount| (Asset::Fungible(*asset_id), amount))
+				.map_err(|_| Error::<T, I>::InvalidAssets),
+			AssetValue::Nonfungible(..) => Err(Error::<T, I>::InvalidAssets),
 		}
-		false
+	}
+
+	/// Collect the balances and items of one user in `ZkappsBalances` and `ZkappsItems` into
+	/// asset values.
+	///
+	/// `InvalidAssets` if a balance doesn't fit in the balance type of its asset.
+	fn asset_values(
+		balances: impl Iterator<Item = (AssetOf<T, I>, u128)>,
+		items: impl Iterator<Item = (CollectionIdOf<T, I>, ItemsOf<T, I>)>,
+	) -> Result<Vec<AssetValueOf<T, I>>, Error<T, I>> {
+		let mut asset_values = Vec::new();
+		for (asset, balance) in balances {
+			let asset_value = match asset {
+				Asset::Currency => balance.try_into().map(AssetValue::Currency),
+				Asset::Fungible(asset_id) =>
+					balance.try_into().map(|value| AssetValue::Fungible(asset_id, value)),
+				Asset::Nonfungible(_) => continue,
+			};
+			asset_values.push(asset_value.map_err(|_| Error::<T, I>::InvalidAssets)?);
+		}
+		asset_values.extend(
+			items.map(|(collection_id, items)| AssetValue::Nonfungible(collection_id, items)),
+		);
+		Ok(asset_values)
+	}
+
+	/// Remove all the assets of `user` in a zkapp, and return them.
+	///
+	/// The assets are removed even if `InvalidAssets` is returned.
+	pub fn take_user_assets(
+		zkapp_id: ZkappId,
+		user: &T::AccountId,
+	) -> Result<Vec<AssetValueOf<T, I>>, Error<T, I>> {
+		let balances: Vec<_> = ZkappsBalances::<T, I>::drain_prefix((zkapp_id, user)).collect();
+		let items: Vec<_> = ZkappsItems::<T, I>::drain_prefix((zkapp_id, user)).collect();
+		Self::asset_values(balances.into_iter(), items.into_iter())
 	}
 
 	/// Add user's asset balance in a zkapp.
 	pub fn add_zkapp_user_asset(
-		program_hash: ProgramHashOf<T, I>,
+		zkapp_id: ZkappId,
 		user: AccountIdOf<T>,
 		asset_value: &AssetValueOf<T, I>,
 	) -> Result<(), Error<T, I>> {
-		let mut account: AccountOf<T, I>;
-		if let Ok(_account) = ZkappsAccounts::<T, I>::try_get(program_hash, user.clone()) {
-			account = _account;
-		} else {
-			account = Account { user: user.clone(), assets: Default::default() };
+		Self::add_user_asset(zkapp_id, &user, asset_value)
+	}
+
+	/// The zkapp of `zkapp_id`, for `OpenRollupApi::zkapp`.
+	pub fn zkapp(zkapp_id: ZkappId) -> Option<ZkappOf<T, I>> {
+		Zkapps::<T, I>::get(zkapp_id)
+	}
+
+	/// The account of `who` in a zkapp with the `page`th `AssetsLimit` assets of `who`, for
+	/// `OpenRollupApi::account`.
+	///
+	/// A user holding more than `AssetsLimit` assets has more than one page, `None` if `who`
+	/// has no assets in the page.
+	pub fn account(zkapp_id: ZkappId, who: T::AccountId, page: u32) -> Option<AccountOf<T, I>> {
+		let limit = T::AssetsLimit::get() as usize;
+		let assets: Vec<_> = Self::asset_values(
+			ZkappsBalances::<T, I>::iter_prefix((zkapp_id, &who)),
+			ZkappsItems::<T, I>::iter_prefix((zkapp_id, &who)),
+		)
+		.ok()?
+		.into_iter()
+		.skip((page as usize).saturating_mul(limit))
+		.take(limit)
+		.collect();
+		if assets.is_empty() {
+			return None
 		}
-		Self::add_user_asset(&mut account, asset_value)?;
-		ZkappsAccounts::<T, I>::insert(program_hash, user, account);
+		BoundedVec::try_from(assets).ok().map(|assets| Account { user: who, assets })
+	}
 
-		Ok(())
+	/// At most `limit` L1 operations in the queue of a zkapp from the position `from`, for
+	/// `OpenRollupApi::pending_l1_operations`.
+	pub fn pending_l1_operations(
+		zkapp_id: ZkappId,
+		from: u32,
+		limit: u32,
+	) -> Vec<OperationOf<T, I>> {
+		let queue = L1OperationsQueue::<T, I>::get(zkapp_id);
+		(queue.head.saturating_add(from.into())..queue.tail)
+			.take(limit as usize)
+			.filter_map(|index| L1Operations::<T, I>::get(zkapp_id, index))
+			.map(|queued| queued.operation)
+			.collect()
+	}
+
+	/// Whether `who` has exited from a zkapp, for `OpenRollupApi::has_exited`.
+	pub fn has_exited(zkapp_id: ZkappId, who: T::AccountId) -> bool {
+		ZkappsExit::<T, I>::get(zkapp_id, who).unwrap_or_default()
+	}
+
+	/// The block number of the last batch of a zkapp, the `block_number` public input of its
+	/// next batch, for `OpenRollupApi::last_batch_at`.
+	///
+	/// `None` if the zkapp is not registered or inactive.
+	pub fn last_batch_at(zkapp_id: ZkappId) -> Option<u64> {
+		LastBatchAt::<T, I>::get(zkapp_id).map(|last_batch_at| last_batch_at.saturated_into())
+	}
+
+	/// The SCALE encoding and the commitment of a batch's `ProofOutput`, for
+	/// `OpenRollupApi::encode_proof_output`.
+	pub fn encode_proof_output(
+		operations: Vec<OperationOf<T, I>>,
+		state_root: StateRootOf<T, I>,
+		l1_operations_pos: u32,
+	) -> (Vec<u8>, verifier::OutputCommitment) {
+		let proof_output = ProofOutput { operations, state_root, l1_operations_pos };
+		(proof_output.encode(), proof_output.commitment())
 	}
 }
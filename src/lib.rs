@@ -26,6 +26,7 @@ pub mod weights;
 
 mod fix_nonfungible;
 mod functions;
+pub mod state_tree;
 mod types;
 pub mod verifier;
 
@@ -44,26 +45,43 @@ use frame_support::{
 			},
 			ExistenceRequirement,
 		},
-		Currency,
+		Currency, Imbalance, OnUnbalanced, PalletInfoAccess, ReservableCurrency,
 	},
 	PalletId,
 };
 
 use frame_system::{ensure_signed, pallet_prelude::*};
-use sp_runtime::traits::{AccountIdConversion, StaticLookup};
-use sp_std::vec::Vec;
+use sp_runtime::{
+	traits::{AccountIdConversion, Saturating, StaticLookup, Zero},
+	ArithmeticError, PerThing, Perbill, SaturatedConversion,
+};
+use sp_std::{vec, vec::Vec};
 
 pub use pallet::*;
+pub use state_tree::{Blake2StateTree, KeccakStateTree, RescuePrimeStateTree, StateTree};
 pub use types::*;
-pub use verifier::{FakeVerifier, MidenVerifier, Verifier};
+#[cfg(feature = "risc-zero")]
+pub use verifier::RiscZeroVerifier;
+pub use verifier::{
+	FakeVerifier, Groth16Bls12_381Verifier, Groth16Bn254Verifier, MidenVerifier, PlonkVerifier,
+	PublicInputs, Verifier, Verifiers, VerifyError,
+};
 pub use weights::WeightInfo;
 
+/// The log target of the pallet.
+const LOG_TARGET: &str = "runtime::open-rollup";
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
 
+	/// The storage layout version, chains with the storage of an older version can't upgrade to
+	/// this pallet as there are no migrations, see the README.
+	pub(super) const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T, I = ()>(_);
 
 	#[cfg(feature = "runtime-benchmarks")]
@@ -142,8 +160,8 @@ pub mod pallet {
 		#[pallet::constant]
 		type PalletId: Get<PalletId>;
 
-		/// The currency asset mechanism.
-		type Currency: Currency<Self::AccountId>;
+		/// The currency asset mechanism, also used to reserve submitters' bonds.
+		type Currency: ReservableCurrency<Self::AccountId>;
 
 		/// The fungibles assets mechanism.
 		type Fungibles: fungibles::Transfer<Self::AccountId>
@@ -156,25 +174,109 @@ pub mod pallet {
 			+ NonfungibleMutate<Self::AccountId>
 			+ NonfungibleInspect<Self::AccountId>;
 
+		/// The state tree of zkapps, used to verify users' assets when `exit_with_proof`.
+		type StateTree: StateTree;
+
 		/// Weight information for extrinsics in this pallet.
 		type WeightInfo: WeightInfo;
 
+		/// The verifiers of the proof systems enabled, e.g. `(MidenVerifier,
+		/// Groth16Bn254Verifier)`, zkapps of other zkvm types can't be registered.
+		///
+		/// `FakeVerifier` must not be included except for tests and benchmarks.
+		type Verifiers: Verifiers;
+
 		/// The maximum of the assets one zkapp supported.
 		#[pallet::constant]
 		type AssetsLimit: Get<u32>;
 
-		/// The maximum of the items one Account supported.
+		/// The maximum of the submitters of one zkapp, at least 1.
 		#[pallet::constant]
-		type AssetsItemLimit: Get<u32>;
+		type MaxSubmitters: Get<u32>;
 
-		/// The maximum of the L1 operations one zkapp supported.
+		/// The maximum number of the L1 operations queued in one zkapp.
+		///
+		/// The queue is paged in storage, so it's not limited by the size of one storage value.
 		#[pallet::constant]
 		type L1OperationLimit: Get<u32>;
 
+		/// The maximum number of the L1 operations visited by one `exit` (or
+		/// `refund_l1_deposits`) call, the rest are refunded by the next `refund_l1_deposits`.
+		#[pallet::constant]
+		type L1OperationsPageSize: Get<u32>;
+
 		/// The maximum of the items of one nonfungible asset
 		#[pallet::constant]
 		type NonfungibleItemLimit: Get<u32>;
 
+		/// The maximum length of the verifying key of one zkapp.
+		#[pallet::constant]
+		type MaxVerifyingKeyLen: Get<u32>;
+
+		/// The maximum number of blocks since the last batch of a zkapp.
+		///
+		/// If exceeded, the zkapp is forced inactive by the liveness watchdog.
+		#[pallet::constant]
+		type MaxBatchInterval: Get<Self::BlockNumber>;
+
+		/// The maximum number of blocks one L1 operation waits in the queue of a zkapp.
+		///
+		/// If exceeded, the zkapp is forced inactive by the liveness watchdog.
+		#[pallet::constant]
+		type MaxL1OperationAge: Get<Self::BlockNumber>;
+
+		/// The number of blocks before the deadline of a forced withdrawal.
+		///
+		/// A batch submitted after the deadline which doesn't include the withdrawal forces the
+		/// zkapp inactive.
+		#[pallet::constant]
+		type ForcedWithdrawalDelay: Get<Self::BlockNumber>;
+
+		/// The amount reserved from a submitter before it can submit batches.
+		#[pallet::constant]
+		type SubmitterBond: Get<CurrencyBalanceOf<Self, I>>;
+
+		/// The number of blocks before an unbonded submitter's bond can be withdrawn.
+		///
+		/// The bond being unbonded still can be slashed in this period.
+		#[pallet::constant]
+		type UnbondingPeriod: Get<Self::BlockNumber>;
+
+		/// The number of blocks before an upgrade of a zkapp's program can be enacted.
+		///
+		/// Users who don't trust the new program can withdraw in the meantime, it must be
+		/// longer than `ForcedWithdrawalDelay`.
+		#[pallet::constant]
+		type UpgradeDelay: Get<Self::BlockNumber>;
+
+		/// The maximum length of the name, description and source URL in a zkapp's metadata.
+		#[pallet::constant]
+		type StringLimit: Get<u32>;
+
+		/// The basic amount reserved for a zkapp's metadata.
+		#[pallet::constant]
+		type MetadataDepositBase: Get<CurrencyBalanceOf<Self, I>>;
+
+		/// The additional amount reserved per byte of a zkapp's metadata.
+		#[pallet::constant]
+		type MetadataDepositPerByte: Get<CurrencyBalanceOf<Self, I>>;
+
+		/// The account receiving the protocol's cut of the fees of L1 operations.
+		type FeeDestination: Get<Self::AccountId>;
+
+		/// The protocol's cut of the fees of L1 operations, the rest is paid to the submitter of
+		/// the batch consuming the operations.
+		#[pallet::constant]
+		type ProtocolFeeCut: Get<Perbill>;
+
+		/// Handler for the slashed submitters' bonds (e.g. a treasury).
+		type Slashed: OnUnbalanced<NegativeImbalanceOf<Self, I>>;
+
+		/// The origin which may force lifecycle actions on any zkapp without its owner (e.g. a
+		/// council), see `force_set_inactive`, `force_change_submitter`, `remove_asset_support`
+		/// and `force_transfer_ownership`.
+		type ForceOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
 		#[cfg(feature = "runtime-benchmarks")]
 		/// A set of helper functions for benchmarking.
 		type Helper: BenchmarkHelper<
@@ -187,84 +289,454 @@ pub mod pallet {
 	}
 
 	#[pallet::storage]
-	/// Map of `program_hash` to `Zkapp`.
+	/// The id of the next zkapp registered.
+	pub(super) type NextZkappId<T: Config<I>, I: 'static = ()> =
+		StorageValue<_, ZkappId, ValueQuery>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to `Zkapp`.
 	///
 	/// Used to retrieve the zkapp's information.
 	pub(super) type Zkapps<T: Config<I>, I: 'static = ()> =
-		StorageMap<_, Blake2_128Concat, T::ProgramHash, ZkappOf<T, I>>;
+		StorageMap<_, Blake2_128Concat, ZkappId, ZkappOf<T, I>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id`, `accountId` and asset to the user's balance of the asset.
+	///
+	/// Used to retrieve one user's balances of the currency and fungible assets in one zkapp,
+	/// as `u128`, the nonfungible items are in `ZkappsItems`.
+	/// It's only used by `exit`, users can also `exit_with_proof` against the zkapp's state tree.
+	pub(super) type ZkappsBalances<T: Config<I>, I: 'static = ()> = StorageNMap<
+		_,
+		(
+			NMapKey<Blake2_128Concat, ZkappId>,
+			NMapKey<Blake2_128Concat, T::AccountId>,
+			NMapKey<Blake2_128Concat, AssetOf<T, I>>,
+		),
+		u128,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id`, `accountId` and collection to the user's items of the collection.
+	///
+	/// Used to retrieve one user's nonfungible assets in one zkapp, the same as `ZkappsBalances`.
+	pub(super) type ZkappsItems<T: Config<I>, I: 'static = ()> = StorageNMap<
+		_,
+		(
+			NMapKey<Blake2_128Concat, ZkappId>,
+			NMapKey<Blake2_128Concat, T::AccountId>,
+			NMapKey<Blake2_128Concat, CollectionIdOf<T, I>>,
+		),
+		ItemsOf<T, I>,
+	>;
 
 	#[pallet::storage]
-	/// Map of `program_hash` and `accountId` to Account.
+	/// Map of `zkapp_id` and asset to the total balance of the asset held by the pallet for the
+	/// zkapp's users, as `u128`.
 	///
-	/// Used to retrieve one user's assets's balances in one zkapp.
-	/// TODO: If the zkapp's program can provide EXIT sub-program, this Map is not necessary.
-	pub(super) type ZkappsAccounts<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+	/// The sum of the zkapp's `ZkappsBalances`, the assets paid by `exit` and `exit_with_proof`
+	/// are debited from it, so one zkapp can't pay out the assets of other zkapps. As
+	/// `exit_with_proof` pays the assets of the user's leaf instead of the user's balances, the
+	/// custody diverges from the sum of the balances left after it, and is only an upper bound
+	/// of the payouts of the zkapp's later exits.
+	pub(super) type ZkappsCustody<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
 		_,
 		Blake2_128Concat,
-		T::ProgramHash,
+		ZkappId,
 		Blake2_128Concat,
-		T::AccountId,
-		AccountOf<T, I>,
+		AssetOf<T, I>,
+		u128,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id`, collection and item to the nonfungible items held by the pallet for the
+	/// zkapp's users, the same as `ZkappsCustody`.
+	pub(super) type ZkappsCustodyItems<T: Config<I>, I: 'static = ()> = StorageNMap<
+		_,
+		(
+			NMapKey<Blake2_128Concat, ZkappId>,
+			NMapKey<Blake2_128Concat, CollectionIdOf<T, I>>,
+			NMapKey<Blake2_128Concat, ItemIdOf<T, I>>,
+		),
+		(),
+	>;
+
+	#[pallet::storage]
+	/// Map of `accountId` and asset to the amount withdrawn by the user's `Withdraw` operations
+	/// and not claimed yet, as `u128`.
+	///
+	/// Batches credit the withdrawals here instead of transferring them, so one recipient whose
+	/// transfer fails can't block the zkapp, the assets are transferred by `claim_withdrawal`.
+	pub(super) type PendingWithdrawals<T: Config<I>, I: 'static = ()> = StorageNMap<
+		_,
+		(NMapKey<Blake2_128Concat, T::AccountId>, NMapKey<Blake2_128Concat, AssetOf<T, I>>),
+		u128,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	/// Map of `accountId`, collection and item to the nonfungible items withdrawn by the user's
+	/// `Withdraw` operations and not claimed yet, the same as `PendingWithdrawals`.
+	pub(super) type PendingItemWithdrawals<T: Config<I>, I: 'static = ()> = StorageNMap<
+		_,
+		(
+			NMapKey<Blake2_128Concat, T::AccountId>,
+			NMapKey<Blake2_128Concat, CollectionIdOf<T, I>>,
+			NMapKey<Blake2_128Concat, ItemIdOf<T, I>>,
+		),
+		(),
 	>;
 
 	#[pallet::storage]
-	/// Map of `program_hash` and `accountId` to Exit status.
+	/// Map of `zkapp_id` and `accountId` to Exit status.
 	///
 	/// Used to retrieve whether one user has exited the zkapp.
 	pub(super) type ZkappsExit<T: Config<I>, I: 'static = ()> =
-		StorageDoubleMap<_, Blake2_128Concat, T::ProgramHash, Blake2_128Concat, T::AccountId, bool>;
+		StorageDoubleMap<_, Blake2_128Concat, ZkappId, Blake2_128Concat, T::AccountId, bool>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the zkapp's verifying key.
+	///
+	/// Used by the verifiers whose circuits are not identified by `program_hash`, e.g. Groth16 and
+	/// PLONK.
+	pub(super) type VerifyingKeys<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, BoundedVec<u8, T::MaxVerifyingKeyLen>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the block number of the zkapp's last batch.
+	///
+	/// Used by the liveness watchdog, initialized when the zkapp registered and removed when the
+	/// zkapp is set inactive.
+	pub(super) type LastBatchAt<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, T::BlockNumber>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the indices of the zkapp's L1 operations queue.
+	pub(super) type L1OperationsQueue<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, QueueIndices, ValueQuery>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` and index to the L1 operation queued.
+	///
+	/// Only the operations at `head..tail` of the zkapp's `L1OperationsQueue` are stored, the
+	/// consumed ones are removed.
+	pub(super) type L1Operations<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ZkappId,
+		Twox64Concat,
+		u64,
+		QueuedL1OperationOf<T, I>,
+	>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the account the ownership of the zkapp is being transferred to.
+	pub(super) type PendingOwners<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, T::AccountId>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the upgrade of the zkapp's program proposed.
+	pub(super) type PendingUpgrades<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, PendingUpgradeOf<T, I>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the metadata of the zkapp.
+	pub(super) type Metadata<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, ZkappMetadataOf<T, I>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the fee policy of the zkapp, no fees if not set.
+	pub(super) type FeePolicies<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, FeePolicyOf<T, I>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` and index to the fee paid for the L1 operation queued, held by the
+	/// pallet until the operation is consumed by a batch.
+	pub(super) type L1OperationFees<T: Config<I>, I: 'static = ()> =
+		StorageDoubleMap<_, Blake2_128Concat, ZkappId, Twox64Concat, u64, AssetValueOf<T, I>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the head and tail of the zkapp's forced withdrawals queue.
+	pub(super) type ForcedWithdrawalsQueue<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, QueueIndices, ValueQuery>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` and index to the forced withdrawal queued, in the order of their
+	/// `Withdraw` operations in the L1 operations queue.
+	///
+	/// Only the forced withdrawals not included by a batch are stored.
+	pub(super) type ForcedWithdrawals<T: Config<I>, I: 'static = ()> =
+		StorageDoubleMap<_, Blake2_128Concat, ZkappId, Twox64Concat, u64, ForcedWithdrawalOf<T, I>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the hash chain of all the L1 operations consumed by the zkapp's
+	/// batches, the zero hash if none.
+	pub(super) type L1OperationsConsumedHash<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, L1OperationsHash, ValueQuery>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the batch committed by `commit_batch` and not fully executed.
+	///
+	/// The zkapp can't commit (or submit) a new batch until it's removed by `execute_batch`, or
+	/// abandoned when the zkapp is set inactive. A batch whose operations fail to execute blocks
+	/// the zkapp's batches, so the liveness watchdog sets it inactive after `MaxBatchInterval`.
+	pub(super) type PendingBatches<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, PendingBatch>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` and `accountId` to the bond of the zkapp's submitter.
+	///
+	/// Only the bonded submitters can submit batches.
+	pub(super) type SubmitterBonds<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ZkappId,
+		Blake2_128Concat,
+		T::AccountId,
+		BondOf<T, I>,
+	>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` to the rotation of the zkapp's submitters, any submitter can submit
+	/// batches if not set.
+	pub(super) type SubmitterRotations<T: Config<I>, I: 'static = ()> =
+		StorageMap<_, Blake2_128Concat, ZkappId, SubmitterRotationOf<T>>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` and `accountId` to the bond being unbonded.
+	///
+	/// Used to retrieve the bond of a former submitter, which can be withdrawn after
+	/// `UnbondingPeriod`.
+	pub(super) type Unbondings<T: Config<I>, I: 'static = ()> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ZkappId,
+		Blake2_128Concat,
+		T::AccountId,
+		UnbondingOf<T, I>,
+	>;
+
+	#[pallet::storage]
+	/// Map of `zkapp_id` and `accountId` to the index of the next L1 operation to refund.
+	///
+	/// Set when an exited user's `Deposit` operations are not all refunded by one call.
+	pub(super) type ExitRefundCursors<T: Config<I>, I: 'static = ()> =
+		StorageDoubleMap<_, Blake2_128Concat, ZkappId, Blake2_128Concat, T::AccountId, u64>;
+
+	#[pallet::storage]
+	/// The id of the zkapp checked last by the liveness watchdog.
+	pub(super) type LivenessCursor<T: Config<I>, I: 'static = ()> = StorageValue<_, ZkappId>;
+
+	#[pallet::hooks]
+	impl<T: Config<I>, I: 'static> Hooks<BlockNumberFor<T>> for Pallet<T, I> {
+		/// Check the liveness of zkapps with the remaining weight of the block.
+		fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			Self::check_liveness(now, remaining_weight)
+		}
+
+		/// Set the storage version of a chain upgrading from the releases before it, only if
+		/// they left no storage, which can't be migrated.
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T, I>>() >= STORAGE_VERSION {
+				return T::DbWeight::get().reads(1)
+			}
+			if Self::has_unversioned_storage() {
+				log::error!(
+					target: LOG_TARGET,
+					"the storage of the unversioned releases can't be migrated, the storage version \
+					is not set, the chain must be restarted with a fresh genesis",
+				);
+				return T::DbWeight::get().reads(4)
+			}
+			STORAGE_VERSION.put::<Pallet<T, I>>();
+			T::DbWeight::get().reads_writes(4, 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			ensure!(
+				StorageVersion::get::<Pallet<T, I>>() >= STORAGE_VERSION ||
+					!Self::has_unversioned_storage(),
+				"the storage of the unversioned releases can't be migrated"
+			);
+			Ok(Vec::new())
+		}
+
+		fn integrity_test() {
+			// users who don't trust an upgrade must be able to force withdraw before it's enacted
+			assert!(
+				T::UpgradeDelay::get() > T::ForcedWithdrawalDelay::get(),
+				"`UpgradeDelay` must be greater than `ForcedWithdrawalDelay`"
+			);
+		}
+	}
 
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	/// All events that can be emitted by Pallet function.
 	pub enum Event<T: Config<I>, I: 'static = ()> {
 		/// A zkapp registered in the pallet.
-		/// \[zkvm_type, program_hash\]
-		ZkappRegister(ZkvmType, T::ProgramHash),
+		/// \[zkapp_id, zkvm_type, program_hash\]
+		ZkappRegister(ZkappId, ZkvmType, T::ProgramHash),
 
 		/// A supported asset added into a zkapp.
-		/// \[program_hash, asset\]
-		AddAssetSupport(T::ProgramHash, AssetOf<T, I>),
+		/// \[zkapp_id, asset\]
+		AddAssetSupport(ZkappId, AssetOf<T, I>),
+
+		/// A supported asset removed from a zkapp.
+		/// \[zkapp_id, asset\]
+		RemoveAssetSupport(ZkappId, AssetOf<T, I>),
+
+		/// The verifying key of a zkapp has been set.
+		/// \[zkapp_id\]
+		SetVerifyingKey(ZkappId),
+
+		/// An upgrade of a zkapp's program has been proposed.
+		/// \[zkapp_id, new_program_hash, new_zkvm_type, enact_at\]
+		UpgradeProposed(ZkappId, T::ProgramHash, ZkvmType, T::BlockNumber),
+
+		/// The upgrade of a zkapp's program has been enacted.
+		/// \[zkapp_id, new_program_hash, new_zkvm_type\]
+		UpgradeEnacted(ZkappId, T::ProgramHash, ZkvmType),
+
+		/// The metadata of a zkapp has been set.
+		/// \[zkapp_id, name, description, source_url, source_hash\]
+		Metadata(
+			ZkappId,
+			MetadataStringOf<T, I>,
+			MetadataStringOf<T, I>,
+			MetadataStringOf<T, I>,
+			[u8; 32],
+		),
+
+		/// The fee policy of a zkapp has been set, `None` if removed.
+		/// \[zkapp_id, fee_policy\]
+		FeePolicySet(ZkappId, Option<FeePolicyOf<T, I>>),
 
 		/// The submitter changed of o zkapp.
-		/// \[program_hash, submitter\]
-		ChangeSubmitter(T::ProgramHash, T::AccountId),
+		/// \[zkapp_id, submitter\]
+		ChangeSubmitter(ZkappId, T::AccountId),
+
+		/// A submitter added into a zkapp.
+		/// \[zkapp_id, submitter\]
+		SubmitterAdded(ZkappId, T::AccountId),
+
+		/// A submitter removed from a zkapp.
+		/// \[zkapp_id, submitter\]
+		SubmitterRemoved(ZkappId, T::AccountId),
+
+		/// The rotation of the submitters of a zkapp has been set, `None` if removed.
+		/// \[zkapp_id, rotation\]
+		SubmitterRotationSet(ZkappId, Option<SubmitterRotationOf<T>>),
 
 		/// One zkapp's status has been setted to inactive.
-		/// \[program_hash\]
-		SetInactive(T::ProgramHash),
+		/// \[zkapp_id\]
+		SetInactive(ZkappId),
+
+		/// The owner of a zkapp proposed to transfer its ownership.
+		/// \[zkapp_id, owner, new_owner\]
+		OwnershipTransferProposed(ZkappId, T::AccountId, T::AccountId),
+
+		/// The owner of a zkapp has been changed.
+		/// \[zkapp_id, old_owner, new_owner\]
+		OwnershipTransferred(ZkappId, T::AccountId, T::AccountId),
+
+		/// One zkapp's status has been forced to inactive by the liveness watchdog, by a batch
+		/// missing a forced withdrawal, or by `ForceOrigin`.
+		/// \[zkapp_id, reason\]
+		ForcedInactive(ZkappId, InactiveReason),
+
+		/// The submitter of a zkapp reserved its bond.
+		/// \[zkapp_id, submitter, amount\]
+		SubmitterBonded(ZkappId, T::AccountId, CurrencyBalanceOf<T, I>),
+
+		/// A former submitter of a zkapp withdrawed its bond.
+		/// \[zkapp_id, account_id, amount\]
+		SubmitterUnbonded(ZkappId, T::AccountId, CurrencyBalanceOf<T, I>),
+
+		/// The bond of a submitter of a zkapp has been slashed.
+		/// \[zkapp_id, account_id, amount\]
+		SubmitterSlashed(ZkappId, T::AccountId, CurrencyBalanceOf<T, I>),
 
 		/// A user deposited asset into a zkapp.
-		/// \[program_hash, account_id, asset_value\]
-		Deposited(T::ProgramHash, T::AccountId, AssetValueOf<T, I>),
+		/// \[zkapp_id, account_id, asset_value\]
+		Deposited(ZkappId, T::AccountId, AssetValueOf<T, I>),
 
 		/// A user withdrawed asset from a zkapp.
-		/// \[program_hash, account_id, asset_value\]
-		Withdrawed(T::ProgramHash, T::AccountId, AssetValueOf<T, I>),
+		/// \[zkapp_id, account_id, asset_value\]
+		Withdrawed(ZkappId, T::AccountId, AssetValueOf<T, I>),
+
+		/// A user forced a withdrawal from a zkapp.
+		/// \[zkapp_id, account_id, asset_value, deadline\]
+		ForcedWithdrawal(ZkappId, T::AccountId, AssetValueOf<T, I>, T::BlockNumber),
 
 		/// A user move asset from a zkapp to another zkapp.
-		/// \[from_program_hash, to_program_hash, account_id, asset_value\]
-		MoveAsset(T::ProgramHash, T::ProgramHash, T::AccountId, AssetValueOf<T, I>),
+		/// \[from_zkapp_id, to_zkapp_id, account_id, asset_value\]
+		MoveAsset(ZkappId, ZkappId, T::AccountId, AssetValueOf<T, I>),
+
+		/// A batch of a zkapp sent a message to another zkapp.
+		/// \[from_zkapp_id, to_zkapp_id, payload\]
+		MessageSent(ZkappId, ZkappId, MessagePayload),
 
 		/// A user exited from a zkapp.
-		/// \[program_hash, account_id\]
-		Exit(T::ProgramHash, T::AccountId),
+		/// \[zkapp_id, account_id\]
+		Exit(ZkappId, T::AccountId),
+
+		/// The `Deposit` operations of an exited user in the L1 operations queue of a zkapp
+		/// have been refunded up to the index.
+		/// \[zkapp_id, account_id, index\]
+		L1DepositsRefunded(ZkappId, T::AccountId, u64),
+
+		/// The withdrawn assets of a user have been claimed.
+		/// \[account_id, asset_value\]
+		WithdrawalClaimed(T::AccountId, AssetValueOf<T, I>),
 
 		/// A batch of a zkapp submited into the pallet.
-		/// \[program_hash, old_state_root, new_state_root, operations\]
-		SubmitBatch(T::ProgramHash, T::StateRoot, T::StateRoot, Vec<OperationOf<T, I>>),
+		/// \[zkapp_id, old_state_root, new_state_root, operations\]
+		SubmitBatch(ZkappId, T::StateRoot, T::StateRoot, Vec<OperationOf<T, I>>),
+
+		/// A batch of a zkapp committed into the pallet, its operations are executed by
+		/// `execute_batch`, which takes them from this event.
+		/// \[zkapp_id, old_state_root, new_state_root, operations, operations_commitment\]
+		BatchCommitted(
+			ZkappId,
+			T::StateRoot,
+			T::StateRoot,
+			Vec<OperationOf<T, I>>,
+			OperationsCommitment,
+		),
+
+		/// The operations of the pending batch of a zkapp have been executed up to the index, the
+		/// batch is fully executed when the index is its length.
+		/// \[zkapp_id, index, len\]
+		BatchExecuted(ZkappId, u32, u32),
+
+		/// The pending batch of a zkapp set inactive has been abandoned, the operations from the
+		/// index are not executed.
+		/// \[zkapp_id, index, len\]
+		BatchAbandoned(ZkappId, u32, u32),
+
+		/// An L1 operation of a batch is skipped, as the user doesn't hold the assets withdrawn
+		/// or moved when the batch is executed, or the destination zkapp of a move can't receive
+		/// them any more.
+		/// \[zkapp_id, operation\]
+		L1OperationSkipped(ZkappId, OperationOf<T, I>),
+
+		/// The metadata of a zkapp has been cleared, and its deposit unreserved.
+		/// \[zkapp_id\]
+		MetadataCleared(ZkappId),
 	}
 
 	#[pallet::error]
 	/// All errors that can be returned by Pallet functions.
 	pub enum Error<T, I = ()> {
-		/// The zkapp has been registered before.
-		DuplicateApp,
 		/// BoundedVec error.
 		BoundedVecInvalid,
-		/// No zkapp registered for program_hash.
+		/// No zkapp registered for zkapp_id.
 		NoProgram,
-		/// Move asset to the same zkapp.
+		/// Move asset (or send a message) to the same zkapp.
 		SameZkapp,
 		/// The supported asset has been added before.
 		DuplicateSupportAsset,
@@ -295,10 +767,68 @@ pub mod pallet {
 		InvalidBatchParams,
 		/// No enough assets when user withdraw or move assets.
 		NoEnoughAssets,
-		/// The operations of the batch submited include unknowned account by the zkapp.
-		NoAccount,
 		/// The operations of the batch submited include invalid assets.
 		InvalidAssets,
+		/// The leaf is not included in the zkapp's state tree, or not owned by the user.
+		InvalidStateProof,
+		/// The zkapp doesn't hold enough assets to pay the user exiting.
+		NoEnoughCustody,
+		/// The submitter has not reserved its bond.
+		NotBonded,
+		/// The submitter has reserved its bond before.
+		AlreadyBonded,
+		/// No bond being unbonded.
+		NoUnbonding,
+		/// The bond is still being unbonded.
+		StillUnbonding,
+		/// The verifying key exceeds `MaxVerifyingKeyLen`.
+		VerifyingKeyTooLong,
+		/// The verifying key can't be parsed by the zkapp's verifier.
+		InvalidVerifyingKey,
+		/// The zkapp's verifier requires a verifying key, which has not been set.
+		NoVerifyingKey,
+		/// No verifier of the zkvm type is enabled by `Config::Verifiers`.
+		UnsupportedZkvm,
+		/// All the L1 deposits of the exited user have been refunded.
+		NoL1DepositsToRefund,
+		/// The zkapp has a committed batch not fully executed.
+		BatchPending,
+		/// The zkapp has no committed batch to execute.
+		NoPendingBatch,
+		/// The operations don't match the commitment of the pending batch.
+		InvalidOperationsCommitment,
+		/// The user has not withdrawn enough assets to claim.
+		NoEnoughWithdrawals,
+		/// The fees of a fee policy must be currency or fungible assets.
+		InvalidFeePolicy,
+		/// The name, description or source URL of the metadata exceeds `StringLimit`.
+		BadMetadata,
+		/// No upgrade of the zkapp's program proposed.
+		NoPendingUpgrade,
+		/// The upgrade can't be enacted before the `UpgradeDelay`.
+		UpgradeTooEarly,
+		/// The upgrade can't be enacted before the forced withdrawals due by it are included.
+		ForcedWithdrawalsPending,
+		/// The account is a submitter of the zkapp already.
+		DuplicateSubmitter,
+		/// The number of submitters exceed `MaxSubmitters`.
+		SubmittersLimitExceed,
+		/// The last submitter of a zkapp can't be removed, use `change_submitter` instead.
+		LastSubmitter,
+		/// The submitters of the zkapp stalled, their bonds are slashed by the liveness watchdog.
+		Stalled,
+		/// The slot length of the rotation is zero.
+		InvalidRotation,
+		/// Only the leader of the current slot can submit batches until the grace period.
+		NotSlotLeader,
+		/// The ownership of the zkapp is not being transferred.
+		NoPendingOwner,
+		/// The ownership of the zkapp is being transferred to another account.
+		NotPendingOwner,
+		/// The amount of the asset withdrawn is zero.
+		ZeroAmount,
+		/// The zkapp has no metadata.
+		NoMetadata,
 	}
 
 	#[pallet::call]
@@ -316,6 +846,9 @@ pub mod pallet {
 		/// - `submitter`: who can submit one batch for the zkapp.
 		/// - `empty_state_root`: the root (hash) of empty state of the zkapp.
 		///
+		/// The zkapp is identified by the next `ZkappId` rather than its program hash, so the
+		/// same program can be registered more than once and the program can be upgraded.
+		///
 		/// Emits `ZkappRegister` event when successful.
 		///
 		/// Weight: `O(1)`
@@ -329,35 +862,39 @@ pub mod pallet {
 			let owner = ensure_signed(origin)?;
 			let submitter = T::Lookup::lookup(submitter)?;
 
-			ensure!(!Zkapps::<T, I>::contains_key(program_hash), Error::<T, I>::DuplicateApp);
+			ensure!(T::Verifiers::is_supported(&zkvm_type), Error::<T, I>::UnsupportedZkvm);
 
+			let submitters = SubmittersOf::<T, I>::try_from(vec![submitter])
+				.map_err(|_| Error::<T, I>::BoundedVecInvalid)?;
 			let mut supported_assets: SupportedAssetsOf<T, I> = Default::default();
 			supported_assets
 				.try_push(Asset::Currency)
 				.map_err(|_| Error::<T, I>::BoundedVecInvalid)?;
 
+			let zkapp_id = NextZkappId::<T, I>::get();
+			NextZkappId::<T, I>::put(zkapp_id.checked_add(1).ok_or(ArithmeticError::Overflow)?);
+
 			Zkapps::<T, I>::insert(
-				program_hash,
+				zkapp_id,
 				Zkapp {
+					program_hash,
 					zkvm_type: zkvm_type.clone(),
 					owner,
-					submitter,
+					submitters,
 					is_inactive: false,
 					state_root: empty_state_root,
 					supported_assets,
-					l1_operations: Vec::new()
-						.try_into()
-						.map_err(|_| Error::<T, I>::BoundedVecInvalid)?,
 				},
 			);
-			Self::deposit_event(Event::ZkappRegister(zkvm_type, program_hash));
+			LastBatchAt::<T, I>::insert(zkapp_id, frame_system::Pallet::<T>::block_number());
+			Self::deposit_event(Event::ZkappRegister(zkapp_id, zkvm_type, program_hash));
 			Ok(())
 		}
 
 		/// Add a asset supported by a zkapp, can only be called by owner of the zkapp.
 		///
 		/// - `origin`: the sender who is the zkapp' owner.
-		/// - `program_hash`: the program hash of the zkapp's program.
+		/// - `zkapp_id`: the id of the zkapp.
 		/// - `asset`: the asset the zkapp supported.
 		///
 		/// Emits `AddAssetSupport` event when successful.
@@ -366,12 +903,12 @@ pub mod pallet {
 		#[pallet::weight(T::WeightInfo::add_asset_support())]
 		pub fn add_asset_support(
 			origin: OriginFor<T>,
-			program_hash: T::ProgramHash,
+			zkapp_id: ZkappId,
 			asset: AssetOf<T, I>,
 		) -> DispatchResult {
 			let owner = ensure_signed(origin)?;
 			let mut zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
 			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
 			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
 			ensure!(!zkapp.supported_assets.contains(&asset), Error::<T, I>::DuplicateSupportAsset);
@@ -380,412 +917,1211 @@ pub mod pallet {
 				.supported_assets
 				.try_push(asset.clone())
 				.map_err(|_| Error::<T, I>::AssetsLimitExceed)?;
-			Zkapps::<T, I>::insert(program_hash, zkapp);
-			Self::deposit_event(Event::AddAssetSupport(program_hash, asset));
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::deposit_event(Event::AddAssetSupport(zkapp_id, asset));
 			Ok(())
 		}
 
-		/// Change the submitter of one zkapp, can only be called by owner of the zkapp.
+		/// Set or remove the fee policy of a zkapp, can only be called by owner of the zkapp.
 		///
-		/// Emits `ChangeSubmitter` event when successful.
+		/// The fees are charged from the users on their L1 operations (`deposit`, `withdraw` and
+		/// `move_asset`, `force_withdraw` is free), and credited to the pending withdrawals of
+		/// the submitter when the batch consuming the operations is accepted, except the
+		/// `ProtocolFeeCut` credited to `FeeDestination`, they are claimed by
+		/// `claim_withdrawal`. The fees of the operations queued already are not changed.
+		///
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `fee_policy`: the new fee policy, `None` to charge no fees.
+		///
+		/// Emits `FeePolicySet` event when successful.
 		///
 		/// Weight: `O(1)`
-		#[pallet::weight(T::WeightInfo::change_submitter())]
-		pub fn change_submitter(
+		#[pallet::weight(T::WeightInfo::set_fee_policy())]
+		pub fn set_fee_policy(
 			origin: OriginFor<T>,
-			program_hash: T::ProgramHash,
-			submitter: AccountIdLookupOf<T>,
+			zkapp_id: ZkappId,
+			fee_policy: Option<FeePolicyOf<T, I>>,
 		) -> DispatchResult {
 			let owner = ensure_signed(origin)?;
-			let mut zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
 			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
-			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
-			let submitter = T::Lookup::lookup(submitter)?;
-			zkapp.submitter = submitter.clone();
-
-			Zkapps::<T, I>::insert(program_hash, zkapp);
-			Self::deposit_event(Event::ChangeSubmitter(program_hash, submitter));
+			ensure!(
+				fee_policy.as_ref().map_or(true, |policy| policy
+					.fees()
+					.iter()
+					.all(|fee| !matches!(fee, AssetValue::Nonfungible(..)))),
+				Error::<T, I>::InvalidFeePolicy
+			);
 
+			FeePolicies::<T, I>::mutate_exists(zkapp_id, |policy| *policy = fee_policy.clone());
+			Self::deposit_event(Event::FeePolicySet(zkapp_id, fee_policy));
 			Ok(())
 		}
 
-		/// Set the zkapp is inactive, can only be called by owner of the zkapp.
-		/// If be called, only `exit` is allowed for the zkapp.
-		///
-		/// TODO: If a zkapp has a fraud program, bugs, or does not submit batch txs for a long
-		/// time, other mechanisms are required to set this program as inactive,
-		/// so that users can fully exit this program to withdraw their assets.
-		///
-		/// Emits `SetInactive` event when successful.
+		/// Propose an upgrade of the program of one zkapp, can only be called by owner of the
+		/// zkapp.
 		///
-		/// Weight: `O(1)`
-		#[pallet::weight(T::WeightInfo::set_inactive())]
-		pub fn set_inactive(origin: OriginFor<T>, program_hash: T::ProgramHash) -> DispatchResult {
-			let owner = ensure_signed(origin)?;
-			let mut zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
-			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
-			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
-			zkapp.is_inactive = true;
-			Zkapps::<T, I>::insert(program_hash, zkapp);
-			Self::deposit_event(Event::SetInactive(program_hash));
-			Ok(())
-		}
-
-		/// Deposit asset to a zkapp, it is a L1 transaction, and trigger `Deposit` operation.
+		/// The zkapp keeps its id, storage, state root and users, only the program verifying the
+		/// batches is replaced. The
+		/// upgrade can be enacted by `enact_upgrade` after `UpgradeDelay` blocks, users who don't
+		/// trust the new program can withdraw (e.g. by `force_withdraw`) in the meantime.
+		/// A proposal replaces the former one.
 		///
-		/// Save `Deposit` operation into `l1_operations` queue, the zkapp's program (off-chain)
-		/// should read `l1_operations` as the program's inputs when execution.
-		/// The asset will be added to the user's assets of the pallet when `submit_batch` is
-		/// called.
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `new_program_hash`: the program hash of the new program.
+		/// - `new_zkvm_type`: the zkvm type of the new program.
+		/// - `new_verifying_key`: the verifying key of the new program, checked the same as
+		/// `set_verifying_key`, `None` if its verifier doesn't require one.
 		///
-		/// Emits `Deposited` event when successful.
+		/// Emits `UpgradeProposed` event when successful.
 		///
-		/// Weight: `O(1)`
-		#[pallet::weight(T::WeightInfo::deposit())]
-		pub fn deposit(
+		/// Weight: `O(K)` where `K` is the length of the verifying key
+		#[pallet::weight(T::WeightInfo::propose_upgrade(
+			new_verifying_key.as_ref().map_or(0, |key| key.len() as u32)
+		))]
+		pub fn propose_upgrade(
 			origin: OriginFor<T>,
-			program_hash: T::ProgramHash,
-			asset_value: AssetValueOf<T, I>,
+			zkapp_id: ZkappId,
+			new_program_hash: T::ProgramHash,
+			new_zkvm_type: ZkvmType,
+			new_verifying_key: Option<Vec<u8>>,
 		) -> DispatchResult {
-			let user = ensure_signed(origin)?;
-			let mut zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
-			let asset = asset_value.clone().into();
+			let owner = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
 			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
-			ensure!(zkapp.supported_assets.contains(&asset), Error::<T, I>::NotSupportAsset);
-
-			Self::user_deposit(user.clone(), asset_value.clone())?;
-
-			zkapp
-				.l1_operations
-				.try_push(Operation::Deposit(user.clone(), asset_value.clone()))
-				.map_err(|_| Error::<T, I>::L1OperationLimitExceed)?;
-			Zkapps::<T, I>::insert(program_hash, zkapp);
-
-			Self::deposit_event(Event::Deposited(program_hash, user, asset_value));
+			ensure!(T::Verifiers::is_supported(&new_zkvm_type), Error::<T, I>::UnsupportedZkvm);
+			let verifying_key = match new_verifying_key {
+				Some(verifying_key) => {
+					let verifying_key: BoundedVec<u8, T::MaxVerifyingKeyLen> =
+						verifying_key.try_into().map_err(|_| Error::<T, I>::VerifyingKeyTooLong)?;
+					T::Verifiers::check_verifying_key(&new_zkvm_type, &verifying_key)
+						.map_err(|_| Error::<T, I>::InvalidVerifyingKey)?;
+					Some(verifying_key)
+				},
+				None => None,
+			};
 
+			Self::schedule_upgrade(zkapp_id, new_program_hash, new_zkvm_type, verifying_key);
 			Ok(())
 		}
 
-		/// Withdraw asset from a zkapp, it is a L1 transaction, and trigger `Withdraw` operation.
+		/// Enact the upgrade proposed of one zkapp, can only be called by owner of the zkapp.
 		///
-		/// Save `Withdraw` operation into `l1_operations` queue, the zkapp's program (off-chain)
-		/// should read `l1_operations` as the program's inputs when execution.
-		/// The asset will be withdrawed to the user when `submit_batch` is called.
+		/// The forced withdrawals due by `enact_at` must be included by the batches of the former
+		/// program first, the ones queued later are due after the upgrade and are included by the
+		/// batches of the new program. The verifying key of the former program is replaced by the
+		/// one proposed with the new program, or removed if none.
 		///
-		/// Emits `Withdrawed` event when successful.
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		///
+		/// Emits `UpgradeEnacted` event when successful.
 		///
 		/// Weight: `O(1)`
-		#[pallet::weight(T::WeightInfo::withdraw())]
-		pub fn withdraw(
-			origin: OriginFor<T>,
-			program_hash: T::ProgramHash,
-			asset_value: AssetValueOf<T, I>,
-		) -> DispatchResult {
-			let user = ensure_signed(origin)?;
+		#[pallet::weight(T::WeightInfo::enact_upgrade())]
+		pub fn enact_upgrade(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
 			let mut zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
-			let asset = asset_value.clone().into();
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
 			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
-			ensure!(zkapp.supported_assets.contains(&asset), Error::<T, I>::NotSupportAsset);
-
-			// check user balance
-			let account = ZkappsAccounts::<T, I>::try_get(program_hash, user.clone())
-				.map_err(|_| Error::<T, I>::NoEnoughAssets)?;
+			let upgrade =
+				PendingUpgrades::<T, I>::get(zkapp_id).ok_or(Error::<T, I>::NoPendingUpgrade)?;
 			ensure!(
-				Self::check_has_enough_asset(&account, &asset_value),
-				Error::<T, I>::NoEnoughAssets
+				upgrade.enact_at <= frame_system::Pallet::<T>::block_number(),
+				Error::<T, I>::UpgradeTooEarly
+			);
+			// the deadlines increase along the queue, so only the oldest one is checked.
+			let forced_queue = ForcedWithdrawalsQueue::<T, I>::get(zkapp_id);
+			ensure!(
+				ForcedWithdrawals::<T, I>::get(zkapp_id, forced_queue.head)
+					.map_or(true, |forced| forced.deadline > upgrade.enact_at),
+				Error::<T, I>::ForcedWithdrawalsPending
 			);
 
-			zkapp
-				.l1_operations
-				.try_push(Operation::Withdraw(user.clone(), asset_value.clone()))
-				.map_err(|_| Error::<T, I>::L1OperationLimitExceed)?;
-			Zkapps::<T, I>::insert(program_hash, zkapp);
+			zkapp.program_hash = upgrade.program_hash;
+			zkapp.zkvm_type = upgrade.zkvm_type.clone();
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			PendingUpgrades::<T, I>::remove(zkapp_id);
+			VerifyingKeys::<T, I>::set(zkapp_id, upgrade.verifying_key);
 
-			Self::deposit_event(Event::Withdrawed(program_hash, user, asset_value));
+			Self::deposit_event(Event::UpgradeEnacted(
+				zkapp_id,
+				upgrade.program_hash,
+				upgrade.zkvm_type,
+			));
 			Ok(())
 		}
 
-		/// Move asset from a zkapp to another zkapp, it is a L1 transaction, and trigger `Move`
-		/// operation.
+		/// Set or replace the metadata of one zkapp, can only be called by owner of the zkapp.
 		///
-		/// Save `Move` operation into `l1_operations` queue, the zkapp's program (off-chain)
-		/// should read `l1_operations` as the program's inputs when execution.
-		/// The asset will be moved when `submit_batch` is called.
+		/// `MetadataDepositBase` plus `MetadataDepositPerByte` per byte of `name`, `description`
+		/// and `source_url` is reserved from the owner, the deposit of the metadata replaced is
+		/// unreserved.
 		///
-		/// Emits `Move` event when successful.
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `name`: the name of the zkapp.
+		/// - `description`: the description of the zkapp.
+		/// - `source_url`: the URL of the source code of the zkapp's program.
+		/// - `source_hash`: the hash of the source code.
+		///
+		/// Emits `Metadata` event when successful.
 		///
 		/// Weight: `O(1)`
-		#[pallet::weight(T::WeightInfo::move_asset())]
-		pub fn move_asset(
+		#[pallet::weight(T::WeightInfo::set_metadata(
+			(name.len() + description.len() + source_url.len()) as u32
+		))]
+		pub fn set_metadata(
 			origin: OriginFor<T>,
-			from_program_hash: T::ProgramHash,
-			to_program_hash: T::ProgramHash,
-			asset_value: AssetValueOf<T, I>,
+			zkapp_id: ZkappId,
+			name: Vec<u8>,
+			description: Vec<u8>,
+			source_url: Vec<u8>,
+			source_hash: [u8; 32],
 		) -> DispatchResult {
-			let user = ensure_signed(origin)?;
-			let mut from_zkapp =
-				Zkapps::<T, I>::try_get(from_program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
-			let to_zkapp =
-				Zkapps::<T, I>::try_get(to_program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
-			let asset = asset_value.clone().into();
-			ensure!(from_program_hash != to_program_hash, Error::<T, I>::SameZkapp);
-			ensure!(!from_zkapp.is_inactive, Error::<T, I>::Inactive);
-			ensure!(!to_zkapp.is_inactive, Error::<T, I>::Inactive);
-			ensure!(from_zkapp.supported_assets.contains(&asset), Error::<T, I>::NotSupportAsset);
-			ensure!(to_zkapp.supported_assets.contains(&asset), Error::<T, I>::NotSupportAsset);
+			let owner = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
 
-			// check user balance
-			let account = ZkappsAccounts::<T, I>::try_get(from_program_hash, user.clone())
-				.map_err(|_| Error::<T, I>::NoEnoughAssets)?;
-			ensure!(
-				Self::check_has_enough_asset(&account, &asset_value),
-				Error::<T, I>::NoEnoughAssets
+			let len = name.len().saturating_add(description.len()).saturating_add(source_url.len());
+			let name: MetadataStringOf<T, I> =
+				name.try_into().map_err(|_| Error::<T, I>::BadMetadata)?;
+			let description: MetadataStringOf<T, I> =
+				description.try_into().map_err(|_| Error::<T, I>::BadMetadata)?;
+			let source_url: MetadataStringOf<T, I> =
+				source_url.try_into().map_err(|_| Error::<T, I>::BadMetadata)?;
+
+			let deposit = T::MetadataDepositPerByte::get()
+				.saturating_mul((len as u32).into())
+				.saturating_add(T::MetadataDepositBase::get());
+			if let Some(metadata) = Metadata::<T, I>::get(zkapp_id) {
+				T::Currency::unreserve(&metadata.depositor, metadata.deposit);
+			}
+			T::Currency::reserve(&owner, deposit)?;
+
+			Metadata::<T, I>::insert(
+				zkapp_id,
+				ZkappMetadata {
+					depositor: owner,
+					deposit,
+					name: name.clone(),
+					description: description.clone(),
+					source_url: source_url.clone(),
+					source_hash,
+				},
 			);
-
-			from_zkapp
-				.l1_operations
-				.try_push(Operation::Move(user.clone(), to_program_hash, asset_value.clone()))
-				.map_err(|_| Error::<T, I>::L1OperationLimitExceed)?;
-			Zkapps::<T, I>::insert(from_program_hash, from_zkapp);
-
-			Self::deposit_event(Event::MoveAsset(
-				from_program_hash,
-				to_program_hash,
-				user,
-				asset_value,
+			Self::deposit_event(Event::Metadata(
+				zkapp_id,
+				name,
+				description,
+				source_url,
+				source_hash,
 			));
 			Ok(())
 		}
 
-		/// User exit a zkapp fully, it can be called only when the status of this program is
-		/// inactive.
+		/// Clear the metadata of one zkapp, can only be called by owner of the zkapp, also when
+		/// the zkapp is inactive.
 		///
-		/// When called, user's assets (saved in ZkappsAccounts DoubleMap) are transfered to user,
-		/// if L1 operations queue `l1_operations` of the zkapp has user's `Deposit` operations,
-		/// they also are transfered to user.
+		/// The deposit of the metadata is unreserved to its depositor.
 		///
-		/// Emits `Exit` event when successful.
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
 		///
-		/// Weight: `O(max(m, n))` where
-		/// - `m = account.assets.len()`
-		/// - `n = zkapp.l1_operations.len()`
-		#[pallet::weight(T::WeightInfo::exit())]
-		pub fn exit(origin: OriginFor<T>, program_hash: T::ProgramHash) -> DispatchResult {
+		/// Emits `MetadataCleared` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::clear_metadata())]
+		pub fn clear_metadata(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+
+			Self::remove_metadata(zkapp_id)
+		}
+
+		/// Upload or replace the verifying key of one zkapp, can only be called by owner of the
+		/// zkapp.
+		///
+		/// The key is passed to the zkapp's verifier together with `program_hash`, and checked by
+		/// `Verifier::check_verifying_key` when uploaded, e.g. it's required by the Groth16 and
+		/// PLONK verifiers, and must be a compressed arkworks `VerifyingKey` on the zkapp's curve
+		/// or `PlonkVerifyingKey`.
+		/// The key is set instantly only if the zkapp has no key yet. A key is replaced the same as
+		/// by `propose_upgrade` with the zkapp's program and the new key, by `enact_upgrade` after
+		/// `UpgradeDelay`, so users can exit before batches are verified with the new key, the
+		/// upgrade proposed replaces the former one.
+		///
+		/// Emits `SetVerifyingKey` event when the key is set, `UpgradeProposed` event when it's
+		/// proposed to replace the former key.
+		///
+		/// Weight: `O(K)` where `K` is the length of the verifying key
+		#[pallet::weight(T::WeightInfo::set_verifying_key(verifying_key.len() as u32))]
+		pub fn set_verifying_key(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			verifying_key: Vec<u8>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			let verifying_key: BoundedVec<u8, T::MaxVerifyingKeyLen> =
+				verifying_key.try_into().map_err(|_| Error::<T, I>::VerifyingKeyTooLong)?;
+
+			T::Verifiers::check_verifying_key(&zkapp.zkvm_type, &verifying_key)
+				.map_err(|_| Error::<T, I>::InvalidVerifyingKey)?;
+
+			if VerifyingKeys::<T, I>::contains_key(zkapp_id) {
+				Self::schedule_upgrade(
+					zkapp_id,
+					zkapp.program_hash,
+					zkapp.zkvm_type,
+					Some(verifying_key),
+				);
+			} else {
+				VerifyingKeys::<T, I>::insert(zkapp_id, verifying_key);
+				Self::deposit_event(Event::SetVerifyingKey(zkapp_id));
+			}
+			Ok(())
+		}
+
+		/// Replace the submitters of one zkapp with `submitter`, can only be called by owner of
+		/// the zkapp.
+		///
+		/// The bonds of the former submitters start unbonding, the new submitter must
+		/// `bond_submitter` before it can submit batches. It fails once the submitters stall,
+		/// see `set_inactive`.
+		///
+		/// Emits `ChangeSubmitter` event when successful.
+		///
+		/// Weight: `O(S)` where S is `MaxSubmitters`
+		#[pallet::weight(T::WeightInfo::change_submitter(T::MaxSubmitters::get()))]
+		pub fn change_submitter(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			submitter: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			Self::ensure_not_stalled(zkapp_id)?;
+			let submitter = T::Lookup::lookup(submitter)?;
+			let submitters = SubmittersOf::<T, I>::try_from(vec![submitter.clone()])
+				.map_err(|_| Error::<T, I>::BoundedVecInvalid)?;
+
+			Self::unbond_submitters(zkapp_id, &zkapp.submitters);
+			zkapp.submitters = submitters;
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::deposit_event(Event::ChangeSubmitter(zkapp_id, submitter));
+
+			Ok(())
+		}
+
+		/// Add a submitter of one zkapp, can only be called by owner of the zkapp.
+		///
+		/// The submitter must `bond_submitter` before it can submit batches, it is the last one in
+		/// the rotation.
+		///
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `submitter`: the submitter added.
+		///
+		/// Emits `SubmitterAdded` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::add_submitter())]
+		pub fn add_submitter(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			submitter: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			let submitter = T::Lookup::lookup(submitter)?;
+			ensure!(!zkapp.submitters.contains(&submitter), Error::<T, I>::DuplicateSubmitter);
+
+			zkapp
+				.submitters
+				.try_push(submitter.clone())
+				.map_err(|_| Error::<T, I>::SubmittersLimitExceed)?;
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::deposit_event(Event::SubmitterAdded(zkapp_id, submitter));
+			Ok(())
+		}
+
+		/// Remove a submitter of one zkapp, can only be called by owner of the zkapp.
+		///
+		/// The bond of the submitter starts unbonding, the submitters after it move forward in
+		/// the rotation. It fails once the submitters stall, see `set_inactive`.
+		///
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `submitter`: the submitter removed.
+		///
+		/// Emits `SubmitterRemoved` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::remove_submitter())]
+		pub fn remove_submitter(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			submitter: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			Self::ensure_not_stalled(zkapp_id)?;
+			let submitter = T::Lookup::lookup(submitter)?;
+			let index = zkapp
+				.submitters
+				.iter()
+				.position(|who| *who == submitter)
+				.ok_or(Error::<T, I>::NotSubmitter)?;
+			ensure!(zkapp.submitters.len() > 1, Error::<T, I>::LastSubmitter);
+
+			zkapp.submitters.remove(index);
+			Self::unbond_submitter(zkapp_id, &submitter);
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::deposit_event(Event::SubmitterRemoved(zkapp_id, submitter));
+			Ok(())
+		}
+
+		/// Set or remove the rotation of the submitters of one zkapp, can only be called by
+		/// owner of the zkapp.
+		///
+		/// With a rotation, only the leader of the current slot can submit batches, unless no
+		/// batch has been accepted for its `grace_period`, see `SubmitterRotation`. Without it,
+		/// any submitter can.
+		///
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `rotation`: the rotation, `None` to remove it.
+		///
+		/// Emits `SubmitterRotationSet` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::set_submitter_rotation())]
+		pub fn set_submitter_rotation(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			rotation: Option<SubmitterRotationOf<T>>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			ensure!(
+				rotation.as_ref().map_or(true, |rotation| !rotation.slot_length.is_zero()),
+				Error::<T, I>::InvalidRotation
+			);
+
+			SubmitterRotations::<T, I>::set(zkapp_id, rotation.clone());
+			Self::deposit_event(Event::SubmitterRotationSet(zkapp_id, rotation));
+			Ok(())
+		}
+
+		/// Set the zkapp is inactive, can only be called by owner of the zkapp.
+		/// If be called, only `exit` is allowed for the zkapp, the bonds of the submitters start
+		/// unbonding, and the pending batch of the zkapp is abandoned.
+		///
+		/// If a zkapp does not submit batch txs or handle L1 operations for a long time, it is
+		/// also set inactive by the liveness watchdog, see `MaxBatchInterval` and
+		/// `MaxL1OperationAge`, and the bonds of the submitters are slashed. The owner can't set
+		/// it inactive (or change its submitters) once they stall, so the bonds can't escape.
+		///
+		/// If a zkapp has a fraud program or bugs, `ForceOrigin` can set it inactive by
+		/// `force_set_inactive`, so that users can fully exit it to withdraw their assets.
+		///
+		/// Emits `SetInactive` event when successful.
+		///
+		/// Weight: `O(S)` where S is `MaxSubmitters`
+		#[pallet::weight(T::WeightInfo::set_inactive(T::MaxSubmitters::get()))]
+		pub fn set_inactive(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			Self::ensure_not_stalled(zkapp_id)?;
+			zkapp.is_inactive = true;
+			Self::unbond_submitters(zkapp_id, &zkapp.submitters);
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::stop_batches(zkapp_id);
+			Self::deposit_event(Event::SetInactive(zkapp_id));
+			Ok(())
+		}
+
+		/// Propose to transfer the ownership of one zkapp, can only be called by owner of the
+		/// zkapp.
+		///
+		/// The new owner (e.g. a multisig or DAO account) becomes the owner when it calls
+		/// `accept_ownership`, a proposal replaces the former one.
+		///
+		/// - `origin`: the sender who is the zkapp' owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `new_owner`: the new owner of the zkapp.
+		///
+		/// Emits `OwnershipTransferProposed` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::transfer_ownership())]
+		pub fn transfer_ownership(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			new_owner: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			let owner = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(owner == zkapp.owner, Error::<T, I>::NotOwner);
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			let new_owner = T::Lookup::lookup(new_owner)?;
+
+			PendingOwners::<T, I>::insert(zkapp_id, new_owner.clone());
+			Self::deposit_event(Event::OwnershipTransferProposed(zkapp_id, owner, new_owner));
+			Ok(())
+		}
+
+		/// Accept the ownership of one zkapp, can only be called by the account the ownership
+		/// is being transferred to by `transfer_ownership`.
+		///
+		/// The deposit of the zkapp's metadata stays reserved from its depositor.
+		///
+		/// - `origin`: the sender who is the new owner.
+		/// - `zkapp_id`: the id of the zkapp.
+		///
+		/// Emits `OwnershipTransferred` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::accept_ownership())]
+		pub fn accept_ownership(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let new_owner = ensure_signed(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			let pending_owner =
+				PendingOwners::<T, I>::get(zkapp_id).ok_or(Error::<T, I>::NoPendingOwner)?;
+			ensure!(new_owner == pending_owner, Error::<T, I>::NotPendingOwner);
+			let old_owner = sp_std::mem::replace(&mut zkapp.owner, new_owner.clone());
+
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			PendingOwners::<T, I>::remove(zkapp_id);
+			Self::deposit_event(Event::OwnershipTransferred(zkapp_id, old_owner, new_owner));
+			Ok(())
+		}
+
+		/// Set one zkapp inactive without its owner, can only be called by `ForceOrigin`.
+		///
+		/// With `slash`, the zkapp is forced inactive the same as by the liveness watchdog, and
+		/// the bonds of its submitters are slashed, e.g. when they are found malicious. Without
+		/// it, the same as `set_inactive`, the bonds of the submitters start unbonding.
+		///
+		/// - `origin`: the `ForceOrigin`.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `slash`: whether to slash the bonds of the submitters.
+		///
+		/// Emits `ForcedInactive` event with `slash`, `SetInactive` event without it, when
+		/// successful.
+		///
+		/// Weight: `O(S)` where S is `MaxSubmitters`
+		#[pallet::weight(T::WeightInfo::force_set_inactive(T::MaxSubmitters::get()))]
+		pub fn force_set_inactive(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			slash: bool,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			if slash {
+				Self::force_inactive(zkapp_id, InactiveReason::Forced);
+				return Ok(())
+			}
+			zkapp.is_inactive = true;
+			Self::unbond_submitters(zkapp_id, &zkapp.submitters);
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::stop_batches(zkapp_id);
+			Self::deposit_event(Event::SetInactive(zkapp_id));
+			Ok(())
+		}
+
+		/// Replace the submitters of one zkapp with `submitter` without its owner, can only be
+		/// called by `ForceOrigin`.
+		///
+		/// The same as `change_submitter`, the bonds of the former submitters start unbonding.
+		///
+		/// - `origin`: the `ForceOrigin`.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `submitter`: the new submitter.
+		///
+		/// Emits `ChangeSubmitter` event when successful.
+		///
+		/// Weight: `O(S)` where S is `MaxSubmitters`
+		#[pallet::weight(T::WeightInfo::force_change_submitter(T::MaxSubmitters::get()))]
+		pub fn force_change_submitter(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			submitter: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			let submitter = T::Lookup::lookup(submitter)?;
+			let submitters = SubmittersOf::<T, I>::try_from(vec![submitter.clone()])
+				.map_err(|_| Error::<T, I>::BoundedVecInvalid)?;
+
+			Self::unbond_submitters(zkapp_id, &zkapp.submitters);
+			zkapp.submitters = submitters;
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::deposit_event(Event::ChangeSubmitter(zkapp_id, submitter));
+			Ok(())
+		}
+
+		/// Remove a asset supported by a zkapp, can only be called by `ForceOrigin`.
+		///
+		/// Users can't deposit or move in the asset any more, the balances of the asset can
+		/// still be withdrawn (or forced withdrawn, or moved out) and are paid when users exit.
+		///
+		/// - `origin`: the `ForceOrigin`.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `asset`: the asset removed.
+		///
+		/// Emits `RemoveAssetSupport` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::remove_asset_support())]
+		pub fn remove_asset_support(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			asset: AssetOf<T, I>,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			let index = zkapp
+				.supported_assets
+				.iter()
+				.position(|supported| *supported == asset)
+				.ok_or(Error::<T, I>::NotSupportAsset)?;
+
+			zkapp.supported_assets.remove(index);
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			Self::deposit_event(Event::RemoveAssetSupport(zkapp_id, asset));
+			Ok(())
+		}
+
+		/// Transfer the ownership of one zkapp, can only be called by `ForceOrigin`.
+		///
+		/// The deposit of the zkapp's metadata stays reserved from its depositor, and the
+		/// ownership transfer proposed by the owner is cancelled.
+		///
+		/// - `origin`: the `ForceOrigin`.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `new_owner`: the new owner of the zkapp.
+		///
+		/// Emits `OwnershipTransferred` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::force_transfer_ownership())]
+		pub fn force_transfer_ownership(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			new_owner: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let mut zkapp =
+				Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			let new_owner = T::Lookup::lookup(new_owner)?;
+			let old_owner = sp_std::mem::replace(&mut zkapp.owner, new_owner.clone());
+
+			Zkapps::<T, I>::insert(zkapp_id, zkapp);
+			PendingOwners::<T, I>::remove(zkapp_id);
+			Self::deposit_event(Event::OwnershipTransferred(zkapp_id, old_owner, new_owner));
+			Ok(())
+		}
+
+		/// Clear the metadata of one zkapp, can only be called by `ForceOrigin`, e.g. when it's
+		/// misleading.
+		///
+		/// The deposit of the metadata is unreserved to its depositor.
+		///
+		/// - `origin`: the `ForceOrigin`.
+		/// - `zkapp_id`: the id of the zkapp.
+		///
+		/// Emits `MetadataCleared` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::force_clear_metadata())]
+		pub fn force_clear_metadata(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(Zkapps::<T, I>::contains_key(zkapp_id), Error::<T, I>::NoProgram);
+
+			Self::remove_metadata(zkapp_id)
+		}
+
+		/// Reserve the bond of a submitter of one zkapp, can only be called by the submitter.
+		///
+		/// The bond is `SubmitterBond`, it starts unbonding when the submitter is removed or the
+		/// zkapp is set inactive by the owner, and is slashed when the zkapp is forced inactive
+		/// by the liveness watchdog.
+		///
+		/// Emits `SubmitterBonded` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::bond_submitter())]
+		pub fn bond_submitter(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let submitter = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			ensure!(zkapp.submitters.contains(&submitter), Error::<T, I>::NotSubmitter);
+			ensure!(
+				!SubmitterBonds::<T, I>::contains_key(zkapp_id, &submitter),
+				Error::<T, I>::AlreadyBonded
+			);
+
+			let amount = T::SubmitterBond::get();
+			T::Currency::reserve(&submitter, amount)?;
+			SubmitterBonds::<T, I>::insert(
+				zkapp_id,
+				&submitter,
+				Bond { who: submitter.clone(), amount },
+			);
+
+			Self::deposit_event(Event::SubmitterBonded(zkapp_id, submitter, amount));
+			Ok(())
+		}
+
+		/// Withdraw the bond of a former submitter of one zkapp, after `UnbondingPeriod` since
+		/// the bond is unbonded.
+		///
+		/// Emits `SubmitterUnbonded` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::withdraw_unbonded())]
+		pub fn withdraw_unbonded(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let unbonding = Unbondings::<T, I>::try_get(zkapp_id, who.clone())
+				.map_err(|_| Error::<T, I>::NoUnbonding)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >= unbonding.unlock_at,
+				Error::<T, I>::StillUnbonding
+			);
+
+			T::Currency::unreserve(&who, unbonding.amount);
+			Unbondings::<T, I>::remove(zkapp_id, who.clone());
+
+			Self::deposit_event(Event::SubmitterUnbonded(zkapp_id, who, unbonding.amount));
+			Ok(())
+		}
+
+		/// Deposit asset to a zkapp, it is a L1 transaction, and trigger `Deposit` operation.
+		///
+		/// Save `Deposit` operation into the L1 operations queue (`L1Operations`), the zkapp's
+		/// program (off-chain) should read the queue as the program's inputs when execution.
+		/// The asset will be added to the user's assets of the pallet when `submit_batch` is
+		/// called.
+		///
+		/// Emits `Deposited` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::deposit())]
+		pub fn deposit(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			asset_value: AssetValueOf<T, I>,
+		) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			let asset = asset_value.clone().into();
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			ensure!(zkapp.supported_assets.contains(&asset), Error::<T, I>::NotSupportAsset);
+
+			Self::user_deposit(user.clone(), asset_value.clone())?;
+
+			Self::push_user_l1_operation(
+				zkapp_id,
+				&user,
+				Operation::Deposit(user.clone(), asset_value.clone()),
+			)?;
+
+			Self::deposit_event(Event::Deposited(zkapp_id, user, asset_value));
+
+			Ok(())
+		}
+
+		/// Withdraw asset from a zkapp, it is a L1 transaction, and trigger `Withdraw` operation.
+		///
+		/// Save `Withdraw` operation into the L1 operations queue (`L1Operations`), the zkapp's
+		/// program (off-chain) should read the queue as the program's inputs when execution.
+		/// The asset will be added to the user's pending withdrawals when `submit_batch` is
+		/// called, and transferred to the user by `claim_withdrawal`.
+		///
+		/// Emits `Withdrawed` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::withdraw())]
+		pub fn withdraw(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			asset_value: AssetValueOf<T, I>,
+		) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			ensure!(!asset_value.is_zero(), Error::<T, I>::ZeroAmount);
+
+			// check user balance
+			ensure!(
+				Self::check_has_enough_asset(zkapp_id, &user, &asset_value),
+				Error::<T, I>::NoEnoughAssets
+			);
+
+			Self::push_user_l1_operation(
+				zkapp_id,
+				&user,
+				Operation::Withdraw(user.clone(), asset_value.clone()),
+			)?;
+
+			Self::deposit_event(Event::Withdrawed(zkapp_id, user, asset_value));
+			Ok(())
+		}
+
+		/// Force a withdrawal from a zkapp, the same as `withdraw` (charged the same fee), but the
+		/// `Withdraw` operation must be included in time.
+		///
+		/// The deadline of the withdrawal is `ForcedWithdrawalDelay` blocks later. If a batch
+		/// submitted after the deadline doesn't include the operation, the batch is not applied
+		/// and the zkapp is forced inactive (with its submitter's bond slashed), so the user can
+		/// exit from it.
+		///
+		/// Emits `ForcedWithdrawal` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::force_withdraw())]
+		pub fn force_withdraw(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			asset_value: AssetValueOf<T, I>,
+		) -> DispatchResult {
 			let user = ensure_signed(origin)?;
-			let zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
+			ensure!(!asset_value.is_zero(), Error::<T, I>::ZeroAmount);
+			ensure!(
+				Self::check_has_enough_asset(zkapp_id, &user, &asset_value),
+				Error::<T, I>::NoEnoughAssets
+			);
+
+			let position = Self::push_user_l1_operation(
+				zkapp_id,
+				&user,
+				Operation::Withdraw(user.clone(), asset_value.clone()),
+			)?;
+			let deadline = frame_system::Pallet::<T>::block_number()
+				.saturating_add(T::ForcedWithdrawalDelay::get());
+			Self::push_forced_withdrawal(zkapp_id, ForcedWithdrawal { position, deadline });
+
+			Self::deposit_event(Event::ForcedWithdrawal(zkapp_id, user, asset_value, deadline));
+			Ok(())
+		}
+
+		/// Move asset from a zkapp to another zkapp, it is a L1 transaction, and trigger `Move`
+		/// operation.
+		///
+		/// Save `Move` operation into the L1 operations queue (`L1Operations`), the zkapp's
+		/// program (off-chain) should read the queue as the program's inputs when execution.
+		/// The asset will be moved when `submit_batch` is called.
+		///
+		/// Emits `Move` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::move_asset())]
+		pub fn move_asset(
+			origin: OriginFor<T>,
+			from_zkapp_id: ZkappId,
+			to_zkapp_id: ZkappId,
+			asset_value: AssetValueOf<T, I>,
+		) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			let from_zkapp =
+				Zkapps::<T, I>::try_get(from_zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			let to_zkapp =
+				Zkapps::<T, I>::try_get(to_zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			let asset = asset_value.clone().into();
+			ensure!(from_zkapp_id != to_zkapp_id, Error::<T, I>::SameZkapp);
+			ensure!(!from_zkapp.is_inactive, Error::<T, I>::Inactive);
+			ensure!(!to_zkapp.is_inactive, Error::<T, I>::Inactive);
+			// the asset may be removed from the source zkapp, only the destination must support it
+			ensure!(to_zkapp.supported_assets.contains(&asset), Error::<T, I>::NotSupportAsset);
+
+			// check user balance
+			ensure!(
+				Self::check_has_enough_asset(from_zkapp_id, &user, &asset_value),
+				Error::<T, I>::NoEnoughAssets
+			);
+
+			Self::push_user_l1_operation(
+				from_zkapp_id,
+				&user,
+				Operation::Move(user.clone(), to_zkapp_id, asset_value.clone()),
+			)?;
+
+			Self::deposit_event(Event::MoveAsset(from_zkapp_id, to_zkapp_id, user, asset_value));
+			Ok(())
+		}
+
+		/// User exit a zkapp fully, it can be called only when the status of this program is
+		/// inactive.
+		///
+		/// When called, user's assets (saved in `ZkappsBalances` and `ZkappsItems`) are transfered
+		/// to user, the operations of a pending batch abandoned when the zkapp was set inactive
+		/// are not included,
+		/// if L1 operations queue of the zkapp has user's `Deposit` operations, they also are
+		/// transfered to user, including the ones of an abandoned batch not executed yet. At most
+		/// `L1OperationsPageSize` operations are visited, the rest are refunded by
+		/// `refund_l1_deposits`.
+		///
+		/// Emits `Exit` event when successful.
+		///
+		/// Weight: `O(max(m, n))` where
+		/// - `m = account.assets.len()`
+		/// - `n = L1OperationsPageSize`
+		#[pallet::weight(T::WeightInfo::exit(T::L1OperationsPageSize::get()))]
+		pub fn exit(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
 			ensure!(zkapp.is_inactive, Error::<T, I>::NotInactive);
 			ensure!(
-				ZkappsExit::<T, I>::try_get(program_hash, user.clone()).is_err(),
+				ZkappsExit::<T, I>::try_get(zkapp_id, user.clone()).is_err(),
 				<Error<T, I>>::HasExit
 			);
-
 			// withdraw from user balance
-			let account = ZkappsAccounts::<T, I>::try_get(program_hash, user.clone());
-			if let Ok(account) = account {
-				for asset_value in account.assets {
-					Self::user_withdraw(user.clone(), asset_value.clone())?;
-				}
+			for asset_value in Self::take_user_assets(zkapp_id, &user)? {
+				Self::reduce_custody(zkapp_id, &asset_value)?;
+				Self::user_withdraw(user.clone(), asset_value)?;
 			}
 
-			// withdraw from L1 operations
-			for op in zkapp.l1_operations {
-				match op {
-					Operation::Deposit(op_user, asset_value) if op_user == user => {
-						Self::user_withdraw(user.clone(), asset_value.clone())?;
-					},
-					_ => (),
-				}
+			// withdraw from the first page of L1 operations
+			let head = L1OperationsQueue::<T, I>::get(zkapp_id).head;
+			Self::refund_l1_deposits_from(&user, zkapp_id, head)?;
+
+			ZkappsExit::<T, I>::insert(zkapp_id, user.clone(), true);
+
+			Self::deposit_event(Event::Exit(zkapp_id, user));
+			Ok(())
+		}
+
+		/// User exit a zkapp fully with a Merkle proof of the user's leaf in the zkapp's state
+		/// tree, it can be called only when the status of this program is inactive.
+		///
+		/// Unlike `exit`, the assets are not read from `ZkappsBalances` and `ZkappsItems`, but from
+		/// the `account` leaf, which must be included in the tree of the zkapp's last
+		/// `state_root`. The leaf is the SCALE-encoded `Account`, hashed by `Config::StateTree`.
+		/// The assets paid are debited from the zkapp's `ZkappsCustody` (and `ZkappsCustodyItems`),
+		/// the exit fails if the zkapp doesn't hold them, and the user's balances in
+		/// `ZkappsBalances` and `ZkappsItems` are removed.
+		/// The user's `Deposit` operations in L1 operations queue are also transfered to user, the
+		/// same as `exit`.
+		///
+		/// - `origin`: the user of the `account` leaf.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `account`: the user's leaf in the state tree.
+		/// - `leaf_index`: the position of the leaf in the state tree.
+		/// - `proof`: the siblings of the nodes from the leaf to the root.
+		///
+		/// Emits `Exit` event when successful.
+		///
+		/// Weight: `O(max(m, n, p))` where
+		/// - `m = account.assets.len()`
+		/// - `n = L1OperationsPageSize`
+		/// - `p = proof.len()`
+		#[pallet::weight(T::WeightInfo::exit_with_proof(
+			proof.len() as u32,
+			T::L1OperationsPageSize::get()
+		))]
+		pub fn exit_with_proof(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			account: AccountOf<T, I>,
+			leaf_index: u64,
+			proof: Vec<state_tree::Node>,
+		) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			let zkapp = Zkapps::<T, I>::try_get(zkapp_id).map_err(|_| Error::<T, I>::NoProgram)?;
+			ensure!(zkapp.is_inactive, Error::<T, I>::NotInactive);
+			ensure!(
+				ZkappsExit::<T, I>::try_get(zkapp_id, user.clone()).is_err(),
+				<Error<T, I>>::HasExit
+			);
+			ensure!(account.user == user, Error::<T, I>::InvalidStateProof);
+			ensure!(
+				T::StateTree::verify_proof(
+					zkapp.state_root.as_ref(),
+					&account.encode(),
+					leaf_index,
+					&proof
+				),
+				Error::<T, I>::InvalidStateProof
+			);
+
+			// withdraw from the user's leaf instead of the user's balances, at most the assets
+			// held by the zkapp
+			Self::take_user_assets(zkapp_id, &user)?;
+			for asset_value in account.assets {
+				Self::reduce_custody(zkapp_id, &asset_value)?;
+				Self::user_withdraw(user.clone(), asset_value)?;
 			}
 
-			ZkappsExit::<T, I>::insert(program_hash, user.clone(), true);
+			// withdraw from the first page of L1 operations
+			let head = L1OperationsQueue::<T, I>::get(zkapp_id).head;
+			Self::refund_l1_deposits_from(&user, zkapp_id, head)?;
+
+			ZkappsExit::<T, I>::insert(zkapp_id, user.clone(), true);
 
-			Self::deposit_event(Event::Exit(program_hash, user));
+			Self::deposit_event(Event::Exit(zkapp_id, user));
 			Ok(())
 		}
 
+		/// Refund the next page of an exited user's `Deposit` operations in the L1 operations
+		/// queue of a zkapp, which are not refunded by `exit` (or the previous call).
+		///
+		/// - `origin`: the exited user.
+		/// - `zkapp_id`: the id of the zkapp.
+		///
+		/// Emits `L1DepositsRefunded` event when successful.
+		///
+		/// Weight: `O(n)` where `n = L1OperationsPageSize`
+		#[pallet::weight(T::WeightInfo::refund_l1_deposits(T::L1OperationsPageSize::get()))]
+		pub fn refund_l1_deposits(origin: OriginFor<T>, zkapp_id: ZkappId) -> DispatchResult {
+			let user = ensure_signed(origin)?;
+			let from = ExitRefundCursors::<T, I>::take(zkapp_id, user.clone())
+				.ok_or(Error::<T, I>::NoL1DepositsToRefund)?;
+
+			Self::refund_l1_deposits_from(&user, zkapp_id, from)
+		}
+
 		/// Submit a batch for a zkapp, can only be called by submitter of the zkapp.
 		///
 		/// A zkapp's program (off-chain component) collects L1 operations from
-		/// `zkapp.l1_operations` or events onchain and L2 transactions from users (interact with
+		/// the `L1Operations` queue or events onchain and L2 transactions from users (interact with
 		/// off-chain component), Every execution of a zkapp's program, use `old_state_root` as the
 		/// public inputs, l1_operations, L2 transactions and state tree as secret inputs,
 		/// the outputs of a execution should include new_state_root,
 		/// all operations and the number of the l1_operations included.
 		///
 		/// - `origin`: submitter of the zkapp.
-		/// - `program_hash`: program hash of the zkapp.
+		/// - `zkapp_id`: the id of the zkapp.
 		/// - `old_state_root`: state root of state tree before execution.
 		/// - `new_state_root`: state root of state tree after execution.
 		/// - `l1_operations_pos`: the number of the L1 operations included in the execution.
 		/// - `operations`: all operations generated by the execution of zkapp's program this time.
 		/// - `zk_proof`: the proof generated during program execution.
+		/// - `zk_outputs`: the raw public outputs of the execution (e.g. Miden's `StackOutputs`).
+		///
+		/// The outputs are bound to the batch by the [`ProofOutput::commitment`] of
+		/// `new_state_root`, `l1_operations_pos` and `operations`, the pallet recomputes it and the
+		/// verifier checks it against the verified outputs, so the operations executed here are
+		/// exactly the ones proven. The hash chain of the L1 operations consumed (see
+		/// [`L1OperationsHash`]) is one of the public inputs, so the proof attests which L1
+		/// operations were processed.
+		///
+		/// The operations are executed at once, a batch too large for one block can be committed
+		/// by `commit_batch` and executed in chunks by `execute_batch`.
 		///
 		/// Emits `SubmitBatch` event when successful.
 		///
 		/// Weight: `O(operations.len())`
-        #[allow(clippy::too_many_arguments)]
+		#[allow(clippy::too_many_arguments)]
 		#[pallet::weight(T::WeightInfo::submit_batch(operations.len() as u32))]
 		pub fn submit_batch(
 			origin: OriginFor<T>,
-			program_hash: T::ProgramHash,
+			zkapp_id: ZkappId,
 			old_state_root: T::StateRoot,
 			new_state_root: T::StateRoot,
 			l1_operations_pos: u32,
 			operations: Vec<OperationOf<T, I>>,
 			zk_proof: Vec<u8>,
-            zk_outputs: Option<Vec<u8>>,
+			zk_outputs: Option<Vec<u8>>,
 		) -> DispatchResult {
 			let submitter = ensure_signed(origin)?;
-			let mut zkapp =
-				Zkapps::<T, I>::try_get(program_hash).map_err(|_| Error::<T, I>::NoProgram)?;
-			ensure!(!zkapp.is_inactive, Error::<T, I>::Inactive);
-			ensure!(zkapp.submitter == submitter, Error::<T, I>::NotSubmitter);
-			ensure!(zkapp.state_root == old_state_root, Error::<T, I>::InvalidStateRoot);
+			let operations = Self::commit(
+				submitter,
+				zkapp_id,
+				old_state_root,
+				new_state_root,
+				l1_operations_pos,
+				operations,
+				zk_proof,
+				zk_outputs,
+			)?;
+			let operations = match operations {
+				Some(operations) => operations,
+				None => return Ok(()),
+			};
+			Self::execute_operations(zkapp_id, 0, l1_operations_pos, &operations)?;
 
-			// the program of the zkapp must handle the l1_operations queue
-			// and the front operations should match the l1_operations queue
-			ensure!(
-				u32::try_from(zkapp.l1_operations.len())
-					.map_err(|_| Error::<T, I>::InvalidBatchParams)? >=
-					l1_operations_pos,
-				Error::<T, I>::InvalidBatchParams
-			);
-			for op_index in 0..l1_operations_pos {
-				ensure!(
-					zkapp.l1_operations[op_index as usize] == operations[op_index as usize],
-					Error::<T, I>::InvalidBatchParams
-				);
-			}
+			Self::deposit_event(Event::SubmitBatch(
+				zkapp_id,
+				old_state_root,
+				new_state_root,
+				operations,
+			));
+			Ok(())
+		}
 
-			// println!("{:?}, {:?}, {:?}", l1_operations_pos, operations, zkapp.l1_operations);
-
-			// verify the zk proof
-			let zk_inputs = old_state_root.as_ref();
-            let zk_outputs = match zk_outputs {
-                Some(outs) => outs,
-                None => ProofOutput {
-                    operations: operations.clone(),
-                    state_root: new_state_root,
-                    l1_operations_pos,
-                }.encode()
-            };
-			let zk_outputs = zk_outputs.as_ref();
-
-            // println!("pallet zk_outputs: {:?}", zk_outputs);
-
-			match zkapp.zkvm_type {
-				ZkvmType::Fake => {
-					FakeVerifier::verify(program_hash.as_ref(), zk_inputs, &zk_proof, zk_outputs)
-						.map_err(|_| Error::<T, I>::InvalidProof)?;
-				},
-				ZkvmType::Miden => {
-					MidenVerifier::verify(program_hash.as_ref(), zk_inputs, &zk_proof, zk_outputs)
-						.map_err(|_| Error::<T, I>::InvalidProof)?;
-				},
+		/// Commit a batch for a zkapp, can only be called by submitter of the zkapp.
+		///
+		/// The same as `submit_batch`, the proof is verified and the new state root is recorded,
+		/// but the operations are not executed, only their commitment (see
+		/// [`operations_commitment`]) is saved, so a batch too large for one block can be
+		/// executed in chunks by `execute_batch`. The zkapp can't commit a new batch until the
+		/// pending one is fully executed.
+		///
+		/// The parameters are the same as `submit_batch`.
+		///
+		/// Emits `BatchCommitted` event with the operations when successful.
+		///
+		/// Weight: `O(operations.len())`
+		#[allow(clippy::too_many_arguments)]
+		#[pallet::weight(T::WeightInfo::commit_batch(operations.len() as u32))]
+		pub fn commit_batch(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			old_state_root: T::StateRoot,
+			new_state_root: T::StateRoot,
+			l1_operations_pos: u32,
+			operations: Vec<OperationOf<T, I>>,
+			zk_proof: Vec<u8>,
+			zk_outputs: Option<Vec<u8>>,
+		) -> DispatchResult {
+			let submitter = ensure_signed(origin)?;
+			let operations = Self::commit(
+				submitter,
+				zkapp_id,
+				old_state_root,
+				new_state_root,
+				l1_operations_pos,
+				operations,
+				zk_proof,
+				zk_outputs,
+			)?;
+			let operations = match operations {
+				Some(operations) => operations,
+				None => return Ok(()),
 			};
 
-			// remove the l1_operations which are executed in the batch
-			zkapp.l1_operations =
-				BoundedVec::try_from(zkapp.l1_operations[l1_operations_pos as usize..].to_vec())
-					.map_err(|_| Error::<T, I>::InvalidBatchParams)?;
-
-			// execute operations
-			for (i, op) in operations.iter().enumerate() {
-				match op {
-					Operation::Deposit(user, asset_value) => {
-						// only execution of L1 Deposit transaction can Deposit Operation
-						ensure!(i < l1_operations_pos as usize, Error::<T, I>::InvalidBatchParams);
-
-						let mut account: AccountOf<T, I>;
-						if let Ok(_account) = ZkappsAccounts::<T, I>::try_get(program_hash, user) {
-							account = _account;
-						} else {
-							account = Account { user: user.clone(), assets: Default::default() };
-						}
-						Self::add_user_asset(&mut account, asset_value)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, user, account);
-					},
-					Operation::Withdraw(user, asset_value) => {
-						let mut account = ZkappsAccounts::<T, I>::try_get(program_hash, user)
-							.map_err(|_| Error::<T, I>::NoAccount)?;
-						Self::user_withdraw(user.clone(), asset_value.clone())?;
-						Self::reduce_user_asset(&mut account, asset_value)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, user, account);
-					},
-					Operation::Move(user, to_program_hash, asset_value) => {
-						// reduce user asset_value
-						let mut account = ZkappsAccounts::<T, I>::try_get(program_hash, user)
-							.map_err(|_| Error::<T, I>::NoAccount)?;
-						Self::reduce_user_asset(&mut account, asset_value)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, user, account);
-
-						// add deposit L1 operation and deposit event to to_program
-						let mut to_zkapp = Zkapps::<T, I>::try_get(to_program_hash)
-							.map_err(|_| Error::<T, I>::NoProgram)?;
-						to_zkapp
-							.l1_operations
-							.try_push(Operation::Deposit(user.clone(), asset_value.clone()))
-							.map_err(|_| Error::<T, I>::L1OperationLimitExceed)?;
-						Zkapps::<T, I>::insert(to_program_hash, to_zkapp);
-						Self::deposit_event(Event::Deposited(
-							*to_program_hash,
-							user.clone(),
-							asset_value.clone(),
-						));
-					},
-					Operation::Transfer(from_user, to_user, asset_value) => {
-						let mut from_account =
-							ZkappsAccounts::<T, I>::try_get(program_hash, from_user)
-								.map_err(|_| Error::<T, I>::NoAccount)?;
-
-						let mut to_account: AccountOf<T, I>;
-						if let Ok(account) = ZkappsAccounts::<T, I>::try_get(program_hash, to_user)
-						{
-							to_account = account;
-						} else {
-							to_account =
-								Account { user: to_user.clone(), assets: Default::default() };
-						}
-						Self::reduce_user_asset(&mut from_account, asset_value)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, from_user, from_account);
-
-						Self::add_user_asset(&mut to_account, asset_value)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, to_user, to_account);
-					},
-					Operation::Swap(user_1, asset_value_1, user_2, asset_value_2) => {
-						// modify user_1 assets
-						let mut account_1 = ZkappsAccounts::<T, I>::try_get(program_hash, user_1)
-							.map_err(|_| Error::<T, I>::NoAccount)?;
-						Self::reduce_user_asset(&mut account_1, asset_value_1)?;
-						Self::add_user_asset(&mut account_1, asset_value_2)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, user_1, account_1);
-
-						// modify user_2 assets
-						let mut account_2 = ZkappsAccounts::<T, I>::try_get(program_hash, user_2)
-							.map_err(|_| Error::<T, I>::NoAccount)?;
-						Self::reduce_user_asset(&mut account_2, asset_value_2)?;
-						Self::add_user_asset(&mut account_2, asset_value_1)?;
-						ZkappsAccounts::<T, I>::insert(program_hash, user_2, account_2);
-					},
-				}
+			let commitment = operations_commitment(&operations, Default::default());
+			let len =
+				u32::try_from(operations.len()).map_err(|_| Error::<T, I>::InvalidBatchParams)?;
+			if len > 0 {
+				PendingBatches::<T, I>::insert(
+					zkapp_id,
+					PendingBatch { commitment, next: 0, len, l1_operations_pos },
+				);
 			}
 
-			// save zkapp's new_state_root
-			zkapp.state_root = new_state_root;
-			Zkapps::<T, I>::insert(program_hash, zkapp);
-
-			Self::deposit_event(Event::SubmitBatch(
-				program_hash,
+			Self::deposit_event(Event::BatchCommitted(
+				zkapp_id,
 				old_state_root,
 				new_state_root,
 				operations,
+				commitment,
 			));
 			Ok(())
 		}
+
+		/// Execute the next chunk of operations of a zkapp's pending batch, anyone can call it.
+		///
+		/// - `origin`: any signed account.
+		/// - `zkapp_id`: the id of the zkapp.
+		/// - `operations`: the next operations of the batch to execute.
+		/// - `rest`: the commitment of the operations after `operations`, the zero hash if they
+		/// are the last ones.
+		///
+		/// The commitment of `operations` followed by `rest` must be the one of the operations
+		/// not executed yet. When the last operation is executed the pending batch is removed.
+		///
+		/// Emits `BatchExecuted` event when successful.
+		///
+		/// Weight: `O(operations.len())`
+		#[pallet::weight(T::WeightInfo::execute_batch(operations.len() as u32))]
+		pub fn execute_batch(
+			origin: OriginFor<T>,
+			zkapp_id: ZkappId,
+			operations: Vec<OperationOf<T, I>>,
+			rest: OperationsCommitment,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let mut batch =
+				PendingBatches::<T, I>::get(zkapp_id).ok_or(Error::<T, I>::NoPendingBatch)?;
+			ensure!(
+				operations_commitment(&operations, rest) == batch.commitment,
+				Error::<T, I>::InvalidOperationsCommitment
+			);
+			let next = u32::try_from(operations.len())
+				.ok()
+				.and_then(|len| batch.next.checked_add(len))
+				.filter(|next| *next <= batch.len)
+				.ok_or(Error::<T, I>::InvalidOperationsCommitment)?;
+			// no operations after the last one
+			ensure!(
+				next < batch.len || rest == OperationsCommitment::default(),
+				Error::<T, I>::InvalidOperationsCommitment
+			);
+
+			Self::execute_operations(zkapp_id, batch.next, batch.l1_operations_pos, &operations)?;
+
+			let len = batch.len;
+			if next == len {
+				PendingBatches::<T, I>::remove(zkapp_id);
+			} else {
+				batch.commitment = rest;
+				batch.next = next;
+				PendingBatches::<T, I>::insert(zkapp_id, batch);
+			}
+
+			Self::deposit_event(Event::BatchExecuted(zkapp_id, next, len));
+			Ok(())
+		}
+
+		/// Claim assets withdrawn by `Withdraw` operations of batches, and transfer them from the
+		/// pallet to the user.
+		///
+		/// - `origin`: any account, the user or anyone on the user's behalf.
+		/// - `who`: the user who withdrawed the assets.
+		/// - `asset_value`: the assets to claim, no more than `PendingWithdrawals` (or
+		/// `PendingItemWithdrawals`) of the user.
+		///
+		/// Emits `WithdrawalClaimed` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[pallet::weight(T::WeightInfo::claim_withdrawal())]
+		pub fn claim_withdrawal(
+			origin: OriginFor<T>,
+			who: AccountIdLookupOf<T>,
+			asset_value: AssetValueOf<T, I>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let who = T::Lookup::lookup(who)?;
+
+			Self::reduce_pending_withdrawal(&who, &asset_value)?;
+			Self::user_withdraw(who.clone(), asset_value.clone())?;
+
+			Self::deposit_event(Event::WithdrawalClaimed(who, asset_value));
+			Ok(())
+		}
 	}
 }
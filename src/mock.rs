@@ -125,6 +125,7 @@ impl pallet_uniques::Config for Test {
 
 parameter_types! {
 	pub const OpenRollupPalletId: PalletId = PalletId(*b"openroll");
+	pub const ProtocolFeeCut: Perbill = Perbill::from_percent(10);
 }
 
 /// Add open rollup pallet.
@@ -136,11 +137,32 @@ impl Config for Test {
 	type Currency = Balances;
 	type Fungibles = Assets;
 	type Nonfungibles = Uniques;
+	type StateTree = Blake2StateTree;
 	type WeightInfo = ();
+	#[cfg(feature = "risc-zero")]
+	type Verifiers =
+		(FakeVerifier, MidenVerifier, Groth16Bn254Verifier, PlonkVerifier, RiscZeroVerifier);
+	#[cfg(not(feature = "risc-zero"))]
+	type Verifiers = (FakeVerifier, MidenVerifier, Groth16Bn254Verifier, PlonkVerifier);
 	type AssetsLimit = ConstU32<10>;
-	type AssetsItemLimit = ConstU32<11>;
+	type MaxSubmitters = ConstU32<3>;
 	type L1OperationLimit = ConstU32<300>;
+	type L1OperationsPageSize = ConstU32<2>;
 	type NonfungibleItemLimit = ConstU32<100>;
+	type MaxVerifyingKeyLen = ConstU32<1024>;
+	type MaxBatchInterval = ConstU64<100>;
+	type MaxL1OperationAge = ConstU64<50>;
+	type ForcedWithdrawalDelay = ConstU64<20>;
+	type UpgradeDelay = ConstU64<30>;
+	type SubmitterBond = ConstU64<100>;
+	type UnbondingPeriod = ConstU64<10>;
+	type StringLimit = ConstU32<50>;
+	type MetadataDepositBase = ConstU64<10>;
+	type MetadataDepositPerByte = ConstU64<1>;
+	type FeeDestination = ConstU64<9>;
+	type ProtocolFeeCut = ProtocolFeeCut;
+	type Slashed = ();
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
 	#[cfg(feature = "runtime-benchmarks")]
 	type Helper = ();
 }
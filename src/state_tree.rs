@@ -0,0 +1,156 @@
+//  Copyright 2022 Open Rollup Lab
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+//! State trees of zkapps, used to verify users' leaves against the committed `state_root`.
+
+use miden::crypto::hash::Rpo256;
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT, Keccak256};
+use sp_std::vec::Vec;
+
+/// One node of a state tree.
+pub type Node = [u8; 32];
+
+/// The maximum depth of a state tree.
+pub const MAX_DEPTH: usize = 64;
+
+/// Binary Merkle tree maintained off-chain by a zkapp, its root is the zkapp's `state_root`.
+pub trait StateTree {
+	/// Hash the data of one leaf.
+	fn hash_leaf(leaf: &[u8]) -> Node;
+
+	/// Hash two children into their parent node.
+	fn hash_nodes(left: &Node, right: &Node) -> Node;
+
+	/// Compute the root of the tree from one leaf and its Merkle proof.
+	///
+	/// - `index`: position of the leaf, its `i`th lowest bit is 0 if the node at level `i` is the
+	/// left child, or 1 if it is the right child.
+	/// - `proof`: siblings of the nodes from the leaf to the root.
+	fn compute_root(leaf: &[u8], index: u64, proof: &[Node]) -> Node {
+		let mut node = Self::hash_leaf(leaf);
+		for (level, sibling) in proof.iter().enumerate() {
+			node = if index.checked_shr(level as u32).unwrap_or(0) & 1 == 0 {
+				Self::hash_nodes(&node, sibling)
+			} else {
+				Self::hash_nodes(sibling, &node)
+			};
+		}
+		node
+	}
+
+	/// Verify `leaf` is included at `index` of the tree of `root`.
+	fn verify_proof(root: &[u8], leaf: &[u8], index: u64, proof: &[Node]) -> bool {
+		proof.len() <= MAX_DEPTH && Self::compute_root(leaf, index, proof)[..] == *root
+	}
+}
+
+/// Prefix of the leaf's preimage, separates leaves from inner nodes.
+const LEAF_PREFIX: u8 = 0;
+/// Prefix of the inner node's preimage.
+const NODE_PREFIX: u8 = 1;
+
+/// Preimage of an inner node, `NODE_PREFIX ++ left ++ right`.
+fn node_preimage(left: &Node, right: &Node) -> Vec<u8> {
+	let mut preimage = Vec::with_capacity(65);
+	preimage.push(NODE_PREFIX);
+	preimage.extend_from_slice(left);
+	preimage.extend_from_slice(right);
+	preimage
+}
+
+/// Preimage of a leaf, `LEAF_PREFIX ++ leaf`.
+fn leaf_preimage(leaf: &[u8]) -> Vec<u8> {
+	let mut preimage = Vec::with_capacity(leaf.len() + 1);
+	preimage.push(LEAF_PREFIX);
+	preimage.extend_from_slice(leaf);
+	preimage
+}
+
+/// State tree using `blake2_256`.
+pub struct Blake2StateTree;
+impl StateTree for Blake2StateTree {
+	fn hash_leaf(leaf: &[u8]) -> Node {
+		BlakeTwo256::hash(&leaf_preimage(leaf)).to_fixed_bytes()
+	}
+
+	fn hash_nodes(left: &Node, right: &Node) -> Node {
+		BlakeTwo256::hash(&node_preimage(left, right)).to_fixed_bytes()
+	}
+}
+
+/// State tree using `keccak_256`.
+pub struct KeccakStateTree;
+impl StateTree for KeccakStateTree {
+	fn hash_leaf(leaf: &[u8]) -> Node {
+		Keccak256::hash(&leaf_preimage(leaf)).to_fixed_bytes()
+	}
+
+	fn hash_nodes(left: &Node, right: &Node) -> Node {
+		Keccak256::hash(&node_preimage(left, right)).to_fixed_bytes()
+	}
+}
+
+/// State tree using Rescue Prime Optimized, the hash of Miden.
+///
+/// The leaves and inner nodes are prefixed as the other trees, and hashed as bytes with
+/// `Rpo256::hash`, a node is 4 field elements, each one is encoded as 8 little-endian bytes.
+pub struct RescuePrimeStateTree;
+impl StateTree for RescuePrimeStateTree {
+	fn hash_leaf(leaf: &[u8]) -> Node {
+		Rpo256::hash(&leaf_preimage(leaf)).as_bytes()
+	}
+
+	fn hash_nodes(left: &Node, right: &Node) -> Node {
+		Rpo256::hash(&node_preimage(left, right)).as_bytes()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Check the proofs of a tree of two leaves.
+	fn check_two_leaves<S: StateTree>() {
+		let (leaf_1, leaf_2) = (b"leaf 1".to_vec(), b"leaf 2".to_vec());
+		let (node_1, node_2) = (S::hash_leaf(&leaf_1), S::hash_leaf(&leaf_2));
+		let root = S::hash_nodes(&node_1, &node_2);
+
+		assert!(S::verify_proof(&root, &leaf_1, 0, &[node_2]));
+		assert!(S::verify_proof(&root, &leaf_2, 1, &[node_1]));
+		// wrong index
+		assert!(!S::verify_proof(&root, &leaf_1, 1, &[node_2]));
+		// wrong leaf
+		assert!(!S::verify_proof(&root, &leaf_2, 0, &[node_2]));
+		// the children of a node aren't a leaf
+		assert!(!S::verify_proof(&root, &[node_1, node_2].concat(), 0, &[]));
+	}
+
+	/// Test Blake2 state tree should work.
+	#[test]
+	fn blake2_works() {
+		check_two_leaves::<Blake2StateTree>();
+	}
+
+	/// Test Keccak state tree should work.
+	#[test]
+	fn keccak_works() {
+		check_two_leaves::<KeccakStateTree>();
+	}
+
+	/// Test Rescue Prime state tree should work.
+	#[test]
+	fn rescue_prime_works() {
+		check_two_leaves::<RescuePrimeStateTree>();
+	}
+}
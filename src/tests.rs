@@ -19,8 +19,10 @@ use crate::mock::*;
 use frame_support::{assert_noop, assert_ok, bounded_vec};
 use sp_runtime::testing::H256;
 
-use miden::{ prove, Assembler, MemAdviceProvider, ProofOptions, Program, utils::Serializable };
-use verifier::raw_inputs_to_stack_inputs;
+use miden::{
+	prove, utils::Serializable, Assembler, MemAdviceProvider, Program, ProofOptions, StackInputs,
+};
+use verifier::{output_commitment_to_elements, public_inputs_to_stack_inputs};
 
 const OWNER_ID: u64 = 1;
 const SUBMITTER_ID: u64 = 2;
@@ -36,13 +38,46 @@ fn assert_last_event(event: RuntimeEvent) {
 	frame_system::Pallet::<Test>::assert_last_event(event);
 }
 
-/// Setup a miden zkapp
-fn setup_miden_app(empty_state_root: H256) -> (Program, RuntimeOrigin, RuntimeOrigin) {
-    let miden_source = "begin push.3 push.5 add end";
+/// Miden program which only outputs the commitment of the `ProofOutput`.
+fn miden_commitment_program(
+	operations: Vec<OperationOf<Test, ()>>,
+	new_state_root: H256,
+	l1_operations_pos: u32,
+) -> Program {
+	let proof_output = ProofOutput { operations, state_root: new_state_root, l1_operations_pos };
+	let elements = output_commitment_to_elements(&proof_output.commitment());
+	let miden_source = format!(
+		"begin dropw push.{} push.{} push.{} push.{} end",
+		elements[3], elements[2], elements[1], elements[0]
+	);
+
+	let assembler = Assembler::default();
+	assembler.compile(&miden_source).unwrap()
+}
 
-    let assembler = Assembler::default();
-    let program = assembler.compile(miden_source).unwrap();
-    let program_hash = H256::from_slice(&program.hash().as_bytes());
+/// Miden stack inputs of the next batch of a zkapp, which consumes `l1_operations_pos` L1
+/// operations.
+fn miden_stack_inputs(
+	zkapp_id: ZkappId,
+	old_state_root: H256,
+	l1_operations_pos: u32,
+) -> StackInputs {
+	public_inputs_to_stack_inputs(&PublicInputs {
+		old_state_root: old_state_root.as_bytes(),
+		l1_operations_commitment: OpenRollup::l1_operations_hash(zkapp_id, l1_operations_pos)
+			.unwrap(),
+		block_number: LastBatchAt::<Test>::get(zkapp_id).unwrap(),
+	})
+	.unwrap()
+}
+
+/// Setup a miden zkapp
+fn setup_miden_app(
+	program: &Program,
+	empty_state_root: H256,
+) -> (ZkappId, RuntimeOrigin, RuntimeOrigin) {
+	let program_hash = H256::from_slice(&program.hash().as_bytes());
+	let zkapp_id = NextZkappId::<Test>::get();
 
 	let owner = RuntimeOrigin::signed(OWNER_ID);
 
@@ -57,19 +92,22 @@ fn setup_miden_app(empty_state_root: H256) -> (Program, RuntimeOrigin, RuntimeOr
 	)
 	.unwrap();
 	// check event
-	assert_last_event(Event::ZkappRegister(ZkvmType::Miden, program_hash).into());
+	assert_last_event(Event::ZkappRegister(zkapp_id, ZkvmType::Miden, program_hash).into());
+	OpenRollup::bond_submitter(RuntimeOrigin::signed(SUBMITTER_ID), zkapp_id).unwrap();
 
 	// check zkapp data
-	let zkapp = Zkapps::<Test>::try_get(program_hash).unwrap();
+	let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
+	assert_eq!(zkapp.program_hash, program_hash);
 	assert_eq!(zkapp.owner, OWNER_ID);
 	assert_eq!(zkapp.state_root, empty_state_root);
 
-	(program, owner, user)
+	(zkapp_id, owner, user)
 }
 
 /// Setup a zkapp, add fungible and nonfungible assets supports.
-fn setup_app(program_hash_u64: u64) -> (H256, RuntimeOrigin, RuntimeOrigin) {
+fn setup_app(program_hash_u64: u64) -> (ZkappId, RuntimeOrigin, RuntimeOrigin) {
 	let program_hash = H256::from_low_u64_be(program_hash_u64);
+	let zkapp_id = NextZkappId::<Test>::get();
 	let owner = RuntimeOrigin::signed(OWNER_ID);
 	let empty_state_root = H256::from_low_u64_be(0);
 
@@ -84,43 +122,49 @@ fn setup_app(program_hash_u64: u64) -> (H256, RuntimeOrigin, RuntimeOrigin) {
 	)
 	.unwrap();
 	// check event
-	assert_last_event(Event::ZkappRegister(ZkvmType::Fake, program_hash).into());
+	assert_last_event(Event::ZkappRegister(zkapp_id, ZkvmType::Fake, program_hash).into());
+
+	OpenRollup::bond_submitter(RuntimeOrigin::signed(SUBMITTER_ID), zkapp_id).unwrap();
+	// check event
+	assert_last_event(Event::SubmitterBonded(zkapp_id, SUBMITTER_ID, 100).into());
 
-	OpenRollup::add_asset_support(owner.clone(), program_hash, Asset::Fungible(ASSET_ID)).unwrap();
+	OpenRollup::add_asset_support(owner.clone(), zkapp_id, Asset::Fungible(ASSET_ID)).unwrap();
 	// check event
-	assert_last_event(Event::AddAssetSupport(program_hash, Asset::Fungible(ASSET_ID)).into());
+	assert_last_event(Event::AddAssetSupport(zkapp_id, Asset::Fungible(ASSET_ID)).into());
 
-	OpenRollup::add_asset_support(owner.clone(), program_hash, Asset::Nonfungible(COLLECTION_ID))
+	OpenRollup::add_asset_support(owner.clone(), zkapp_id, Asset::Nonfungible(COLLECTION_ID))
 		.unwrap();
 	// check event
-	assert_last_event(
-		Event::AddAssetSupport(program_hash, Asset::Nonfungible(COLLECTION_ID)).into(),
-	);
+	assert_last_event(Event::AddAssetSupport(zkapp_id, Asset::Nonfungible(COLLECTION_ID)).into());
 
 	// check zkapp data
-	let zkapp = Zkapps::<Test>::try_get(program_hash).unwrap();
+	let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
 	assert_eq!(zkapp.owner, OWNER_ID);
 	assert_eq!(zkapp.state_root, empty_state_root);
 	assert_eq!(zkapp.supported_assets.first().unwrap(), &Asset::Currency);
 
-	(program_hash, owner, user)
+	(zkapp_id, owner, user)
 }
 
-/// Register the same app duplicately.
+/// Register the same program twice, as two zkapps.
 #[test]
 fn duplicate_register_zkapp() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, owner, _user) = setup_app(PROGRAM_HASH_64);
-		assert_noop!(
-			OpenRollup::zkapp_register(
-				owner,
-				program_hash,
-				ZkvmType::Miden,
-				2,
-				H256::from_low_u64_be(0),
-			),
-			Error::<Test>::DuplicateApp
-		);
+		let (zkapp_id, owner, _user) = setup_app(PROGRAM_HASH_64);
+		let program_hash = H256::from_low_u64_be(PROGRAM_HASH_64);
+		assert_ok!(OpenRollup::zkapp_register(
+			owner,
+			program_hash,
+			ZkvmType::Miden,
+			2,
+			H256::from_low_u64_be(0),
+		));
+		// check event
+		assert_last_event(Event::ZkappRegister(zkapp_id + 1, ZkvmType::Miden, program_hash).into());
+		// check data
+		assert_eq!(NextZkappId::<Test>::get(), zkapp_id + 2);
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id).unwrap().zkvm_type, ZkvmType::Fake);
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id + 1).unwrap().program_hash, program_hash);
 	});
 }
 
@@ -128,24 +172,20 @@ fn duplicate_register_zkapp() {
 #[test]
 fn duplicate_add_asset_support() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, owner, _) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id, owner, _) = setup_app(PROGRAM_HASH_64);
 		// Currency
 		assert_noop!(
-			OpenRollup::add_asset_support(owner.clone(), program_hash, Asset::Currency),
+			OpenRollup::add_asset_support(owner.clone(), zkapp_id, Asset::Currency),
 			Error::<Test>::DuplicateSupportAsset
 		);
 		// Fungible
 		assert_noop!(
-			OpenRollup::add_asset_support(owner.clone(), program_hash, Asset::Fungible(ASSET_ID)),
+			OpenRollup::add_asset_support(owner.clone(), zkapp_id, Asset::Fungible(ASSET_ID)),
 			Error::<Test>::DuplicateSupportAsset
 		);
 		// Nonfungible
 		assert_noop!(
-			OpenRollup::add_asset_support(
-				owner,
-				program_hash,
-				Asset::Nonfungible(COLLECTION_ID)
-			),
+			OpenRollup::add_asset_support(owner, zkapp_id, Asset::Nonfungible(COLLECTION_ID)),
 			Error::<Test>::DuplicateSupportAsset
 		);
 	});
@@ -155,18 +195,18 @@ fn duplicate_add_asset_support() {
 #[test]
 fn called_by_not_owner() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
 		let new_submitter = 5;
 
 		assert_noop!(
-			OpenRollup::add_asset_support(user.clone(), program_hash, Asset::Fungible(ASSET_ID)),
+			OpenRollup::add_asset_support(user.clone(), zkapp_id, Asset::Fungible(ASSET_ID)),
 			Error::<Test>::NotOwner
 		);
 		assert_noop!(
-			OpenRollup::change_submitter(user.clone(), program_hash, new_submitter),
+			OpenRollup::change_submitter(user.clone(), zkapp_id, new_submitter),
 			Error::<Test>::NotOwner
 		);
-		assert_noop!(OpenRollup::set_inactive(user, program_hash), Error::<Test>::NotOwner);
+		assert_noop!(OpenRollup::set_inactive(user, zkapp_id), Error::<Test>::NotOwner);
 	});
 }
 
@@ -174,18 +214,18 @@ fn called_by_not_owner() {
 #[test]
 fn called_by_not_submitter() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
 
 		assert_noop!(
 			OpenRollup::submit_batch(
 				user,
-				program_hash,
+				zkapp_id,
 				H256::from_low_u64_be(0),
 				H256::from_low_u64_be(1),
 				0,
 				vec![],
 				vec![1, 2, 3],
-                None,
+				None,
 			),
 			Error::<Test>::NotSubmitter
 		);
@@ -196,35 +236,32 @@ fn called_by_not_submitter() {
 #[test]
 fn called_on_unkowned_zkapp() {
 	new_test_ext().execute_with(|| {
-		let (_program_hash, owner, user) = setup_app(PROGRAM_HASH_64);
-		let unknow_program_hash = H256::from_low_u64_be(999);
+		let (_zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let unknow_zkapp_id = 999;
 		let new_submitter = 5;
 
 		assert_noop!(
 			OpenRollup::add_asset_support(
 				owner.clone(),
-				unknow_program_hash,
+				unknow_zkapp_id,
 				Asset::Fungible(ASSET_ID)
 			),
 			Error::<Test>::NoProgram
 		);
 		assert_noop!(
-			OpenRollup::change_submitter(owner.clone(), unknow_program_hash, new_submitter),
-			Error::<Test>::NoProgram
-		);
-		assert_noop!(
-			OpenRollup::set_inactive(owner, unknow_program_hash),
+			OpenRollup::change_submitter(owner.clone(), unknow_zkapp_id, new_submitter),
 			Error::<Test>::NoProgram
 		);
+		assert_noop!(OpenRollup::set_inactive(owner, unknow_zkapp_id), Error::<Test>::NoProgram);
 
 		let asset_fungible = AssetValue::Fungible(ASSET_ID, 10);
 		assert_noop!(
-			OpenRollup::deposit(user.clone(), unknow_program_hash, asset_fungible),
+			OpenRollup::deposit(user.clone(), unknow_zkapp_id, asset_fungible),
 			Error::<Test>::NoProgram
 		);
 
 		assert_noop!(
-			OpenRollup::withdraw(user, unknow_program_hash, AssetValue::Currency(10)),
+			OpenRollup::withdraw(user, unknow_zkapp_id, AssetValue::Currency(10)),
 			Error::<Test>::NoProgram
 		);
 	});
@@ -234,16 +271,17 @@ fn called_on_unkowned_zkapp() {
 #[test]
 fn called_on_not_supported_assets() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
 		let asset_fungible = AssetValue::Fungible(ASSET_ID + 10, 10);
 		assert_noop!(
-			OpenRollup::deposit(user.clone(), program_hash, asset_fungible.clone()),
+			OpenRollup::deposit(user.clone(), zkapp_id, asset_fungible.clone()),
 			Error::<Test>::NotSupportAsset
 		);
 
+		// the balances of unsupported assets can still be withdrawn
 		assert_noop!(
-			OpenRollup::withdraw(user, program_hash, asset_fungible),
-			Error::<Test>::NotSupportAsset
+			OpenRollup::withdraw(user, zkapp_id, asset_fungible),
+			Error::<Test>::NoEnoughAssets
 		);
 	});
 }
@@ -252,14 +290,14 @@ fn called_on_not_supported_assets() {
 #[test]
 fn change_submitter_should_work() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, owner, _user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id, owner, _user) = setup_app(PROGRAM_HASH_64);
 		let new_submitter = 5;
-		assert_ok!(OpenRollup::change_submitter(owner, program_hash, new_submitter));
+		assert_ok!(OpenRollup::change_submitter(owner, zkapp_id, new_submitter));
 		// check event
-		assert_last_event(Event::ChangeSubmitter(program_hash, new_submitter).into());
+		assert_last_event(Event::ChangeSubmitter(zkapp_id, new_submitter).into());
 		// check data
-		let zkapp = Zkapps::<Test>::try_get(program_hash).unwrap();
-		assert_eq!(zkapp.submitter, new_submitter);
+		let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
+		assert_eq!(zkapp.submitters.to_vec(), vec![new_submitter]);
 	});
 }
 
@@ -267,249 +305,2083 @@ fn change_submitter_should_work() {
 #[test]
 fn set_inactive_should_work() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, owner, _user) = setup_app(PROGRAM_HASH_64);
-		assert_ok!(OpenRollup::set_inactive(owner.clone(), program_hash));
+		let (zkapp_id, owner, _user) = setup_app(PROGRAM_HASH_64);
+		assert_ok!(OpenRollup::set_inactive(owner.clone(), zkapp_id));
 		// check event
-		assert_last_event(Event::SetInactive(program_hash).into());
+		assert_last_event(Event::SetInactive(zkapp_id).into());
 
 		// if set inactive, only `exit` can be called
 		assert_noop!(
-			OpenRollup::add_asset_support(owner.clone(), program_hash, Asset::Fungible(ASSET_ID)),
-			Error::<Test>::Inactive
-		);
-		assert_noop!(
-			OpenRollup::change_submitter(owner, program_hash, 3),
+			OpenRollup::add_asset_support(owner.clone(), zkapp_id, Asset::Fungible(ASSET_ID)),
 			Error::<Test>::Inactive
 		);
+		assert_noop!(OpenRollup::change_submitter(owner, zkapp_id, 3), Error::<Test>::Inactive);
 		// check data
-		let zkapp = Zkapps::<Test>::try_get(program_hash).unwrap();
+		let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
 		assert!(zkapp.is_inactive);
+		assert!(!LastBatchAt::<Test>::contains_key(zkapp_id));
 	});
 }
 
-/// User L1 deposit Tx into a zkapp
+/// Set a zkapp to inactive by the liveness watchdog when no batch submitted
 #[test]
-fn zkapp_deposit_should_work() {
+fn watchdog_batch_timeout_should_work() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Currency(10)).unwrap();
 
-		let asset_fungible = AssetValue::Fungible(ASSET_ID, 10);
-		assert_ok!(OpenRollup::deposit(user.clone(), program_hash, asset_fungible.clone()));
+		// a batch keeps the zkapp alive
+		System::set_block_number(40);
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			vec![Operation::Deposit(USER_ID, AssetValue::Currency(10))],
+			vec![1, 2, 3],
+			None,
+		));
+
+		// the `block_number` public input of the next batch
+		assert_eq!(OpenRollup::last_batch_at(zkapp_id), Some(40));
+
+		// `MaxBatchInterval` not exceeded
+		OpenRollup::on_idle(140, Weight::MAX);
+		assert!(!Zkapps::<Test>::try_get(zkapp_id).unwrap().is_inactive);
+
+		OpenRollup::on_idle(141, Weight::MAX);
 		// check event
-		assert_last_event(Event::Deposited(program_hash, USER_ID, asset_fungible.clone()).into());
+		assert_last_event(Event::ForcedInactive(zkapp_id, InactiveReason::BatchTimeout).into());
+		// check data
+		assert!(Zkapps::<Test>::try_get(zkapp_id).unwrap().is_inactive);
+		assert!(!LastBatchAt::<Test>::contains_key(zkapp_id));
+		assert_eq!(OpenRollup::last_batch_at(zkapp_id), None);
 
-		let asset_nonfungible = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3]);
-		assert_ok!(OpenRollup::deposit(user, program_hash, asset_nonfungible.clone()));
+		// the watchdog doesn't check the inactive zkapp anymore
+		let cursor_weight = <Test as frame_system::Config>::DbWeight::get().reads_writes(1, 1);
+		assert_eq!(OpenRollup::on_idle(142, Weight::MAX), cursor_weight);
+
+		// users can exit without the owner
+		assert_ok!(OpenRollup::exit(user, zkapp_id));
+	});
+}
+
+/// Set a zkapp to inactive by the liveness watchdog when L1 operations are not handled
+#[test]
+fn watchdog_l1_operation_timeout_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (other_zkapp_id, _, _) = setup_app(456);
+
+		System::set_block_number(10);
+		OpenRollup::deposit(user, zkapp_id, AssetValue::Currency(10)).unwrap();
+
+		// `MaxL1OperationAge` not exceeded
+		OpenRollup::on_idle(60, Weight::MAX);
+		assert!(!Zkapps::<Test>::try_get(zkapp_id).unwrap().is_inactive);
+
+		OpenRollup::on_idle(61, Weight::MAX);
 		// check event
 		assert_last_event(
-			Event::Deposited(program_hash, USER_ID, asset_nonfungible.clone()).into(),
+			Event::ForcedInactive(zkapp_id, InactiveReason::L1OperationTimeout).into(),
 		);
 		// check data
-		let zkapp = Zkapps::<Test>::try_get(program_hash).unwrap();
-		assert_eq!(
-			zkapp.l1_operations.first().unwrap(),
-			&Operation::Deposit(USER_ID, asset_fungible)
-		);
-		assert_eq!(
-			zkapp.l1_operations.last().unwrap(),
-			&Operation::Deposit(USER_ID, asset_nonfungible)
-		);
+		assert!(Zkapps::<Test>::try_get(zkapp_id).unwrap().is_inactive);
+		assert!(!Zkapps::<Test>::try_get(other_zkapp_id).unwrap().is_inactive);
 	});
 }
 
-/// User L1 withdraw Tx from a zkapp
+/// The liveness watchdog only checks the zkapps the remaining weight allows
 #[test]
-fn zkapp_withdraw_should_work() {
+fn watchdog_checks_within_remaining_weight() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, _owner, user) = setup_app(PROGRAM_HASH_64);
-		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+		let (zkapp_id_1, _, _) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id_2, _, _) = setup_app(456);
 
-		// need add asset for user befor withdraw
-		OpenRollup::add_zkapp_user_asset(program_hash, USER_ID, &asset_value).unwrap();
+		let cursor_weight = <Test as frame_system::Config>::DbWeight::get().reads_writes(1, 1);
+		let check_weight = <Test as Config>::WeightInfo::check_liveness();
+
+		// not enough weight to check any zkapp
+		assert_eq!(OpenRollup::on_idle(200, cursor_weight), Weight::zero());
+
+		// check one zkapp each time
+		let weight = cursor_weight.saturating_add(check_weight);
+		assert_eq!(OpenRollup::on_idle(200, weight), weight);
+		assert_eq!(OpenRollup::on_idle(200, weight), weight);
+		assert!(Zkapps::<Test>::try_get(zkapp_id_1).unwrap().is_inactive);
+		assert!(Zkapps::<Test>::try_get(zkapp_id_2).unwrap().is_inactive);
+	});
+}
+
+/// Set the verifying key of a zkapp
+#[test]
+fn set_verifying_key_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+
+		assert_noop!(
+			OpenRollup::set_verifying_key(user, zkapp_id, vec![1, 2, 3]),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			OpenRollup::set_verifying_key(owner.clone(), zkapp_id, vec![1; 1025]),
+			Error::<Test>::VerifyingKeyTooLong
+		);
 
-		assert_ok!(OpenRollup::withdraw(user, program_hash, asset_value.clone()));
+		assert_ok!(OpenRollup::set_verifying_key(owner.clone(), zkapp_id, vec![1, 2, 3]));
 		// check event
-		assert_last_event(Event::Withdrawed(program_hash, USER_ID, asset_value.clone()).into());
+		assert_last_event(Event::SetVerifyingKey(zkapp_id).into());
 		// check data
-		let zkapp = Zkapps::<Test>::try_get(program_hash).unwrap();
-		assert_eq!(
-			zkapp.l1_operations.first().unwrap(),
-			&Operation::Withdraw(USER_ID, asset_value)
+		assert_eq!(VerifyingKeys::<Test>::get(zkapp_id).unwrap().into_inner(), vec![1, 2, 3]);
+
+		// the key is replaced by an upgrade of the zkapp's program
+		let program_hash = H256::from_low_u64_be(PROGRAM_HASH_64);
+		assert_ok!(OpenRollup::set_verifying_key(owner.clone(), zkapp_id, vec![4, 5, 6]));
+		// check event
+		assert_last_event(
+			Event::UpgradeProposed(zkapp_id, program_hash, ZkvmType::Fake, 31).into(),
 		);
+		// check data, the key is not replaced until the upgrade is enacted
+		assert_eq!(VerifyingKeys::<Test>::get(zkapp_id).unwrap().into_inner(), vec![1, 2, 3]);
+		System::set_block_number(31);
+		assert_ok!(OpenRollup::enact_upgrade(owner, zkapp_id));
+		assert_eq!(VerifyingKeys::<Test>::get(zkapp_id).unwrap().into_inner(), vec![4, 5, 6]);
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id).unwrap().program_hash, program_hash);
 	});
 }
 
-/// move asset from a zkapp to another zkapp
+/// Set the metadata of a zkapp with a reserved deposit
 #[test]
-fn zkapp_move_asset_should_work() {
+fn set_metadata_should_work() {
 	new_test_ext().execute_with(|| {
-		let (program_hash_1, _owner_2, _) = setup_app(PROGRAM_HASH_64);
-		let (program_hash_2, _owner_2, user) = setup_app(456);
-		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
-
-		// Add asset for user befor move
-		OpenRollup::add_zkapp_user_asset(program_hash_1, USER_ID, &asset_value).unwrap();
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let set_metadata = |origin, name: &[u8], description: &[u8], source_url: &[u8]| {
+			OpenRollup::set_metadata(
+				origin,
+				zkapp_id,
+				name.to_vec(),
+				description.to_vec(),
+				source_url.to_vec(),
+				[1u8; 32],
+			)
+		};
+
+		assert_noop!(set_metadata(user, b"app", b"", b""), Error::<Test>::NotOwner);
+		assert_noop!(
+			set_metadata(owner.clone(), &[b'a'; 51], b"", b""),
+			Error::<Test>::BadMetadata
+		);
 
-		assert_ok!(OpenRollup::move_asset(
-			user,
-			program_hash_1,
-			program_hash_2,
-			asset_value.clone(),
-		));
+		assert_ok!(set_metadata(owner.clone(), b"app", b"desc", b"url"));
 		// check event
 		assert_last_event(
-			Event::MoveAsset(program_hash_1, program_hash_2, USER_ID, asset_value.clone()).into(),
-		);
-		// check data
-		let zkapp = Zkapps::<Test>::try_get(program_hash_1).unwrap();
-		assert_eq!(
-			zkapp.l1_operations.first().unwrap(),
-			&Operation::Move(USER_ID, program_hash_2, asset_value)
+			Event::Metadata(
+				zkapp_id,
+				bounded_vec![b'a', b'p', b'p'],
+				bounded_vec![b'd', b'e', b's', b'c'],
+				bounded_vec![b'u', b'r', b'l'],
+				[1u8; 32],
+			)
+			.into(),
 		);
+		// check data, the deposit is 10 + 1 per byte
+		let metadata = Metadata::<Test>::get(zkapp_id).unwrap();
+		assert_eq!(metadata.name.into_inner(), b"app".to_vec());
+		assert_eq!(metadata.source_hash, [1u8; 32]);
+		assert_eq!(<Test as Config>::Currency::reserved_balance(OWNER_ID), 20);
+
+		// the deposit of the metadata replaced is unreserved
+		assert_ok!(set_metadata(owner, b"app2", b"", b""));
+		assert_eq!(Metadata::<Test>::get(zkapp_id).unwrap().deposit, 14);
+		assert_eq!(<Test as Config>::Currency::reserved_balance(OWNER_ID), 14);
 	});
 }
 
-// Exit from zkapp
+/// Clear the metadata of a zkapp and unreserve its deposit
 #[test]
-fn zkapp_exit_should_work() {
+fn clear_metadata_should_work() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, owner, user) = setup_app(PROGRAM_HASH_64);
-
-		// need add asset for user befor withdraw
-		let asset_value = AssetValue::Currency(10);
-		OpenRollup::deposit(user.clone(), program_hash, asset_value).unwrap();
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
 
-		// check currency balance
-		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+		assert_noop!(
+			OpenRollup::clear_metadata(owner.clone(), zkapp_id),
+			Error::<Test>::NoMetadata
+		);
+		assert_ok!(OpenRollup::set_metadata(
+			owner.clone(),
+			zkapp_id,
+			b"app".to_vec(),
+			b"desc".to_vec(),
+			b"url".to_vec(),
+			[1u8; 32],
+		));
+		assert_noop!(OpenRollup::clear_metadata(user.clone(), zkapp_id), Error::<Test>::NotOwner);
 
-		// set zkapp to inactive
-		OpenRollup::set_inactive(owner, program_hash).unwrap();
+		// the owner can clear it after the zkapp is set inactive
+		assert_ok!(OpenRollup::set_inactive(owner.clone(), zkapp_id));
+		assert_ok!(OpenRollup::clear_metadata(owner, zkapp_id));
+		// check event
+		assert_last_event(Event::MetadataCleared(zkapp_id).into());
+		// check data
+		assert!(!Metadata::<Test>::contains_key(zkapp_id));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(OWNER_ID), 0);
 
-		assert_ok!(OpenRollup::exit(user, program_hash));
+		// the ForceOrigin clears the metadata of another zkapp
+		let (zkapp_id, owner, _) = setup_app(456);
+		assert_ok!(OpenRollup::set_metadata(owner, zkapp_id, vec![], vec![], vec![], [0u8; 32]));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(OWNER_ID), 10);
+		assert_noop!(
+			OpenRollup::force_clear_metadata(user, zkapp_id),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(OpenRollup::force_clear_metadata(RuntimeOrigin::root(), zkapp_id));
 		// check event
-		assert_last_event(Event::Exit(program_hash, USER_ID).into());
+		assert_last_event(Event::MetadataCleared(zkapp_id).into());
+		// check data
+		assert!(!Metadata::<Test>::contains_key(zkapp_id));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(OWNER_ID), 0);
+	});
+}
 
-		// check currency balance
-		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 0);
+/// Register a zkapp whose verifier is not enabled
+#[test]
+fn register_unsupported_zkapp() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			OpenRollup::zkapp_register(
+				RuntimeOrigin::signed(OWNER_ID),
+				H256::from_low_u64_be(PROGRAM_HASH_64),
+				ZkvmType::Groth16(Groth16Curve::Bls12_381),
+				SUBMITTER_ID,
+				H256::from_low_u64_be(0),
+			),
+			Error::<Test>::UnsupportedZkvm
+		);
 	});
 }
 
-// submit a batch
+/// Groth16 zkapps require a valid verifying key
 #[test]
-fn zkapp_submit_batch_should_work() {
+fn groth16_zkapp_requires_verifying_key() {
 	new_test_ext().execute_with(|| {
-		let (program_hash, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let zkapp_id = NextZkappId::<Test>::get();
+		let owner = RuntimeOrigin::signed(OWNER_ID);
+		let submitter = RuntimeOrigin::signed(SUBMITTER_ID);
+		let empty_state_root = H256::from_low_u64_be(0);
+
+		OpenRollup::zkapp_register(
+			owner.clone(),
+			H256::from_low_u64_be(PROGRAM_HASH_64),
+			ZkvmType::Groth16(Groth16Curve::Bn254),
+			SUBMITTER_ID,
+			empty_state_root,
+		)
+		.unwrap();
+		OpenRollup::bond_submitter(submitter.clone(), zkapp_id).unwrap();
 
-		// state_root
-		let state_root_1 = H256::from_low_u64_be(0);
-		let state_root_2 = H256::from_low_u64_be(1);
+		assert_noop!(
+			OpenRollup::submit_batch(
+				submitter,
+				zkapp_id,
+				empty_state_root,
+				H256::from_low_u64_be(1),
+				0,
+				vec![],
+				vec![1, 2, 3],
+				None,
+			),
+			Error::<Test>::NoVerifyingKey
+		);
+		assert_noop!(
+			OpenRollup::set_verifying_key(owner, zkapp_id, vec![1, 2, 3]),
+			Error::<Test>::InvalidVerifyingKey
+		);
+	});
+}
 
-		// zk proof
-		let zk_proof = vec![1, 2, 3];
+/// Bond the submitter of a zkapp
+#[test]
+fn bond_submitter_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let submitter = RuntimeOrigin::signed(SUBMITTER_ID);
 
-		// assets
-		let asset_value_1 = AssetValue::Currency(10);
-		let asset_value_2 = AssetValue::Fungible(ASSET_ID, 10);
-		let asset_value_3 = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3, 4]);
+		// check data
+		let bond = SubmitterBonds::<Test>::try_get(zkapp_id, SUBMITTER_ID).unwrap();
+		assert_eq!(bond.who, SUBMITTER_ID);
+		assert_eq!(bond.amount, 100);
+		assert_eq!(<Test as Config>::Currency::reserved_balance(SUBMITTER_ID), 100);
 
-		let l1_operations_pos = 3;
+		assert_noop!(OpenRollup::bond_submitter(submitter, zkapp_id), Error::<Test>::AlreadyBonded);
+		assert_noop!(OpenRollup::bond_submitter(user, zkapp_id), Error::<Test>::NotSubmitter);
 
-		let user_id_2 = 6;
+		// the new submitter can't submit batches before bonded
+		OpenRollup::change_submitter(owner, zkapp_id, USER_ID).unwrap();
+		assert_noop!(
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(USER_ID),
+				zkapp_id,
+				H256::from_low_u64_be(0),
+				H256::from_low_u64_be(1),
+				0,
+				vec![],
+				vec![1, 2, 3],
+				None,
+			),
+			Error::<Test>::NotBonded
+		);
+		assert_ok!(OpenRollup::bond_submitter(RuntimeOrigin::signed(USER_ID), zkapp_id));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(USER_ID), 100);
+	});
+}
 
-		// deposit
-		OpenRollup::deposit(user.clone(), program_hash, asset_value_1.clone()).unwrap();
-		OpenRollup::deposit(user.clone(), program_hash, asset_value_2.clone()).unwrap();
-		OpenRollup::deposit(user, program_hash, asset_value_3.clone()).unwrap();
+/// Submit batches with multiple submitters in rotation
+#[test]
+fn submitter_rotation_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let submit = |who: u64, old: u64, new: u64| {
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(who),
+				zkapp_id,
+				H256::from_low_u64_be(old),
+				H256::from_low_u64_be(new),
+				0,
+				vec![],
+				vec![1, 2, 3],
+				None,
+			)
+		};
 
-		// check currency balance
-		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+		// add submitters
+		assert_noop!(
+			OpenRollup::add_submitter(user.clone(), zkapp_id, USER_ID),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(OpenRollup::add_submitter(owner.clone(), zkapp_id, USER_ID));
+		// check event
+		assert_last_event(Event::SubmitterAdded(zkapp_id, USER_ID).into());
+		assert_noop!(
+			OpenRollup::add_submitter(owner.clone(), zkapp_id, USER_ID),
+			Error::<Test>::DuplicateSubmitter
+		);
+		assert_ok!(OpenRollup::add_submitter(owner.clone(), zkapp_id, OWNER_ID));
+		assert_noop!(
+			OpenRollup::add_submitter(owner.clone(), zkapp_id, 5),
+			Error::<Test>::SubmittersLimitExceed
+		);
+		// check data
+		let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
+		assert_eq!(zkapp.submitters.to_vec(), vec![SUBMITTER_ID, USER_ID, OWNER_ID]);
+		OpenRollup::bond_submitter(user.clone(), zkapp_id).unwrap();
+		OpenRollup::bond_submitter(owner.clone(), zkapp_id).unwrap();
 
-		let operations = vec![
-			Operation::Deposit(USER_ID, asset_value_1),
-			Operation::Deposit(USER_ID, asset_value_2),
-			Operation::Deposit(USER_ID, asset_value_3),
-			Operation::Transfer(USER_ID, user_id_2, AssetValue::Fungible(ASSET_ID, 2)),
-			Operation::Transfer(
-				USER_ID,
-				user_id_2,
-				AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3]),
+		// set the rotation
+		let rotation = SubmitterRotation { slot_length: 10, grace_period: 25 };
+		assert_noop!(
+			OpenRollup::set_submitter_rotation(user.clone(), zkapp_id, Some(rotation.clone())),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			OpenRollup::set_submitter_rotation(
+				owner.clone(),
+				zkapp_id,
+				Some(SubmitterRotation { slot_length: 0, grace_period: 25 })
 			),
-		];
-
-		// submit_batch
-		assert_ok!(OpenRollup::submit_batch(
-			RuntimeOrigin::signed(SUBMITTER_ID),
-			program_hash,
-			state_root_1,
-			state_root_2,
-			l1_operations_pos,
-			operations.clone(),
-			zk_proof,
-            None,
+			Error::<Test>::InvalidRotation
+		);
+		assert_ok!(OpenRollup::set_submitter_rotation(
+			owner.clone(),
+			zkapp_id,
+			Some(rotation.clone())
 		));
-
 		// check event
-		assert_last_event(
-			Event::SubmitBatch(program_hash, state_root_1, state_root_2, operations).into(),
+		assert_last_event(Event::SubmitterRotationSet(zkapp_id, Some(rotation.clone())).into());
+		assert_eq!(SubmitterRotations::<Test>::get(zkapp_id), Some(rotation));
+
+		// only the leader of the slot can submit batches
+		System::set_block_number(10);
+		assert_noop!(submit(SUBMITTER_ID, 0, 1), Error::<Test>::NotSlotLeader);
+		assert_ok!(submit(USER_ID, 0, 1));
+		System::set_block_number(20);
+		assert_noop!(submit(USER_ID, 1, 2), Error::<Test>::NotSlotLeader);
+
+		// any submitter can submit batches after the grace period
+		System::set_block_number(45);
+		assert_ok!(submit(SUBMITTER_ID, 1, 2));
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id).unwrap().state_root, H256::from_low_u64_be(2));
+
+		// remove submitters
+		assert_noop!(
+			OpenRollup::remove_submitter(owner.clone(), zkapp_id, 5),
+			Error::<Test>::NotSubmitter
 		);
-
-		// check account data
-		let account = ZkappsAccounts::<Test>::try_get(program_hash, USER_ID).unwrap();
-
-		assert_eq!(account.assets.len(), 3);
-		assert_eq!(account.assets.first().unwrap(), &AssetValue::Currency(10));
-		assert_eq!(account.assets.get(1).unwrap(), &AssetValue::Fungible(ASSET_ID, 8));
-		assert_eq!(
-			account.assets.last().unwrap(),
-			&AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![4])
+		assert_ok!(OpenRollup::remove_submitter(owner.clone(), zkapp_id, USER_ID));
+		// check event
+		assert_last_event(Event::SubmitterRemoved(zkapp_id, USER_ID).into());
+		// check data
+		assert!(!SubmitterBonds::<Test>::contains_key(zkapp_id, USER_ID));
+		assert!(Unbondings::<Test>::contains_key(zkapp_id, USER_ID));
+		assert_noop!(submit(USER_ID, 2, 3), Error::<Test>::NotSubmitter);
+		assert_ok!(OpenRollup::remove_submitter(owner.clone(), zkapp_id, OWNER_ID));
+		assert_noop!(
+			OpenRollup::remove_submitter(owner.clone(), zkapp_id, SUBMITTER_ID),
+			Error::<Test>::LastSubmitter
 		);
+
+		// remove the rotation
+		assert_ok!(OpenRollup::set_submitter_rotation(owner, zkapp_id, None));
+		// check event
+		assert_last_event(Event::SubmitterRotationSet(zkapp_id, None).into());
+		assert!(!SubmitterRotations::<Test>::contains_key(zkapp_id));
 	});
 }
 
-// submit a miden project batch
+/// Withdraw the bond of a former submitter after the unbonding period
 #[test]
-fn zkapp_miden_submit_batch_should_work() {
+fn withdraw_unbonded_should_work() {
 	new_test_ext().execute_with(|| {
-		// state_root
-		let old_state_root = H256::repeat_byte(1);
-		let new_state_root = H256::repeat_byte(2);
-
-		let (program, _owner, _user) = setup_miden_app(old_state_root);
+		let (zkapp_id, owner, _user) = setup_app(PROGRAM_HASH_64);
+		let submitter = RuntimeOrigin::signed(SUBMITTER_ID);
 
-        let program_hash = H256::from_slice(&program.hash().as_bytes());
+		assert_noop!(
+			OpenRollup::withdraw_unbonded(submitter.clone(), zkapp_id),
+			Error::<Test>::NoUnbonding
+		);
 
-		let inputs = raw_inputs_to_stack_inputs(old_state_root.as_bytes()).unwrap();
+		System::set_block_number(1);
+		OpenRollup::change_submitter(owner, zkapp_id, USER_ID).unwrap();
+		assert!(!SubmitterBonds::<Test>::contains_key(zkapp_id, SUBMITTER_ID));
+		let unbonding = Unbondings::<Test>::try_get(zkapp_id, SUBMITTER_ID).unwrap();
+		assert_eq!(unbonding.amount, 100);
+		assert_eq!(unbonding.unlock_at, 11);
 
-        // println!("miden inputs: {:?}", inputs);
+		System::set_block_number(10);
+		assert_noop!(
+			OpenRollup::withdraw_unbonded(submitter.clone(), zkapp_id),
+			Error::<Test>::StillUnbonding
+		);
 
-		let (outputs, proof) =
-			prove(&program, inputs, MemAdviceProvider::default(), ProofOptions::default()).unwrap();
-		assert_eq!(*outputs.stack().first().unwrap(), 8);
-        
-        // println!("miden outputs: {:?}", outputs);
+		System::set_block_number(11);
+		assert_ok!(OpenRollup::withdraw_unbonded(submitter, zkapp_id));
+		// check event
+		assert_last_event(Event::SubmitterUnbonded(zkapp_id, SUBMITTER_ID, 100).into());
+		// check data
+		assert!(!Unbondings::<Test>::contains_key(zkapp_id, SUBMITTER_ID));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(SUBMITTER_ID), 0);
+		assert_eq!(<Test as Config>::Currency::free_balance(SUBMITTER_ID), 10000);
+	});
+}
 
-		// zk proof
-		let zk_proof = proof.to_bytes();
-        let zk_outputs = Some(outputs.to_bytes());
+/// Slash the bonds of the submitter when the zkapp is forced inactive
+#[test]
+fn watchdog_slashes_submitter() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, _user) = setup_app(PROGRAM_HASH_64);
 
-		let l1_operations_pos = 0;
-		let operations = vec![ ];
-		// submit_batch
-		assert_ok!(OpenRollup::submit_batch(
-			RuntimeOrigin::signed(SUBMITTER_ID),
-			program_hash,
-			old_state_root,
-			new_state_root,
-			l1_operations_pos,
-			operations.clone(),
-			zk_proof,
-            zk_outputs,
-		));
+		// the bond being unbonded is slashed too
+		OpenRollup::change_submitter(owner.clone(), zkapp_id, SUBMITTER_ID).unwrap();
+		OpenRollup::bond_submitter(RuntimeOrigin::signed(SUBMITTER_ID), zkapp_id).unwrap();
+		assert_eq!(<Test as Config>::Currency::reserved_balance(SUBMITTER_ID), 200);
 
+		OpenRollup::on_idle(200, Weight::MAX);
 		// check event
-		assert_last_event(
-			Event::SubmitBatch(program_hash, old_state_root, new_state_root, operations).into(),
+		System::assert_has_event(Event::SubmitterSlashed(zkapp_id, SUBMITTER_ID, 200).into());
+		// check data
+		assert!(!SubmitterBonds::<Test>::contains_key(zkapp_id, SUBMITTER_ID));
+		assert!(!Unbondings::<Test>::contains_key(zkapp_id, SUBMITTER_ID));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(SUBMITTER_ID), 0);
+		assert_eq!(<Test as Config>::Currency::free_balance(SUBMITTER_ID), 9800);
+
+		// the bond is released when the owner sets the zkapp inactive
+		let (other_zkapp_id, _, _) = setup_app(456);
+		OpenRollup::set_inactive(owner, other_zkapp_id).unwrap();
+		assert!(Unbondings::<Test>::contains_key(other_zkapp_id, SUBMITTER_ID));
+		OpenRollup::on_idle(400, Weight::MAX);
+		assert_eq!(<Test as Config>::Currency::reserved_balance(SUBMITTER_ID), 100);
+	});
+}
+
+/// The bonds of stalled submitters can't be unbonded, and the removed submitters are slashed
+#[test]
+fn stalled_submitters_cant_unbond() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, _user) = setup_app(PROGRAM_HASH_64);
+		OpenRollup::add_submitter(owner.clone(), zkapp_id, USER_ID).unwrap();
+		OpenRollup::bond_submitter(RuntimeOrigin::signed(USER_ID), zkapp_id).unwrap();
+		OpenRollup::remove_submitter(owner.clone(), zkapp_id, USER_ID).unwrap();
+		assert!(Unbondings::<Test>::contains_key(zkapp_id, USER_ID));
+
+		// `MaxBatchInterval` exceeded
+		System::set_block_number(102);
+		assert_noop!(OpenRollup::set_inactive(owner.clone(), zkapp_id), Error::<Test>::Stalled);
+		assert_noop!(
+			OpenRollup::change_submitter(owner.clone(), zkapp_id, OWNER_ID),
+			Error::<Test>::Stalled
+		);
+		assert_noop!(
+			OpenRollup::remove_submitter(owner, zkapp_id, SUBMITTER_ID),
+			Error::<Test>::Stalled
 		);
 
+		OpenRollup::on_idle(102, Weight::MAX);
+		// check event
+		System::assert_has_event(Event::SubmitterSlashed(zkapp_id, SUBMITTER_ID, 100).into());
+		System::assert_has_event(Event::SubmitterSlashed(zkapp_id, USER_ID, 100).into());
+		// check data
+		assert!(!Unbondings::<Test>::contains_key(zkapp_id, USER_ID));
+		assert_eq!(<Test as Config>::Currency::reserved_balance(USER_ID), 0);
+	});
+}
+
+/// User L1 deposit Tx into a zkapp
+#[test]
+fn zkapp_deposit_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+
+		let asset_fungible = AssetValue::Fungible(ASSET_ID, 10);
+		assert_ok!(OpenRollup::deposit(user.clone(), zkapp_id, asset_fungible.clone()));
+		// check event
+		assert_last_event(Event::Deposited(zkapp_id, USER_ID, asset_fungible.clone()).into());
+
+		let asset_nonfungible = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3]);
+		assert_ok!(OpenRollup::deposit(user, zkapp_id, asset_nonfungible.clone()));
+		// check event
+		assert_last_event(Event::Deposited(zkapp_id, USER_ID, asset_nonfungible.clone()).into());
+		// check data
+		assert_eq!(L1OperationsQueue::<Test>::get(zkapp_id).len(), 2);
+		assert_eq!(
+			OpenRollup::pending_l1_operations(zkapp_id, 0, 10),
+			vec![
+				Operation::Deposit(USER_ID, asset_fungible),
+				Operation::Deposit(USER_ID, asset_nonfungible)
+			]
+		);
+	});
+}
+
+/// User L1 withdraw Tx from a zkapp
+#[test]
+fn zkapp_withdraw_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+
+		// need add asset for user befor withdraw
+		OpenRollup::add_zkapp_user_asset(zkapp_id, USER_ID, &asset_value).unwrap();
+
+		assert_ok!(OpenRollup::withdraw(user, zkapp_id, asset_value.clone()));
+		// check event
+		assert_last_event(Event::Withdrawed(zkapp_id, USER_ID, asset_value.clone()).into());
+		// check data
+		assert_eq!(
+			L1Operations::<Test>::get(zkapp_id, 0).unwrap().operation,
+			Operation::Withdraw(USER_ID, asset_value)
+		);
+	});
+}
+
+/// Force the zkapp inactive when a batch misses a forced withdrawal past its deadline
+#[test]
+fn zkapp_force_withdraw_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let state_root = H256::from_low_u64_be;
+		let asset_value = AssetValue::Currency(5);
+		let submit_batch = |old_state_root, operations: Vec<_>| {
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id,
+				state_root(old_state_root),
+				state_root(old_state_root + 1),
+				operations.len() as u32,
+				operations,
+				vec![1, 2, 3],
+				None,
+			)
+		};
+
+		OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Currency(10)).unwrap();
+		assert_ok!(submit_batch(0, vec![Operation::Deposit(USER_ID, AssetValue::Currency(10))]));
+
+		System::set_block_number(2);
+		assert_noop!(
+			OpenRollup::force_withdraw(user.clone(), zkapp_id, AssetValue::Currency(0)),
+			Error::<Test>::ZeroAmount
+		);
+		assert_noop!(
+			OpenRollup::force_withdraw(user.clone(), zkapp_id, AssetValue::Currency(11)),
+			Error::<Test>::NoEnoughAssets
+		);
+		assert_ok!(OpenRollup::force_withdraw(user.clone(), zkapp_id, asset_value.clone()));
+		// check event
+		assert_last_event(
+			Event::ForcedWithdrawal(zkapp_id, USER_ID, asset_value.clone(), 22).into(),
+		);
+		System::set_block_number(5);
+		assert_ok!(OpenRollup::force_withdraw(user.clone(), zkapp_id, asset_value.clone()));
+		// check data
+		assert_eq!(ForcedWithdrawalsQueue::<Test>::get(zkapp_id).len(), 2);
+		assert_eq!(
+			ForcedWithdrawals::<Test>::get(zkapp_id, 0),
+			Some(ForcedWithdrawal { position: 1, deadline: 22 })
+		);
+
+		// the first one is included after its deadline
+		System::set_block_number(24);
+		assert_ok!(submit_batch(1, vec![Operation::Withdraw(USER_ID, asset_value)]));
+		assert_eq!(ForcedWithdrawalsQueue::<Test>::get(zkapp_id).head, 1);
+		assert!(!ForcedWithdrawals::<Test>::contains_key(zkapp_id, 0));
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id).unwrap().state_root, state_root(2));
+
+		// the second one is missed
+		System::set_block_number(26);
+		assert_ok!(submit_batch(2, vec![]));
+		// check event
+		assert_last_event(
+			Event::ForcedInactive(zkapp_id, InactiveReason::ForcedWithdrawalMissed).into(),
+		);
+		// check data, the batch is not applied and the bond is slashed
+		let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
+		assert!(zkapp.is_inactive);
+		assert_eq!(zkapp.state_root, state_root(2));
+		assert_eq!(SubmitterBonds::<Test>::get(zkapp_id, SUBMITTER_ID), None);
+
+		// users can exit from the zkapp
+		assert_ok!(OpenRollup::exit(user, zkapp_id));
+	});
+}
+
+/// Skip the L1 withdrawals exceeding the user's assets, instead of failing the batch
+#[test]
+fn zkapp_underfunded_l1_withdrawal_is_skipped() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let asset_value = AssetValue::Currency(10);
+
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value.clone()).unwrap();
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			vec![Operation::Deposit(USER_ID, asset_value.clone())],
+			vec![1, 2, 3],
+			None,
+		));
+		// both pass the balance check when queued, but exceed it together
+		assert_ok!(OpenRollup::force_withdraw(user.clone(), zkapp_id, asset_value.clone()));
+		assert_ok!(OpenRollup::withdraw(user, zkapp_id, asset_value.clone()));
+
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(1),
+			H256::from_low_u64_be(2),
+			2,
+			vec![
+				Operation::Withdraw(USER_ID, asset_value.clone()),
+				Operation::Withdraw(USER_ID, asset_value.clone()),
+			],
+			vec![1, 2, 3],
+			None,
+		));
+		// check event
+		let skipped =
+			Event::L1OperationSkipped(zkapp_id, Operation::Withdraw(USER_ID, asset_value));
+		assert!(System::events().iter().any(|record| record.event == skipped.clone().into()));
+		// check data, the batch is applied and the first withdrawal only is credited
+		let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
+		assert!(!zkapp.is_inactive);
+		assert_eq!(zkapp.state_root, H256::from_low_u64_be(2));
+		assert_eq!(PendingWithdrawals::<Test>::get((USER_ID, Asset::Currency)), 10);
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Currency)), 0);
+		assert!(L1OperationsQueue::<Test>::get(zkapp_id).is_empty());
+		assert!(SubmitterBonds::<Test>::contains_key(zkapp_id, SUBMITTER_ID));
+	});
+}
+
+/// Upgrade the program of a zkapp after the upgrade delay
+#[test]
+fn zkapp_upgrade_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let new_program_hash = H256::from_low_u64_be(789);
+		let propose_upgrade = |origin, zkvm_type| {
+			OpenRollup::propose_upgrade(origin, zkapp_id, new_program_hash, zkvm_type, None)
+		};
+		let submit_batch = |old_state_root, operation| {
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id,
+				H256::from_low_u64_be(old_state_root),
+				H256::from_low_u64_be(old_state_root + 1),
+				1,
+				vec![operation],
+				vec![1, 2, 3],
+				None,
+			)
+		};
+		let withdrawal = Operation::Withdraw(USER_ID, AssetValue::Currency(10));
+
+		assert_noop!(propose_upgrade(user.clone(), ZkvmType::Fake), Error::<Test>::NotOwner);
+		assert_noop!(
+			propose_upgrade(owner.clone(), ZkvmType::Groth16(Groth16Curve::Bls12_381)),
+			Error::<Test>::UnsupportedZkvm
+		);
+		assert_noop!(
+			OpenRollup::enact_upgrade(owner.clone(), zkapp_id),
+			Error::<Test>::NoPendingUpgrade
+		);
+
+		assert_noop!(
+			OpenRollup::propose_upgrade(
+				owner.clone(),
+				zkapp_id,
+				new_program_hash,
+				ZkvmType::Fake,
+				Some(vec![1; 1025])
+			),
+			Error::<Test>::VerifyingKeyTooLong
+		);
+
+		System::set_block_number(2);
+		assert_ok!(OpenRollup::propose_upgrade(
+			owner.clone(),
+			zkapp_id,
+			new_program_hash,
+			ZkvmType::Fake,
+			Some(vec![4, 5, 6])
+		));
+		// check event
+		assert_last_event(
+			Event::UpgradeProposed(zkapp_id, new_program_hash, ZkvmType::Fake, 32).into(),
+		);
+		assert_ok!(OpenRollup::set_verifying_key(owner.clone(), zkapp_id, vec![1, 2, 3]));
+
+		System::set_block_number(11);
+		OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Currency(20)).unwrap();
+		assert_ok!(submit_batch(0, Operation::Deposit(USER_ID, AssetValue::Currency(20))));
+		assert_ok!(OpenRollup::force_withdraw(user.clone(), zkapp_id, AssetValue::Currency(10)));
+
+		System::set_block_number(31);
+		assert_noop!(
+			OpenRollup::enact_upgrade(owner.clone(), zkapp_id),
+			Error::<Test>::UpgradeTooEarly
+		);
+
+		// the forced withdrawals due by the upgrade must be included by the former program
+		System::set_block_number(32);
+		assert_noop!(
+			OpenRollup::enact_upgrade(owner.clone(), zkapp_id),
+			Error::<Test>::ForcedWithdrawalsPending
+		);
+		assert_ok!(submit_batch(1, withdrawal.clone()));
+		// the forced withdrawals due after the upgrade don't block it
+		assert_ok!(OpenRollup::force_withdraw(user, zkapp_id, AssetValue::Currency(10)));
+
+		assert_ok!(OpenRollup::enact_upgrade(owner.clone(), zkapp_id));
+		// check event
+		assert_last_event(Event::UpgradeEnacted(zkapp_id, new_program_hash, ZkvmType::Fake).into());
+		// check data, the zkapp keeps its identity and state
+		let zkapp = Zkapps::<Test>::try_get(zkapp_id).unwrap();
+		assert_eq!(zkapp.program_hash, new_program_hash);
+		assert_eq!(zkapp.state_root, H256::from_low_u64_be(2));
+		assert!(!PendingUpgrades::<Test>::contains_key(zkapp_id));
+		// the verifying key is replaced with the upgrade
+		assert_eq!(VerifyingKeys::<Test>::get(zkapp_id).unwrap().to_vec(), vec![4, 5, 6]);
+
+		// an upgrade without a verifying key removes the former one
+		assert_ok!(propose_upgrade(owner.clone(), ZkvmType::Fake));
+		System::set_block_number(62);
+		assert_noop!(
+			OpenRollup::enact_upgrade(owner.clone(), zkapp_id),
+			Error::<Test>::ForcedWithdrawalsPending
+		);
+		assert_ok!(submit_batch(2, withdrawal));
+		assert_ok!(OpenRollup::enact_upgrade(owner, zkapp_id));
+		assert!(!VerifyingKeys::<Test>::contains_key(zkapp_id));
+	});
+}
+
+/// The upgrade delay of the mock is longer than the forced withdrawal delay
+#[test]
+fn integrity_test_should_pass() {
+	new_test_ext().execute_with(OpenRollup::integrity_test);
+}
+
+/// Set the storage version when upgrading from the releases before it, unless they left storage
+#[test]
+fn on_runtime_upgrade_should_work() {
+	new_test_ext().execute_with(|| {
+		let on_runtime_upgrade = <OpenRollup as Hooks<u64>>::on_runtime_upgrade;
+
+		// the storage version is set when the former releases left no storage
+		StorageVersion::new(0).put::<OpenRollup>();
+		on_runtime_upgrade();
+		assert_eq!(StorageVersion::get::<OpenRollup>(), STORAGE_VERSION);
+
+		// the storage of the former releases is not migrated
+		StorageVersion::new(0).put::<OpenRollup>();
+		let pallet = <OpenRollup as PalletInfoAccess>::name().as_bytes();
+		let mut key = frame_support::storage::storage_prefix(pallet, b"ZkappsAccounts").to_vec();
+		key.extend_from_slice(&[1; 32]);
+		frame_support::storage::unhashed::put_raw(&key, &[1]);
+		assert!(OpenRollup::has_unversioned_storage());
+		on_runtime_upgrade();
+		assert_eq!(StorageVersion::get::<OpenRollup>(), StorageVersion::new(0));
+	});
+}
+
+/// Transfer the ownership of a zkapp in two steps
+#[test]
+fn transfer_ownership_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let new_owner = RuntimeOrigin::signed(5);
+
+		assert_noop!(
+			OpenRollup::transfer_ownership(user.clone(), zkapp_id, 5),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			OpenRollup::accept_ownership(new_owner.clone(), zkapp_id),
+			Error::<Test>::NoPendingOwner
+		);
+
+		assert_ok!(OpenRollup::transfer_ownership(owner.clone(), zkapp_id, 5));
+		// check event
+		assert_last_event(Event::OwnershipTransferProposed(zkapp_id, OWNER_ID, 5).into());
+		// the owner is not changed until the new owner accepts
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id).unwrap().owner, OWNER_ID);
+		assert_noop!(OpenRollup::accept_ownership(user, zkapp_id), Error::<Test>::NotPendingOwner);
+
+		assert_ok!(OpenRollup::accept_ownership(new_owner.clone(), zkapp_id));
+		// check event
+		assert_last_event(Event::OwnershipTransferred(zkapp_id, OWNER_ID, 5).into());
+		// check data
+		assert_eq!(Zkapps::<Test>::try_get(zkapp_id).unwrap().owner, 5);
+		assert!(!PendingOwners::<Test>::contains_key(zkapp_id));
+		assert_noop!(OpenRollup::set_inactive(owner, zkapp_id), Error::<Test>::NotOwner);
+		assert_ok!(OpenRollup::set_inactive(new_owner, zkapp_id));
+	});
+}
+
+/// The lifecycle actions forced by `ForceOrigin`
+#[test]
+fn force_origin_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let root = RuntimeOrigin::root();
+		let new_owner = 5;
+		let new_submitter = 6;
+
+		assert_noop!(
+			OpenRollup::force_set_inactive(owner.clone(), zkapp_id, false),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_noop!(
+			OpenRollup::force_transfer_ownership(owner.clone(), zkapp_id, new_owner),
+			sp_runtime::DispatchError::BadOrigin
+		);
+
+		// remove a supported asset
+		assert_ok!(OpenRollup::remove_asset_support(
+			root.clone(),
+			zkapp_id,
+			Asset::Fungible(ASSET_ID)
+		));
+		// check event
+		assert_last_event(Event::RemoveAssetSupport(zkapp_id, Asset::Fungible(ASSET_ID)).into());
+		assert_noop!(
+			OpenRollup::remove_asset_support(root.clone(), zkapp_id, Asset::Fungible(ASSET_ID)),
+			Error::<Test>::NotSupportAsset
+		);
+		assert_noop!(
+			OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Fungible(ASSET_ID, 10)),
+			Error::<Test>::NotSupportAsset
+		);
+		// the balances of the asset can still be withdrawn
+		OpenRollup::add_zkapp_user_asset(zkapp_id, USER_ID, &AssetValue::Fungible(ASSET_ID, 10))
+			.unwrap();
+		assert_ok!(OpenRollup::withdraw(user.clone(), zkapp_id, AssetValue::Fungible(ASSET_ID, 5)));
+		assert_ok!(OpenRollup::force_withdraw(user, zkapp_id, AssetValue::Fungible(ASSET_ID, 5)));
+
+		// change the submitter
+		assert_ok!(OpenRollup::force_change_submitter(root.clone(), zkapp_id, new_submitter));
+		// check event
+		assert_last_event(Event::ChangeSubmitter(zkapp_id, new_submitter).into());
+		assert_eq!(
+			Zkapps::<Test>::try_get(zkapp_id).unwrap().submitters.to_vec(),
+			vec![new_submitter]
+		);
+		assert!(Unbondings::<Test>::contains_key(zkapp_id, SUBMITTER_ID));
+
+		// transfer the ownership
+		assert_ok!(OpenRollup::force_transfer_ownership(root.clone(), zkapp_id, new_owner));
+		// check event
+		assert_last_event(Event::OwnershipTransferred(zkapp_id, OWNER_ID, new_owner).into());
+		assert_noop!(OpenRollup::set_inactive(owner, zkapp_id), Error::<Test>::NotOwner);
+
+		// set inactive
+		assert_ok!(OpenRollup::force_set_inactive(root.clone(), zkapp_id, false));
+		// check event
+		assert_last_event(Event::SetInactive(zkapp_id).into());
+		assert!(Zkapps::<Test>::try_get(zkapp_id).unwrap().is_inactive);
+		assert!(!LastBatchAt::<Test>::contains_key(zkapp_id));
+		assert_noop!(
+			OpenRollup::force_set_inactive(root.clone(), zkapp_id, false),
+			Error::<Test>::Inactive
+		);
+
+		// set inactive and slash the submitters
+		let (other_zkapp_id, _, _) = setup_app(456);
+		assert_ok!(OpenRollup::force_set_inactive(root, other_zkapp_id, true));
+		// check event
+		System::assert_has_event(Event::SubmitterSlashed(other_zkapp_id, SUBMITTER_ID, 100).into());
+		assert_last_event(Event::ForcedInactive(other_zkapp_id, InactiveReason::Forced).into());
+		assert!(Zkapps::<Test>::try_get(other_zkapp_id).unwrap().is_inactive);
+		assert!(!SubmitterBonds::<Test>::contains_key(other_zkapp_id, SUBMITTER_ID));
+	});
+}
+
+/// Charge the fees of L1 operations and pay them to the submitter
+#[test]
+fn zkapp_fee_policy_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let fee_policy = FeePolicy::PerOperation(
+			AssetValue::Currency(10),
+			AssetValue::Currency(20),
+			AssetValue::Currency(30),
+		);
+		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+		let fee_destination = <Test as Config>::FeeDestination::get();
+
+		assert_noop!(
+			OpenRollup::set_fee_policy(user.clone(), zkapp_id, Some(fee_policy.clone())),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			OpenRollup::set_fee_policy(
+				owner.clone(),
+				zkapp_id,
+				Some(FeePolicy::Flat(AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![1])))
+			),
+			Error::<Test>::InvalidFeePolicy
+		);
+		assert_ok!(OpenRollup::set_fee_policy(owner.clone(), zkapp_id, Some(fee_policy.clone())));
+		// check event
+		assert_last_event(Event::FeePolicySet(zkapp_id, Some(fee_policy)).into());
+
+		// the fee is held by the pallet until the operation is consumed
+		assert_ok!(OpenRollup::deposit(user.clone(), zkapp_id, asset_value.clone()));
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), 9990);
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+		assert_eq!(L1OperationFees::<Test>::get(zkapp_id, 0), Some(AssetValue::Currency(10)));
+
+		let submitter_balance = <Test as Config>::Currency::free_balance(SUBMITTER_ID);
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			vec![Operation::Deposit(USER_ID, asset_value)],
+			vec![1, 2, 3],
+			None,
+		));
+		// check data, 10% of the fee is the protocol's cut, the fees are claimed later
+		assert_eq!(PendingWithdrawals::<Test>::get((SUBMITTER_ID, Asset::Currency)), 9);
+		assert_eq!(PendingWithdrawals::<Test>::get((fee_destination, Asset::Currency)), 1);
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+		assert!(!L1OperationFees::<Test>::contains_key(zkapp_id, 0));
+		assert_ok!(OpenRollup::claim_withdrawal(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			SUBMITTER_ID,
+			AssetValue::Currency(9)
+		));
+		assert_eq!(<Test as Config>::Currency::free_balance(SUBMITTER_ID), submitter_balance + 9);
+
+		// the fees of the operations not consumed are refunded when exit
+		assert_ok!(OpenRollup::withdraw(user.clone(), zkapp_id, AssetValue::Fungible(ASSET_ID, 5)));
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), 9970);
+		// forced withdrawals are charged the same fee
+		assert_ok!(OpenRollup::force_withdraw(
+			user.clone(),
+			zkapp_id,
+			AssetValue::Fungible(ASSET_ID, 5)
+		));
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), 9950);
+		assert_eq!(L1OperationFees::<Test>::get(zkapp_id, 2), Some(AssetValue::Currency(20)));
+		assert_ok!(OpenRollup::set_inactive(owner, zkapp_id));
+		assert_ok!(OpenRollup::exit(user, zkapp_id));
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), 9990);
+	});
+}
+
+/// move asset from a zkapp to another zkapp
+#[test]
+fn zkapp_move_asset_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id_1, _owner_2, _) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id_2, _owner_2, user) = setup_app(456);
+		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+
+		// Add asset for user befor move
+		OpenRollup::add_zkapp_user_asset(zkapp_id_1, USER_ID, &asset_value).unwrap();
+
+		assert_ok!(OpenRollup::move_asset(user, zkapp_id_1, zkapp_id_2, asset_value.clone(),));
+		// check event
+		assert_last_event(
+			Event::MoveAsset(zkapp_id_1, zkapp_id_2, USER_ID, asset_value.clone()).into(),
+		);
+		// check data
+		assert_eq!(
+			L1Operations::<Test>::get(zkapp_id_1, 0).unwrap().operation,
+			Operation::Move(USER_ID, zkapp_id_2, asset_value)
+		);
+	});
+}
+
+/// A batch can't move assets to its own zkapp, as `move_asset`
+#[test]
+fn zkapp_batch_move_to_same_zkapp_should_fail() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+		OpenRollup::deposit(user, zkapp_id, asset_value.clone()).unwrap();
+
+		assert_noop!(
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id,
+				H256::from_low_u64_be(0),
+				H256::from_low_u64_be(1),
+				1,
+				vec![
+					Operation::Deposit(USER_ID, asset_value.clone()),
+					Operation::Move(USER_ID, zkapp_id, asset_value),
+				],
+				vec![1, 2, 3],
+				None,
+			),
+			Error::<Test>::SameZkapp
+		);
+	});
+}
+
+/// A batch can only move assets to an active zkapp supporting them, an L1 move queued before the
+/// destination changed is skipped
+#[test]
+fn zkapp_batch_move_to_unavailable_zkapp() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id_1, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id_2, owner_2, _) = setup_app(456);
+		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+		let move_op = Operation::Move(USER_ID, zkapp_id_2, asset_value.clone());
+		let submit_batch = |l1_operations_pos, operations| {
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id_1,
+				H256::from_low_u64_be(0),
+				H256::from_low_u64_be(1),
+				l1_operations_pos,
+				operations,
+				vec![1, 2, 3],
+				None,
+			)
+		};
+
+		OpenRollup::add_zkapp_user_asset(zkapp_id_1, USER_ID, &asset_value).unwrap();
+		assert_ok!(OpenRollup::move_asset(user, zkapp_id_1, zkapp_id_2, asset_value));
+		assert_ok!(OpenRollup::set_inactive(owner_2, zkapp_id_2));
+
+		// a move of the batch itself fails
+		assert_noop!(
+			submit_batch(1, vec![move_op.clone(), move_op.clone()]),
+			Error::<Test>::Inactive
+		);
+
+		// the L1 move is skipped
+		assert_ok!(submit_batch(1, vec![move_op.clone()]));
+		// check event
+		let skipped = Event::L1OperationSkipped(zkapp_id_1, move_op);
+		assert!(System::events().iter().any(|record| record.event == skipped.clone().into()));
+		// check data, the assets stay in the source zkapp
+		assert_eq!(
+			ZkappsBalances::<Test>::get((zkapp_id_1, USER_ID, Asset::Fungible(ASSET_ID))),
+			10
+		);
+		assert!(L1OperationsQueue::<Test>::get(zkapp_id_1).is_empty());
+		assert!(L1OperationsQueue::<Test>::get(zkapp_id_2).is_empty());
+	});
+}
+
+/// Send a message with assets from a zkapp to another zkapp
+#[test]
+fn zkapp_message_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id_1, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id_2, _owner, _) = setup_app(456);
+		let asset_value = AssetValue::Fungible(ASSET_ID, 10);
+		let payload: MessagePayload = bounded_vec![1, 2, 3];
+
+		OpenRollup::deposit(user, zkapp_id_1, asset_value.clone()).unwrap();
+		let message = Operation::Message(
+			zkapp_id_1,
+			zkapp_id_2,
+			payload.clone(),
+			Some((USER_ID, AssetValue::Fungible(ASSET_ID, 4))),
+		);
+
+		// a zkapp can't send messages for others
+		let other_message = Operation::Message(zkapp_id_2, 789, payload.clone(), None);
+		assert_noop!(
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id_1,
+				H256::from_low_u64_be(0),
+				H256::from_low_u64_be(1),
+				0,
+				vec![other_message],
+				vec![1, 2, 3],
+				None,
+			),
+			Error::<Test>::InvalidBatchParams
+		);
+
+		// the destination zkapp must be active and support the assets
+		let (zkapp_id_3, owner_3, _) = setup_app(789);
+		assert_ok!(OpenRollup::set_inactive(owner_3, zkapp_id_3));
+		for (to_zkapp_id, asset_value, error) in [
+			(zkapp_id_3, AssetValue::Fungible(ASSET_ID, 4), Error::<Test>::Inactive),
+			(zkapp_id_2, AssetValue::Fungible(ASSET_ID + 1, 4), Error::<Test>::NotSupportAsset),
+		] {
+			let message = Operation::Message(
+				zkapp_id_1,
+				to_zkapp_id,
+				payload.clone(),
+				Some((USER_ID, asset_value)),
+			);
+			assert_noop!(
+				OpenRollup::submit_batch(
+					RuntimeOrigin::signed(SUBMITTER_ID),
+					zkapp_id_1,
+					H256::from_low_u64_be(0),
+					H256::from_low_u64_be(1),
+					0,
+					vec![message],
+					vec![1, 2, 3],
+					None,
+				),
+				error
+			);
+		}
+
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id_1,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			vec![Operation::Deposit(USER_ID, asset_value), message.clone()],
+			vec![1, 2, 3],
+			None,
+		));
+		// check event
+		assert!(System::events().iter().any(|record| record.event ==
+			Event::MessageSent(zkapp_id_1, zkapp_id_2, payload.clone()).into()));
+		// check data, the message is queued for the destination zkapp
+		assert_eq!(
+			ZkappsBalances::<Test>::get((zkapp_id_1, USER_ID, Asset::Fungible(ASSET_ID))),
+			6
+		);
+		assert_eq!(OpenRollup::pending_l1_operations(zkapp_id_2, 0, 10), vec![message.clone()]);
+
+		// the destination zkapp receives the message as an L1 operation
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id_2,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			vec![message],
+			vec![1, 2, 3],
+			None,
+		));
+		assert_eq!(
+			ZkappsBalances::<Test>::get((zkapp_id_2, USER_ID, Asset::Fungible(ASSET_ID))),
+			4
+		);
+		assert!(L1OperationsQueue::<Test>::get(zkapp_id_2).is_empty());
+	});
+}
+
+/// The hash chain of the L1 operations is updated when queued and consumed
+#[test]
+fn l1_operations_hash_chain_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let asset_value_1 = AssetValue::Currency(10);
+		let asset_value_2 = AssetValue::Fungible(ASSET_ID, 10);
+		let deposit_1 = Operation::Deposit(USER_ID, asset_value_1.clone());
+		let deposit_2 = Operation::Deposit(USER_ID, asset_value_2.clone());
+
+		let hash_1 = chain_l1_operation(&[0u8; 32], &deposit_1);
+		let hash_2 = chain_l1_operation(&hash_1, &deposit_2);
+
+		assert_eq!(OpenRollup::l1_operations_hash(zkapp_id, 0), Some([0u8; 32]));
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_1).unwrap();
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_2).unwrap();
+		assert_eq!(L1Operations::<Test>::get(zkapp_id, 0).unwrap().hash, hash_1);
+		assert_eq!(L1Operations::<Test>::get(zkapp_id, 1).unwrap().hash, hash_2);
+		assert_eq!(OpenRollup::l1_operations_hash(zkapp_id, 1), Some(hash_1));
+		assert_eq!(OpenRollup::l1_operations_hash(zkapp_id, 2), Some(hash_2));
+
+		// consume the first L1 operation
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			vec![deposit_1],
+			vec![1, 2, 3],
+			None,
+		));
+		assert_eq!(L1OperationsConsumedHash::<Test>::get(zkapp_id), hash_1);
+		assert_eq!(L1OperationsQueue::<Test>::get(zkapp_id), QueueIndices { head: 1, tail: 2 });
+		assert!(!L1Operations::<Test>::contains_key(zkapp_id, 0));
+		assert_eq!(OpenRollup::l1_operations_hash(zkapp_id, 0), Some(hash_1));
+		assert_eq!(OpenRollup::l1_operations_hash(zkapp_id, 1), Some(hash_2));
+
+		// the chain continues from the queued operations
+		let asset_value_3 = AssetValue::Currency(20);
+		OpenRollup::deposit(user, zkapp_id, asset_value_3.clone()).unwrap();
+		assert_eq!(
+			OpenRollup::l1_operations_hash(zkapp_id, 2),
+			Some(chain_l1_operation(&hash_2, &Operation::Deposit(USER_ID, asset_value_3)))
+		);
+		// no hash past the queue
+		assert_eq!(OpenRollup::l1_operations_hash(zkapp_id, 3), None);
+	});
+}
+
+// Exit from zkapp
+#[test]
+fn zkapp_exit_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+
+		// need add asset for user befor withdraw
+		let asset_value = AssetValue::Currency(10);
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value).unwrap();
+
+		// check currency balance
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+
+		// set zkapp to inactive
+		OpenRollup::set_inactive(owner, zkapp_id).unwrap();
+
+		assert_ok!(OpenRollup::exit(user, zkapp_id));
+		// check event
+		assert_last_event(Event::Exit(zkapp_id, USER_ID).into());
+
+		// check currency balance
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 0);
+	});
+}
+
+/// The L1 deposits of an exited user are refunded page by page
+#[test]
+fn zkapp_exit_refunds_l1_deposits_by_pages() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+
+		// 3 deposits with the page size of 2
+		for amount in [10, 20, 30] {
+			OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Currency(amount)).unwrap();
+		}
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 60);
+
+		assert_noop!(
+			OpenRollup::refund_l1_deposits(user.clone(), zkapp_id),
+			Error::<Test>::NoL1DepositsToRefund
+		);
+
+		OpenRollup::set_inactive(owner, zkapp_id).unwrap();
+		assert_ok!(OpenRollup::exit(user.clone(), zkapp_id));
+		// the first page is refunded
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 30);
+		assert_eq!(ExitRefundCursors::<Test>::get(zkapp_id, USER_ID), Some(2));
+
+		assert_ok!(OpenRollup::refund_l1_deposits(user.clone(), zkapp_id));
+		// check event
+		assert_last_event(Event::L1DepositsRefunded(zkapp_id, USER_ID, 3).into());
+		// check data
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 0);
+		assert_eq!(ExitRefundCursors::<Test>::get(zkapp_id, USER_ID), None);
+
+		assert_noop!(
+			OpenRollup::refund_l1_deposits(user, zkapp_id),
+			Error::<Test>::NoL1DepositsToRefund
+		);
+	});
+}
+
+/// Register a zkapp whose state tree includes `account` as the first leaf of two leaves.
+fn setup_app_with_state_tree(account: &AccountOf<Test, ()>) -> (ZkappId, state_tree::Node) {
+	let zkapp_id = NextZkappId::<Test>::get();
+	let sibling = Blake2StateTree::hash_leaf(b"other leaf");
+	let state_root =
+		Blake2StateTree::hash_nodes(&Blake2StateTree::hash_leaf(&account.encode()), &sibling);
+
+	OpenRollup::zkapp_register(
+		RuntimeOrigin::signed(OWNER_ID),
+		H256::from_low_u64_be(PROGRAM_HASH_64),
+		ZkvmType::Fake,
+		SUBMITTER_ID,
+		H256::from(state_root),
+	)
+	.unwrap();
+
+	(zkapp_id, sibling)
+}
+
+// Exit from zkapp with a proof of the state tree
+#[test]
+fn zkapp_exit_with_proof_should_work() {
+	new_test_ext().execute_with(|| {
+		let account = Account { user: USER_ID, assets: bounded_vec![AssetValue::Currency(10)] };
+		let (zkapp_id, sibling) = setup_app_with_state_tree(&account);
+		let user = RuntimeOrigin::signed(USER_ID);
+		let state_root = Zkapps::<Test>::get(zkapp_id).unwrap().state_root;
+
+		// the zkapp holds the assets of the owner and the user
+		OpenRollup::bond_submitter(RuntimeOrigin::signed(SUBMITTER_ID), zkapp_id).unwrap();
+		OpenRollup::deposit(RuntimeOrigin::signed(OWNER_ID), zkapp_id, AssetValue::Currency(10))
+			.unwrap();
+		OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Currency(10)).unwrap();
+		OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			state_root,
+			state_root,
+			2,
+			vec![
+				Operation::Deposit(OWNER_ID, AssetValue::Currency(10)),
+				Operation::Deposit(USER_ID, AssetValue::Currency(10)),
+			],
+			vec![1, 2, 3],
+			None,
+		)
+		.unwrap();
+		assert_eq!(ZkappsCustody::<Test>::get(zkapp_id, Asset::Currency), 20);
+		OpenRollup::set_inactive(RuntimeOrigin::signed(OWNER_ID), zkapp_id).unwrap();
+
+		let user_balance = <Test as Config>::Currency::free_balance(USER_ID);
+		assert_ok!(OpenRollup::exit_with_proof(
+			user.clone(),
+			zkapp_id,
+			account.clone(),
+			0,
+			vec![sibling]
+		));
+		// check event
+		assert_last_event(Event::Exit(zkapp_id, USER_ID).into());
+
+		// check currency balance
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), user_balance + 10);
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+		// check account data
+		assert!(!ZkappsBalances::<Test>::contains_key((zkapp_id, USER_ID, Asset::Currency)));
+		assert_eq!(ZkappsCustody::<Test>::get(zkapp_id, Asset::Currency), 10);
+
+		// exit only once
+		assert_noop!(
+			OpenRollup::exit_with_proof(user, zkapp_id, account, 0, vec![sibling]),
+			Error::<Test>::HasExit
+		);
+	});
+}
+
+// Exit from zkapp with a proof of more assets than the zkapp holds
+#[test]
+fn zkapp_exit_with_proof_over_custody() {
+	new_test_ext().execute_with(|| {
+		let account = Account { user: USER_ID, assets: bounded_vec![AssetValue::Currency(10)] };
+		let (zkapp_id, sibling) = setup_app_with_state_tree(&account);
+
+		// the pallet account holds the deposit, but it is not executed for the zkapp
+		OpenRollup::deposit(RuntimeOrigin::signed(OWNER_ID), zkapp_id, AssetValue::Currency(10))
+			.unwrap();
+		OpenRollup::set_inactive(RuntimeOrigin::signed(OWNER_ID), zkapp_id).unwrap();
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+
+		assert_noop!(
+			OpenRollup::exit_with_proof(
+				RuntimeOrigin::signed(USER_ID),
+				zkapp_id,
+				account,
+				0,
+				vec![sibling]
+			),
+			Error::<Test>::NoEnoughCustody
+		);
+	});
+}
+
+// Exit from zkapp with an invalid proof of the state tree
+#[test]
+fn zkapp_exit_with_invalid_proof() {
+	new_test_ext().execute_with(|| {
+		let account = Account { user: USER_ID, assets: bounded_vec![AssetValue::Currency(10)] };
+		let (zkapp_id, sibling) = setup_app_with_state_tree(&account);
+		let user = RuntimeOrigin::signed(USER_ID);
+
+		// zkapp is active
+		assert_noop!(
+			OpenRollup::exit_with_proof(user.clone(), zkapp_id, account.clone(), 0, vec![sibling]),
+			Error::<Test>::NotInactive
+		);
+
+		OpenRollup::set_inactive(RuntimeOrigin::signed(OWNER_ID), zkapp_id).unwrap();
+
+		// not the owner of the leaf
+		assert_noop!(
+			OpenRollup::exit_with_proof(
+				RuntimeOrigin::signed(OWNER_ID),
+				zkapp_id,
+				account.clone(),
+				0,
+				vec![sibling]
+			),
+			Error::<Test>::InvalidStateProof
+		);
+		// more assets than the leaf
+		let forged = Account { user: USER_ID, assets: bounded_vec![AssetValue::Currency(100)] };
+		assert_noop!(
+			OpenRollup::exit_with_proof(user.clone(), zkapp_id, forged, 0, vec![sibling]),
+			Error::<Test>::InvalidStateProof
+		);
+		// wrong position
+		assert_noop!(
+			OpenRollup::exit_with_proof(user, zkapp_id, account, 1, vec![sibling]),
+			Error::<Test>::InvalidStateProof
+		);
+	});
+}
+
+// submit a batch
+#[test]
+fn zkapp_submit_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+
+		// state_root
+		let state_root_1 = H256::from_low_u64_be(0);
+		let state_root_2 = H256::from_low_u64_be(1);
+
+		// zk proof
+		let zk_proof = vec![1, 2, 3];
+
+		// assets
+		let asset_value_1 = AssetValue::Currency(10);
+		let asset_value_2 = AssetValue::Fungible(ASSET_ID, 10);
+		let asset_value_3 = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3, 4]);
+
+		let l1_operations_pos = 3;
+
+		let user_id_2 = 6;
+
+		// deposit
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_1.clone()).unwrap();
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_2.clone()).unwrap();
+		OpenRollup::deposit(user, zkapp_id, asset_value_3.clone()).unwrap();
+
+		// check currency balance
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+
+		let operations = vec![
+			Operation::Deposit(USER_ID, asset_value_1),
+			Operation::Deposit(USER_ID, asset_value_2),
+			Operation::Deposit(USER_ID, asset_value_3),
+			Operation::Transfer(USER_ID, user_id_2, AssetValue::Fungible(ASSET_ID, 2)),
+			Operation::Transfer(
+				USER_ID,
+				user_id_2,
+				AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3]),
+			),
+		];
+
+		// submit_batch
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			state_root_1,
+			state_root_2,
+			l1_operations_pos,
+			operations.clone(),
+			zk_proof,
+			None,
+		));
+
+		// check event
+		assert_last_event(
+			Event::SubmitBatch(zkapp_id, state_root_1, state_root_2, operations).into(),
+		);
+
+		// check account data
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Currency)), 10);
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Fungible(ASSET_ID))), 8);
+		assert_eq!(
+			ZkappsBalances::<Test>::get((zkapp_id, user_id_2, Asset::Fungible(ASSET_ID))),
+			2
+		);
+		assert_eq!(
+			ZkappsItems::<Test>::get((zkapp_id, USER_ID, COLLECTION_ID)),
+			Some(bounded_vec![4])
+		);
+		assert_eq!(
+			ZkappsItems::<Test>::get((zkapp_id, user_id_2, COLLECTION_ID)),
+			Some(bounded_vec![3])
+		);
+	});
+}
+
+/// Commit a batch and execute its operations in chunks
+#[test]
+fn zkapp_commit_and_execute_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let state_root_1 = H256::from_low_u64_be(0);
+		let state_root_2 = H256::from_low_u64_be(1);
+		let user_id_2 = 6;
+
+		let asset_value_1 = AssetValue::Currency(10);
+		let asset_value_2 = AssetValue::Fungible(ASSET_ID, 10);
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_1.clone()).unwrap();
+		OpenRollup::deposit(user, zkapp_id, asset_value_2.clone()).unwrap();
+
+		let operations = vec![
+			Operation::Deposit(USER_ID, asset_value_1),
+			Operation::Deposit(USER_ID, asset_value_2),
+			Operation::Transfer(USER_ID, user_id_2, AssetValue::Fungible(ASSET_ID, 2)),
+		];
+		let commitment = operations_commitment(&operations, Default::default());
+
+		assert_ok!(OpenRollup::commit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			state_root_1,
+			state_root_2,
+			2,
+			operations.clone(),
+			vec![1, 2, 3],
+			None,
+		));
+		// check event
+		assert_last_event(
+			Event::BatchCommitted(
+				zkapp_id,
+				state_root_1,
+				state_root_2,
+				operations.clone(),
+				commitment,
+			)
+			.into(),
+		);
+		// check data, the state root is recorded and the operations are not executed
+		assert_eq!(Zkapps::<Test>::get(zkapp_id).unwrap().state_root, state_root_2);
+		assert_eq!(L1OperationsQueue::<Test>::get(zkapp_id).len(), 2);
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Currency)), 0);
+
+		// no new batch until the pending one is executed
+		assert_noop!(
+			OpenRollup::commit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id,
+				state_root_2,
+				state_root_1,
+				0,
+				vec![],
+				vec![1, 2, 3],
+				None,
+			),
+			Error::<Test>::BatchPending
+		);
+
+		// the chunk must match the commitment
+		let rest = operations_commitment(&operations[1..], Default::default());
+		assert_noop!(
+			OpenRollup::execute_batch(
+				RuntimeOrigin::signed(user_id_2),
+				zkapp_id,
+				operations[1..].to_vec(),
+				Default::default(),
+			),
+			Error::<Test>::InvalidOperationsCommitment
+		);
+		assert_noop!(
+			OpenRollup::execute_batch(
+				RuntimeOrigin::signed(user_id_2),
+				zkapp_id,
+				operations[..1].to_vec(),
+				Default::default(),
+			),
+			Error::<Test>::InvalidOperationsCommitment
+		);
+
+		// anyone can execute the batch
+		assert_ok!(OpenRollup::execute_batch(
+			RuntimeOrigin::signed(user_id_2),
+			zkapp_id,
+			operations[..1].to_vec(),
+			rest,
+		));
+		assert_last_event(Event::BatchExecuted(zkapp_id, 1, 3).into());
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Currency)), 10);
+		// the L1 operations are removed from the queue once executed
+		assert_eq!(L1OperationsQueue::<Test>::get(zkapp_id).len(), 1);
+
+		assert_ok!(OpenRollup::execute_batch(
+			RuntimeOrigin::signed(user_id_2),
+			zkapp_id,
+			operations[1..].to_vec(),
+			Default::default(),
+		));
+		assert_last_event(Event::BatchExecuted(zkapp_id, 3, 3).into());
+		assert!(!PendingBatches::<Test>::contains_key(zkapp_id));
+		assert!(L1OperationsQueue::<Test>::get(zkapp_id).is_empty());
+		assert_eq!(
+			ZkappsBalances::<Test>::get((zkapp_id, user_id_2, Asset::Fungible(ASSET_ID))),
+			2
+		);
+
+		assert_noop!(
+			OpenRollup::execute_batch(
+				RuntimeOrigin::signed(user_id_2),
+				zkapp_id,
+				vec![],
+				Default::default(),
+			),
+			Error::<Test>::NoPendingBatch
+		);
+	});
+}
+
+/// A pending batch whose operations fail to execute is abandoned when the zkapp is set inactive
+#[test]
+fn zkapp_failed_batch_is_abandoned() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		OpenRollup::deposit(user.clone(), zkapp_id, AssetValue::Currency(10)).unwrap();
+
+		// the second operation withdraws more than the balance of the user
+		let operations = vec![
+			Operation::Deposit(USER_ID, AssetValue::Currency(10)),
+			Operation::Withdraw(USER_ID, AssetValue::Currency(100)),
+		];
+		assert_ok!(OpenRollup::commit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			1,
+			operations.clone(),
+			vec![1, 2, 3],
+			None,
+		));
+		assert_noop!(
+			OpenRollup::execute_batch(user.clone(), zkapp_id, operations, Default::default()),
+			Error::<Test>::InvalidAssets
+		);
+
+		// the liveness watchdog sets the stuck zkapp inactive
+		OpenRollup::on_idle(102, Weight::MAX);
+		// check event
+		assert!(System::events()
+			.iter()
+			.any(|record| record.event == Event::BatchAbandoned(zkapp_id, 0, 2).into()));
+		assert_last_event(Event::ForcedInactive(zkapp_id, InactiveReason::BatchTimeout).into());
+		// check data, the deposit never executed is still queued
+		assert!(!PendingBatches::<Test>::contains_key(zkapp_id));
+		assert_eq!(L1OperationsQueue::<Test>::get(zkapp_id).len(), 1);
+
+		// the user exits with the deposit refunded
+		let user_balance = <Test as Config>::Currency::free_balance(USER_ID);
+		assert_ok!(OpenRollup::exit(user, zkapp_id));
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), user_balance + 10);
+	});
+}
+
+/// The L1 deposits of a batch abandoned before they are executed are refunded by `exit`
+#[test]
+fn zkapp_abandoned_batch_deposits_are_refunded() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let (zkapp_id_2, _, _) = setup_app(456);
+		let asset_value_1 = AssetValue::Currency(10);
+		let asset_value_2 = AssetValue::Fungible(ASSET_ID, 10);
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_1.clone()).unwrap();
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_2.clone()).unwrap();
+
+		let operations = vec![
+			Operation::Deposit(USER_ID, asset_value_1),
+			Operation::Deposit(USER_ID, asset_value_2),
+			Operation::Move(USER_ID, zkapp_id_2, AssetValue::Currency(4)),
+		];
+		assert_ok!(OpenRollup::commit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(1),
+			2,
+			operations,
+			vec![1, 2, 3],
+			None,
+		));
+
+		// the owner sets the zkapp inactive before the batch is executed
+		assert_ok!(OpenRollup::set_inactive(owner, zkapp_id));
+		System::assert_has_event(Event::BatchAbandoned(zkapp_id, 0, 3).into());
+		assert_eq!(L1OperationsQueue::<Test>::get(zkapp_id).len(), 2);
+		assert!(!ZkappsCustody::<Test>::contains_key(zkapp_id, Asset::Currency));
+
+		// the deposits are refunded, nothing is moved to the other zkapp
+		let currency_balance = <Test as Config>::Currency::free_balance(USER_ID);
+		let fungible_balance = Assets::balance(ASSET_ID, USER_ID);
+		assert_ok!(OpenRollup::exit(user, zkapp_id));
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), currency_balance + 10);
+		assert_eq!(Assets::balance(ASSET_ID, USER_ID), fungible_balance + 10);
+		assert!(L1OperationsQueue::<Test>::get(zkapp_id_2).is_empty());
+	});
+}
+
+/// Withdraw by a batch and claim the pending withdrawals
+#[test]
+fn zkapp_claim_withdrawal_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, _owner, user) = setup_app(PROGRAM_HASH_64);
+		let state_root_1 = H256::from_low_u64_be(0);
+		let state_root_2 = H256::from_low_u64_be(1);
+		let user_id_2 = 6;
+
+		let asset_value_1 = AssetValue::Currency(10);
+		let asset_value_2 = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3, 4]);
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_1.clone()).unwrap();
+		OpenRollup::deposit(user, zkapp_id, asset_value_2.clone()).unwrap();
+
+		let operations = vec![
+			Operation::Deposit(USER_ID, asset_value_1),
+			Operation::Deposit(USER_ID, asset_value_2),
+			Operation::Withdraw(USER_ID, AssetValue::Currency(6)),
+			Operation::Withdraw(USER_ID, AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3])),
+		];
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			state_root_1,
+			state_root_2,
+			2,
+			operations,
+			vec![1, 2, 3],
+			None,
+		));
+
+		// the withdrawals are pending, the assets are still held by the pallet
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Currency)), 4);
+		assert_eq!(PendingWithdrawals::<Test>::get((USER_ID, Asset::Currency)), 6);
+		assert!(PendingItemWithdrawals::<Test>::contains_key((USER_ID, COLLECTION_ID, 3)));
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 10);
+
+		// no more than the pending withdrawals
+		assert_noop!(
+			OpenRollup::claim_withdrawal(
+				RuntimeOrigin::signed(USER_ID),
+				USER_ID,
+				AssetValue::Currency(7)
+			),
+			Error::<Test>::NoEnoughWithdrawals
+		);
+		assert_noop!(
+			OpenRollup::claim_withdrawal(
+				RuntimeOrigin::signed(USER_ID),
+				USER_ID,
+				AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3, 4])
+			),
+			Error::<Test>::NoEnoughWithdrawals
+		);
+
+		// anyone can claim on the user's behalf
+		let user_balance = <Test as Config>::Currency::free_balance(USER_ID);
+		assert_ok!(OpenRollup::claim_withdrawal(
+			RuntimeOrigin::signed(user_id_2),
+			USER_ID,
+			AssetValue::Currency(6)
+		));
+		assert_last_event(Event::WithdrawalClaimed(USER_ID, AssetValue::Currency(6)).into());
+		assert_eq!(<Test as Config>::Currency::free_balance(USER_ID), user_balance + 6);
+		assert_eq!(<Test as Config>::Currency::free_balance(OpenRollup::account_id()), 4);
+		assert!(!PendingWithdrawals::<Test>::contains_key((USER_ID, Asset::Currency)));
+
+		let items = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3]);
+		assert_ok!(OpenRollup::claim_withdrawal(
+			RuntimeOrigin::signed(USER_ID),
+			USER_ID,
+			items.clone()
+		));
+		assert_last_event(Event::WithdrawalClaimed(USER_ID, items.clone()).into());
+		assert!(!PendingItemWithdrawals::<Test>::contains_key((USER_ID, COLLECTION_ID, 3)));
+
+		// claimed already
+		assert_noop!(
+			OpenRollup::claim_withdrawal(RuntimeOrigin::signed(USER_ID), USER_ID, items),
+			Error::<Test>::NoEnoughWithdrawals
+		);
+	});
+}
+
+/// Add and reduce user's balances of each asset
+#[test]
+fn user_assets_should_work() {
+	new_test_ext().execute_with(|| {
+		let zkapp_id = 0;
+		let currency = AssetValue::Currency(10);
+		let fungible = AssetValue::Fungible(ASSET_ID, 10);
+		let nonfungible = AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![3, 4]);
+
+		for asset_value in [&currency, &fungible, &nonfungible] {
+			assert!(!OpenRollup::check_has_enough_asset(zkapp_id, &USER_ID, asset_value));
+			assert_ok!(OpenRollup::add_user_asset(zkapp_id, &USER_ID, asset_value));
+			assert!(OpenRollup::check_has_enough_asset(zkapp_id, &USER_ID, asset_value));
+		}
+		assert_ok!(OpenRollup::add_user_asset(zkapp_id, &USER_ID, &currency));
+		assert_eq!(ZkappsBalances::<Test>::get((zkapp_id, USER_ID, Asset::Currency)), 20);
+
+		// not enough
+		assert!(matches!(
+			OpenRollup::reduce_user_asset(zkapp_id, &USER_ID, &AssetValue::Currency(21)),
+			Err(Error::<Test>::InvalidAssets)
+		));
+		assert!(matches!(
+			OpenRollup::reduce_user_asset(
+				zkapp_id,
+				&USER_ID,
+				&AssetValue::Nonfungible(COLLECTION_ID, bounded_vec![4, 5])
+			),
+			Err(Error::<Test>::InvalidAssets)
+		));
+
+		// the balances and items used up are removed
+		assert_ok!(OpenRollup::reduce_user_asset(zkapp_id, &USER_ID, &fungible));
+		assert_ok!(OpenRollup::reduce_user_asset(zkapp_id, &USER_ID, &nonfungible));
+		assert!(!ZkappsBalances::<Test>::contains_key((
+			zkapp_id,
+			USER_ID,
+			Asset::Fungible(ASSET_ID)
+		)));
+		assert!(!ZkappsItems::<Test>::contains_key((zkapp_id, USER_ID, COLLECTION_ID)));
+
+		assert_eq!(
+			OpenRollup::take_user_assets(zkapp_id, &USER_ID).ok(),
+			Some(vec![AssetValue::Currency(20)])
+		);
+		assert_eq!(OpenRollup::account(zkapp_id, USER_ID, 0), None);
+
+		// a balance over the balance type of the asset is rejected instead of saturated
+		let max = AssetValue::Currency(u64::MAX);
+		assert_ok!(OpenRollup::add_user_asset(zkapp_id, &USER_ID, &max));
+		assert_ok!(OpenRollup::add_user_asset(zkapp_id, &USER_ID, &max));
+		assert_eq!(OpenRollup::account(zkapp_id, USER_ID, 0), None);
+		assert!(matches!(
+			OpenRollup::take_user_assets(zkapp_id, &USER_ID),
+			Err(Error::<Test>::InvalidAssets)
+		));
+		assert!(!ZkappsBalances::<Test>::contains_key((zkapp_id, USER_ID, Asset::Currency)));
+	});
+}
+
+// submit a miden project batch
+#[test]
+fn zkapp_miden_submit_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		// state_root
+		let old_state_root = H256::repeat_byte(1);
+		let new_state_root = H256::repeat_byte(2);
+
+		let l1_operations_pos = 0;
+		let operations = vec![];
+
+		let program =
+			miden_commitment_program(operations.clone(), new_state_root, l1_operations_pos);
+		let (zkapp_id, _owner, _user) = setup_miden_app(&program, old_state_root);
+
+		let inputs = miden_stack_inputs(zkapp_id, old_state_root, l1_operations_pos);
+
+		let (outputs, proof) =
+			prove(&program, inputs, MemAdviceProvider::default(), ProofOptions::default()).unwrap();
+
+		// zk proof
+		let zk_proof = proof.to_bytes();
+		let zk_outputs = Some(outputs.to_bytes());
+
+		// submit_batch
+		assert_ok!(OpenRollup::submit_batch(
+			RuntimeOrigin::signed(SUBMITTER_ID),
+			zkapp_id,
+			old_state_root,
+			new_state_root,
+			l1_operations_pos,
+			operations.clone(),
+			zk_proof,
+			zk_outputs,
+		));
+
+		// check event
+		assert_last_event(
+			Event::SubmitBatch(zkapp_id, old_state_root, new_state_root, operations).into(),
+		);
+	});
+}
+
+// submit a miden project batch which doesn't match the proven outputs
+#[test]
+fn zkapp_miden_submit_batch_with_other_outputs() {
+	new_test_ext().execute_with(|| {
+		// state_root
+		let old_state_root = H256::repeat_byte(1);
+		let new_state_root = H256::repeat_byte(2);
+
+		let program = miden_commitment_program(vec![], new_state_root, 0);
+		let (zkapp_id, _owner, _user) = setup_miden_app(&program, old_state_root);
+
+		let inputs = miden_stack_inputs(zkapp_id, old_state_root, 0);
+
+		let (outputs, proof) =
+			prove(&program, inputs, MemAdviceProvider::default(), ProofOptions::default()).unwrap();
+
+		// the proof is valid, but the submitted state root isn't the proven one
+		assert_noop!(
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id,
+				old_state_root,
+				H256::repeat_byte(3),
+				0,
+				vec![],
+				proof.to_bytes(),
+				Some(outputs.to_bytes()),
+			),
+			Error::<Test>::InvalidProof
+		);
+	});
+}
+
+// submit a miden project batch proven with other public inputs
+#[test]
+fn zkapp_miden_submit_batch_with_other_inputs() {
+	new_test_ext().execute_with(|| {
+		// state_root
+		let old_state_root = H256::repeat_byte(1);
+		let new_state_root = H256::repeat_byte(2);
+
+		let program = miden_commitment_program(vec![], new_state_root, 0);
+		let (zkapp_id, _owner, _user) = setup_miden_app(&program, old_state_root);
+
+		// the proof starts from another block
+		let inputs = public_inputs_to_stack_inputs(&PublicInputs {
+			old_state_root: old_state_root.as_bytes(),
+			l1_operations_commitment: OpenRollup::l1_operations_hash(zkapp_id, 0).unwrap(),
+			block_number: LastBatchAt::<Test>::get(zkapp_id).unwrap() + 1,
+		})
+		.unwrap();
+
+		let (outputs, proof) =
+			prove(&program, inputs, MemAdviceProvider::default(), ProofOptions::default()).unwrap();
+
+		assert_noop!(
+			OpenRollup::submit_batch(
+				RuntimeOrigin::signed(SUBMITTER_ID),
+				zkapp_id,
+				old_state_root,
+				new_state_root,
+				0,
+				vec![],
+				proof.to_bytes(),
+				Some(outputs.to_bytes()),
+			),
+			Error::<Test>::InvalidProof
+		);
+	});
+}
+
+/// The functions of the runtime API should work
+#[test]
+fn runtime_api_functions_should_work() {
+	new_test_ext().execute_with(|| {
+		let (zkapp_id, owner, user) = setup_app(PROGRAM_HASH_64);
+		let user_id_2 = 6;
+		let asset_value_1 = AssetValue::Currency(10);
+		let asset_value_2 = AssetValue::Fungible(ASSET_ID, 10);
+
+		assert_eq!(OpenRollup::zkapp(zkapp_id), Zkapps::<Test>::get(zkapp_id));
+		assert_eq!(OpenRollup::zkapp(zkapp_id + 1), None);
+
+		// the L1 operations queue
+		OpenRollup::deposit(user.clone(), zkapp_id, asset_value_1.clone()).unwrap();
+		OpenRollup::deposit(user, zkapp_id, asset_value_2.clone()).unwrap();
+		assert_eq!(
+			OpenRollup::pending_l1_operations(zkapp_id, 0, 10),
+			vec![
+				Operation::Deposit(USER_ID, asset_value_1.clone()),
+				Operation::Deposit(USER_ID, asset_value_2.clone())
+			]
+		);
+		assert_eq!(
+			OpenRollup::pending_l1_operations(zkapp_id, 1, 1),
+			vec![Operation::Deposit(USER_ID, asset_value_2)]
+		);
+		assert_eq!(OpenRollup::pending_l1_operations(zkapp_id, 2, 10), vec![]);
+
+		// accounts
+		assert_eq!(OpenRollup::account(zkapp_id, user_id_2, 0), None);
+		OpenRollup::add_zkapp_user_asset(zkapp_id, user_id_2, &asset_value_1).unwrap();
+		assert_eq!(
+			OpenRollup::account(zkapp_id, user_id_2, 0),
+			Some(Account { user: user_id_2, assets: bounded_vec![asset_value_1.clone()] })
+		);
+		assert_eq!(OpenRollup::account(zkapp_id, user_id_2, 1), None);
+		// the assets over `AssetsLimit` are in the next page
+		let user_id_3 = 7;
+		for asset_id in 0..11 {
+			let asset_value = AssetValue::Fungible(asset_id, 1);
+			OpenRollup::add_zkapp_user_asset(zkapp_id, user_id_3, &asset_value).unwrap();
+		}
+		assert_eq!(OpenRollup::account(zkapp_id, user_id_3, 0).unwrap().assets.len(), 10);
+		assert_eq!(OpenRollup::account(zkapp_id, user_id_3, 1).unwrap().assets.len(), 1);
+		assert_eq!(OpenRollup::account(zkapp_id, user_id_3, 2), None);
+
+		// exit
+		assert!(!OpenRollup::has_exited(zkapp_id, user_id_2));
+		OpenRollup::set_inactive(owner, zkapp_id).unwrap();
+		OpenRollup::exit(RuntimeOrigin::signed(user_id_2), zkapp_id).unwrap();
+		assert!(OpenRollup::has_exited(zkapp_id, user_id_2));
+
+		// proof output
+		let operations = vec![Operation::Deposit(USER_ID, asset_value_1)];
+		let proof_output = ProofOutput {
+			operations: operations.clone(),
+			state_root: H256::repeat_byte(2),
+			l1_operations_pos: 1,
+		};
+		assert_eq!(
+			OpenRollup::encode_proof_output(operations, H256::repeat_byte(2), 1),
+			(proof_output.encode(), proof_output.commitment())
+		);
 	});
 }
@@ -16,6 +16,8 @@
 
 use super::*;
 use frame_support::{pallet_prelude::*, BoundedVec};
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT, Zero};
+use verifier::OutputCommitment;
 
 // type alias
 pub(super) type AccountIdLookupOf<T> =
@@ -24,6 +26,9 @@ pub(super) type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
 pub(super) type ProgramHashOf<T, I = ()> = <T as Config<I>>::ProgramHash;
 pub(super) type StateRootOf<T, I = ()> = <T as Config<I>>::StateRoot;
 
+/// The id of a zkapp, assigned in the order of registration.
+pub type ZkappId = u64;
+
 pub(super) type CurrencyBalanceOf<T, I = ()> =
 	<<T as Config<I>>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 pub(super) type FungibleBalanceOf<T, I=()> = <<T as Config<I>>::Fungibles as fungibles::Inspect<
@@ -41,20 +46,24 @@ pub(super) type ItemIdOf<T, I = ()> =
 		<T as frame_system::Config>::AccountId,
 	>>::ItemId;
 
+pub(super) type NegativeImbalanceOf<T, I = ()> = <<T as Config<I>>::Currency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::NegativeImbalance;
+
 pub(super) type AssetsLimitOf<T, I = ()> = <T as Config<I>>::AssetsLimit;
-pub(super) type AssetsItemLimitOf<T, I = ()> = <T as Config<I>>::AssetsItemLimit;
-pub(super) type L1OperationLimitOf<T, I = ()> = <T as Config<I>>::L1OperationLimit;
+pub(super) type MaxSubmittersOf<T, I = ()> = <T as Config<I>>::MaxSubmitters;
 
-pub(super) type ZkappOf<T, I> = Zkapp<
+pub type ZkappOf<T, I> = Zkapp<
+	ProgramHashOf<T, I>,
 	StateRootOf<T, I>,
 	AccountIdOf<T>,
 	AssetIdOf<T, I>,
 	CollectionIdOf<T, I>,
-	AssetValueOf<T, I>,
-	ProgramHashOf<T, I>,
 	AssetsLimitOf<T, I>,
-	L1OperationLimitOf<T, I>,
+	MaxSubmittersOf<T, I>,
 >;
+pub(super) type SubmittersOf<T, I> = BoundedVec<AccountIdOf<T>, MaxSubmittersOf<T, I>>;
+pub type SubmitterRotationOf<T> = SubmitterRotation<<T as frame_system::Config>::BlockNumber>;
 
 pub(super) type AssetOf<T, I = ()> = Asset<AssetIdOf<T, I>, CollectionIdOf<T, I>>;
 pub(super) type SupportedAssetsOf<T, I> = BoundedVec<AssetOf<T, I>, AssetsLimitOf<T, I>>;
@@ -65,18 +74,42 @@ pub(super) type AssetValueOf<T, I> = AssetValue<
 	CollectionIdOf<T, I>,
 	ItemIdOf<T, I>,
 >;
+pub(super) type ItemsOf<T, I> = BoundedVec<ItemIdOf<T, I>, ConstU32<100>>;
+
+/// The payload of a message between zkapps, interpreted by the destination zkapp's program.
+pub type MessagePayload = BoundedVec<u8, ConstU32<256>>;
+
+pub type OperationOf<T, I> = Operation<AccountIdOf<T>, AssetValueOf<T, I>, ZkappId>;
+pub(super) type QueuedL1OperationOf<T, I> =
+	QueuedL1Operation<OperationOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+
+pub type FeePolicyOf<T, I> = FeePolicy<AssetValueOf<T, I>>;
+
+pub(super) type PendingUpgradeOf<T, I> = PendingUpgrade<
+	ProgramHashOf<T, I>,
+	<T as frame_system::Config>::BlockNumber,
+	<T as Config<I>>::MaxVerifyingKeyLen,
+>;
 
-pub(super) type OperationOf<T, I> =
-	Operation<AccountIdOf<T>, AssetValueOf<T, I>, ProgramHashOf<T, I>>;
+pub type ZkappMetadataOf<T, I> =
+	ZkappMetadata<AccountIdOf<T>, CurrencyBalanceOf<T, I>, <T as Config<I>>::StringLimit>;
+pub(super) type MetadataStringOf<T, I> = BoundedVec<u8, <T as Config<I>>::StringLimit>;
 
-pub(super) type AccountOf<T, I> = Account<
+pub(super) type ForcedWithdrawalOf<T, I> =
+	ForcedWithdrawal<<T as frame_system::Config>::BlockNumber>;
+
+pub(super) type BondOf<T, I> = Bond<AccountIdOf<T>, CurrencyBalanceOf<T, I>>;
+pub(super) type UnbondingOf<T, I> =
+	Unbonding<CurrencyBalanceOf<T, I>, <T as frame_system::Config>::BlockNumber>;
+
+pub type AccountOf<T, I> = Account<
 	AccountIdOf<T>,
 	CurrencyBalanceOf<T, I>,
 	AssetIdOf<T, I>,
 	FungibleBalanceOf<T, I>,
 	CollectionIdOf<T, I>,
 	ItemIdOf<T, I>,
-	AssetsItemLimitOf<T, I>,
+	AssetsLimitOf<T, I>,
 >;
 
 /// Asset types supported by open rollup pallet.
@@ -95,6 +128,19 @@ pub enum AssetValue<CurrencyBalance, AssetId, FungibleBalance, CollectionId, Ite
 	Nonfungible(CollectionId, BoundedVec<ItemId, ConstU32<100>>),
 }
 
+impl<CurrencyBalance: Zero, AssetId, FungibleBalance: Zero, CollectionId, ItemId>
+	AssetValue<CurrencyBalance, AssetId, FungibleBalance, CollectionId, ItemId>
+{
+	/// Whether the asset value has no amount or no items.
+	pub fn is_zero(&self) -> bool {
+		match self {
+			AssetValue::Currency(amount) => amount.is_zero(),
+			AssetValue::Fungible(_, amount) => amount.is_zero(),
+			AssetValue::Nonfungible(_, items) => items.is_empty(),
+		}
+	}
+}
+
 /// Implement From trait, from AssetValue to Asset Enum.
 impl<CurrencyBalance, AssetId, FungibleBalance, CollectionId, ItemId>
 	From<AssetValue<CurrencyBalance, AssetId, FungibleBalance, CollectionId, ItemId>>
@@ -114,19 +160,23 @@ impl<CurrencyBalance, AssetId, FungibleBalance, CollectionId, ItemId>
 /// Supported operations of L1 and L2 operations.
 ///
 /// User's L1 Txs can trigger Deposit, Withdraw, Move operations,
-/// L2 Txs can trigger all operations.
+/// L2 Txs can trigger all operations, and a zkapp's Message operations are appended to the L1
+/// operations queue of the destination zkapp.
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
-pub enum Operation<AccountId, AssetValue, ProgramHash> {
+pub enum Operation<AccountId, AssetValue, ZkappId> {
 	/// Deposit, [account_id, asset_value]
 	Deposit(AccountId, AssetValue),
 	/// Withdraw, [account_id, asset_value]
 	Withdraw(AccountId, AssetValue),
-	/// Move asset, [account_id, from_program_hash, asset_value]
-	Move(AccountId, ProgramHash, AssetValue),
+	/// Move asset, [account_id, from_zkapp_id, asset_value]
+	Move(AccountId, ZkappId, AssetValue),
 	/// Transfer, [from_account_id, to_account_id, asset_value]
 	Transfer(AccountId, AccountId, AssetValue),
 	/// Swap, [account_id_1, asset_value_1, account_id_2, asset_value_2]
 	Swap(AccountId, AssetValue, AccountId, AssetValue),
+	/// Message to another zkapp, with the assets of an account moved along,
+	/// [from_zkapp_id, to_zkapp_id, payload, (account_id, asset_value)]
+	Message(ZkappId, ZkappId, MessagePayload, Option<(AccountId, AssetValue)>),
 }
 
 /// Supported zkvm types.
@@ -136,43 +186,233 @@ pub enum ZkvmType {
 	Fake,
 	/// Miden verifier type.
 	Miden,
+	/// Groth16 verifier type, the verifying key must be registered by the zkapp's owner.
+	Groth16(Groth16Curve),
+	/// PLONK verifier type (KZG on BN254, snarkjs circuits), the verifying key must be
+	/// registered by the zkapp's owner. Halo2-KZG proofs are not verified by this type.
+	Plonk,
+	/// RISC Zero verifier type, `program_hash` is the image ID of the guest program.
+	RiscZero,
+}
+
+/// Pairing-friendly curves supported by the Groth16 verifier.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum Groth16Curve {
+	/// BN254 (alt_bn128), e.g. circom and gnark circuits.
+	Bn254,
+	/// BLS12-381.
+	Bls12_381,
+}
+
+/// Why a zkapp is forced inactive.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum InactiveReason {
+	/// No batch submitted for `MaxBatchInterval` blocks.
+	BatchTimeout,
+	/// One L1 operation not handled for `MaxL1OperationAge` blocks.
+	L1OperationTimeout,
+	/// A batch submitted after the deadline of a forced withdrawal didn't include it.
+	ForcedWithdrawalMissed,
+	/// `ForceOrigin` set it inactive and slashed its submitters by `force_set_inactive`.
+	Forced,
 }
 
 /// One zkapp's saved data.
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
-#[scale_info(skip_type_params(AssetsLimit, L1OperationLimit))]
+#[scale_info(skip_type_params(AssetsLimit, MaxSubmitters))]
 pub struct Zkapp<
+	ProgramHash,
 	StateRoot,
 	AccountId,
 	AssetId,
 	CollectionId,
-	AssetValue,
-	ProgramHash,
 	AssetsLimit: Get<u32>,
-	L1OperationLimit: Get<u32>,
+	MaxSubmitters: Get<u32>,
 > {
+	/// The program hash of the zkapp's current program, the one registered unless upgraded.
+	pub(super) program_hash: ProgramHash,
 	/// The zkapp's zkvm type
 	pub(super) zkvm_type: ZkvmType,
-	/// The Zkapp's owner, who can change `submitter`, `is_inactive`.
+	/// The Zkapp's owner, who can change `submitters`, `is_inactive`.
 	pub(super) owner: AccountId,
-	/// The account who can submit one batch.
-	pub(super) submitter: AccountId,
+	/// The accounts who can submit batches, in the order of the rotation.
+	pub(super) submitters: BoundedVec<AccountId, MaxSubmitters>,
 	/// Whether the zkapp is inactive.
 	pub(super) is_inactive: bool,
 	/// Root of the state (e.g. off-chain's users tree) of the zkapp.
 	pub(super) state_root: StateRoot,
 	/// supported Assets of the zkapp.
 	pub(super) supported_assets: BoundedVec<Asset<AssetId, CollectionId>, AssetsLimit>,
-	/// L1 operation queue trigger by L1 Txs.
-	pub(super) l1_operations:
-		BoundedVec<Operation<AccountId, AssetValue, ProgramHash>, L1OperationLimit>,
+}
+
+/// The metadata of a zkapp, to show users what the zkapp is.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(StringLimit))]
+pub struct ZkappMetadata<AccountId, Balance, StringLimit: Get<u32>> {
+	/// The account whose deposit is reserved for the metadata.
+	pub(super) depositor: AccountId,
+	/// The deposit reserved.
+	pub(super) deposit: Balance,
+	/// The name of the zkapp.
+	pub(super) name: BoundedVec<u8, StringLimit>,
+	/// The description of the zkapp.
+	pub(super) description: BoundedVec<u8, StringLimit>,
+	/// The URL of the source code of the zkapp's program.
+	pub(super) source_url: BoundedVec<u8, StringLimit>,
+	/// The hash of the source code, users can check the code at `source_url` compiles to the
+	/// zkapp's program of `program_hash`.
+	pub(super) source_hash: [u8; 32],
+}
+
+/// An upgrade of a zkapp's program proposed by `propose_upgrade`.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+#[scale_info(skip_type_params(MaxVerifyingKeyLen))]
+pub struct PendingUpgrade<ProgramHash, BlockNumber, MaxVerifyingKeyLen: Get<u32>> {
+	/// The program hash of the new program.
+	pub(super) program_hash: ProgramHash,
+	/// The zkvm type of the new program.
+	pub(super) zkvm_type: ZkvmType,
+	/// The verifying key of the new program, `None` if its verifier doesn't require one.
+	pub(super) verifying_key: Option<BoundedVec<u8, MaxVerifyingKeyLen>>,
+	/// The upgrade can be enacted from this block.
+	pub(super) enact_at: BlockNumber,
+}
+
+/// The indices of one zkapp's L1 operation queue triggered by L1 Txs.
+///
+/// The operations queued are stored at `head..tail` of `L1Operations`.
+#[derive(
+	Clone, Copy, Default, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo,
+)]
+pub struct QueueIndices {
+	/// The index of the first operation queued, i.e. the number of operations consumed.
+	pub(super) head: u64,
+	/// The index of the next operation to queue.
+	pub(super) tail: u64,
+}
+
+impl QueueIndices {
+	/// The number of operations queued.
+	pub fn len(&self) -> u64 {
+		self.tail.saturating_sub(self.head)
+	}
+
+	/// Whether no operation is queued.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+/// One L1 operation in the queue of a zkapp.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct QueuedL1Operation<Operation, BlockNumber> {
+	/// The operation.
+	pub(super) operation: Operation,
+	/// The block number when the operation was queued, used by the liveness watchdog.
+	pub(super) queued_at: BlockNumber,
+	/// The hash chain after the operation, see [`L1OperationsHash`].
+	pub(super) hash: L1OperationsHash,
+}
+
+/// The fees a zkapp charges on users' L1 operations, in currency or fungible assets.
+///
+/// The fees are paid to the submitter of the batch consuming the operations, except the
+/// protocol's cut (`ProtocolFeeCut`).
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum FeePolicy<AssetValue> {
+	/// The same fee for every L1 operation.
+	Flat(AssetValue),
+	/// The fees of each kind of L1 operation, [deposit, withdraw, move]
+	PerOperation(AssetValue, AssetValue, AssetValue),
+}
+
+impl<AssetValue> FeePolicy<AssetValue> {
+	/// The fee of one L1 operation.
+	pub fn fee<AccountId, ZkappId>(
+		&self,
+		operation: &Operation<AccountId, AssetValue, ZkappId>,
+	) -> Option<&AssetValue> {
+		match (self, operation) {
+			(FeePolicy::Flat(fee), _) => Some(fee),
+			(FeePolicy::PerOperation(fee, _, _), Operation::Deposit(..)) => Some(fee),
+			(FeePolicy::PerOperation(_, fee, _), Operation::Withdraw(..)) => Some(fee),
+			(FeePolicy::PerOperation(_, _, fee), Operation::Move(..)) => Some(fee),
+			_ => None,
+		}
+	}
+
+	/// All the fees of the policy.
+	pub fn fees(&self) -> Vec<&AssetValue> {
+		match self {
+			FeePolicy::Flat(fee) => vec![fee],
+			FeePolicy::PerOperation(deposit, withdraw, move_asset) =>
+				vec![deposit, withdraw, move_asset],
+		}
+	}
+}
+
+/// A withdrawal forced by `force_withdraw`, whose `Withdraw` operation is in the L1 operations
+/// queue of a zkapp.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct ForcedWithdrawal<BlockNumber> {
+	/// The index of the `Withdraw` operation in the L1 operations queue.
+	pub(super) position: u64,
+	/// The batches submitted after this block must include the operation.
+	pub(super) deadline: BlockNumber,
+}
+
+/// A batch committed by `commit_batch`, whose operations are not all executed.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct PendingBatch {
+	/// Commitment of the operations not executed, see [`operations_commitment`].
+	pub(super) commitment: OperationsCommitment,
+	/// The index of the next operation to execute.
+	pub(super) next: u32,
+	/// The number of the operations of the batch.
+	pub(super) len: u32,
+	/// The number of the L1 operations the batch include, only the first ones of the batch can
+	/// be `Deposit` operations.
+	pub(super) l1_operations_pos: u32,
+}
+
+/// The rotation of the submitters of a zkapp.
+///
+/// The blocks are divided into slots of `slot_length` blocks, the submitters lead the slots in
+/// turn, only the leader can submit batches in its slot. If no batch has been accepted for
+/// `grace_period` blocks, any submitter can.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct SubmitterRotation<BlockNumber> {
+	/// The number of blocks of a slot.
+	pub slot_length: BlockNumber,
+	/// The number of blocks without a batch after which any submitter can submit.
+	pub grace_period: BlockNumber,
+}
+
+/// The bond reserved from a zkapp's submitter.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Bond<AccountId, Balance> {
+	/// The submitter.
+	pub(super) who: AccountId,
+	/// The amount reserved.
+	pub(super) amount: Balance,
+}
+
+/// The bond of a former submitter being unbonded.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub struct Unbonding<Balance, BlockNumber> {
+	/// The amount reserved.
+	pub(super) amount: Balance,
+	/// The block number after which the bond can be withdrawn.
+	pub(super) unlock_at: BlockNumber,
 }
 
 /// User data of one zkapp.
 ///
-/// Include user's assets in on zkapp.
+/// Include user's assets in on zkapp, at most one asset value for each asset the zkapp
+/// supports. It's the leaf of the zkapp's state tree for `exit_with_proof`, the pallet keeps
+/// the balances in `ZkappsBalances` and `ZkappsItems`.
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
-#[scale_info(skip_type_params(AssetsItemLimit))]
+#[scale_info(skip_type_params(AssetsLimit))]
 pub struct Account<
 	AccountId,
 	CurrencyBalance,
@@ -180,7 +420,7 @@ pub struct Account<
 	FungibleBalance,
 	CollectionId,
 	ItemId,
-	AssetsItemLimit: Get<u32>,
+	AssetsLimit: Get<u32>,
 > {
 	/// User AccountId.
 	pub(super) user: AccountId,
@@ -188,11 +428,13 @@ pub struct Account<
 	/// User's Assets in one zkapp.
 	pub(super) assets: BoundedVec<
 		AssetValue<CurrencyBalance, AssetId, FungibleBalance, CollectionId, ItemId>,
-		AssetsItemLimit,
+		AssetsLimit,
 	>,
 }
 
 /// The output of zk-program's execution
+///
+/// The zk-program doesn't expose the whole output publicly, only its `commitment`.
 #[derive(Clone, Encode, Decode, Eq, PartialEq)]
 pub struct ProofOutput<Operation, StateRoot> {
 	/// Operations triggered by one batch's txs.
@@ -202,3 +444,47 @@ pub struct ProofOutput<Operation, StateRoot> {
 	/// The number of L1 operations the execution include.
 	pub l1_operations_pos: u32,
 }
+
+impl<Operation: Encode, StateRoot: Encode> ProofOutput<Operation, StateRoot> {
+	/// Commitment of the output, the `blake2_256` hash of the SCALE-encoded `ProofOutput`.
+	///
+	/// Every verifier checks the public outputs of a proof against it, e.g. for Miden the
+	/// commitment is split into the top 4 elements of the output stack, see
+	/// [`verifier::output_commitment_to_elements`].
+	pub fn commitment(&self) -> OutputCommitment {
+		BlakeTwo256::hash_of(self).to_fixed_bytes()
+	}
+}
+
+/// Hash chain of a zkapp's L1 operations, starting from the zero hash, each operation is chained
+/// by [`chain_l1_operation`].
+///
+/// The hash of the L1 operations consumed by a batch is one of its public inputs, see
+/// [`verifier::PublicInputs`].
+pub type L1OperationsHash = [u8; 32];
+
+/// Chain an L1 operation to `hash`, the `blake2_256` hash of `hash ++ SCALE(operation)`.
+pub fn chain_l1_operation<Operation: Encode>(
+	hash: &L1OperationsHash,
+	operation: &Operation,
+) -> L1OperationsHash {
+	BlakeTwo256::hash_of(&(hash, operation)).to_fixed_bytes()
+}
+
+/// Commitment of the operations of a batch, see [`operations_commitment`].
+pub type OperationsCommitment = [u8; 32];
+
+/// Commitment of `operations` followed by the operations of the commitment `rest`.
+///
+/// It's chained from the last operation: the commitment of no operations is the zero hash, and
+/// the one of `[operation, ..]` is the `blake2_256` hash of `SCALE(operation) ++ commitment(..)`.
+/// So `execute_batch` checks a chunk of a committed batch against the commitment with the
+/// commitment of the operations after it, before executing the chunk.
+pub fn operations_commitment<Operation: Encode>(
+	operations: &[Operation],
+	rest: OperationsCommitment,
+) -> OperationsCommitment {
+	operations.iter().rev().fold(rest, |commitment, operation| {
+		BlakeTwo256::hash_of(&(operation, commitment)).to_fixed_bytes()
+	})
+}
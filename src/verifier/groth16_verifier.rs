@@ -0,0 +1,206 @@
+//  Copyright 2022 Open Rollup Lab
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use super::*;
+use crate::Groth16Curve;
+use ark_ec::pairing::Pairing;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, VerifyingKey};
+use ark_serialize::CanonicalDeserialize;
+use sp_std::marker::PhantomData;
+
+/// Groth16 verifier on the BN254 curve, e.g. proofs of circom (snarkjs) and gnark circuits.
+pub type Groth16Bn254Verifier = Groth16Verifier<ark_bn254::Bn254>;
+/// Groth16 verifier on the BLS12-381 curve.
+pub type Groth16Bls12_381Verifier = Groth16Verifier<ark_bls12_381::Bls12_381>;
+
+/// Pairing-friendly curves supported by the Groth16 verifier.
+pub trait Groth16Pairing: Pairing {
+	/// The curve in the zkapp's `ZkvmType::Groth16`.
+	const CURVE: Groth16Curve;
+}
+impl Groth16Pairing for ark_bn254::Bn254 {
+	const CURVE: Groth16Curve = Groth16Curve::Bn254;
+}
+impl Groth16Pairing for ark_bls12_381::Bls12_381 {
+	const CURVE: Groth16Curve = Groth16Curve::Bls12_381;
+}
+
+/// Verifier for Groth16 proofs on the pairing-friendly curve `E`.
+///
+/// The verifying key is registered on-chain per zkapp, the `verifying_key` and `proof` are
+/// compressed as arkworks' `CanonicalSerialize`.
+///
+/// <https://eprint.iacr.org/2016/260>
+pub struct Groth16Verifier<E>(PhantomData<E>);
+impl<E: Groth16Pairing> Groth16Verifier<E> {
+	/// Deserialize and check the compressed verifying key.
+	pub fn parse_verifying_key(verifying_key: &[u8]) -> Result<VerifyingKey<E>, VerifyError> {
+		let mut reader = verifying_key;
+		let vk = VerifyingKey::<E>::deserialize_compressed(&mut reader)
+			.map_err(|_| VerifyError::ParseError)?;
+		// the circuit must have exactly the public inputs of `public_inputs`.
+		if !reader.is_empty() || vk.gamma_abc_g1.len() != FIELD_PUBLIC_INPUTS_LEN + 1 {
+			return Err(VerifyError::ParseError)
+		}
+		Ok(vk)
+	}
+}
+impl<E: Groth16Pairing> Verifier for Groth16Verifier<E> {
+	const ZKVM_TYPE: ZkvmType = ZkvmType::Groth16(E::CURVE);
+
+	/// Verify Groth16 proof
+	///
+	/// The `program_hash` is not used, the circuit is identified by `verifying_key`.
+	/// The public inputs of the circuit are the `inputs` and `output_commitment`, see
+	/// [`public_inputs`], the `outputs` are not used.
+	#[allow(unused_variables)]
+	fn verify(
+		program_hash: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
+		proof: &[u8],
+		outputs: &[u8],
+		output_commitment: &OutputCommitment,
+	) -> Result<(), VerifyError> {
+		if verifying_key.is_empty() {
+			return Err(VerifyError::NoVerifyingKey)
+		}
+		let vk = Self::parse_verifying_key(verifying_key)?;
+		let proof =
+			Proof::<E>::deserialize_compressed(proof).map_err(|_| VerifyError::ParseError)?;
+		let inputs = public_inputs::<E::ScalarField>(inputs, output_commitment)?;
+
+		let pvk = prepare_verifying_key(&vk);
+		match Groth16::<E>::verify_proof(&pvk, &proof, &inputs) {
+			Ok(true) => Ok(()),
+			_ => Err(VerifyError::VerifyError),
+		}
+	}
+
+	fn check_verifying_key(verifying_key: &[u8]) -> Result<(), VerifyError> {
+		Self::parse_verifying_key(verifying_key).map(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ark_relations::{
+		lc,
+		r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+	};
+	use ark_serialize::CanonicalSerialize;
+	use ark_snark::SNARK;
+	use ark_std::test_rng;
+	use sp_runtime::testing::H256;
+
+	/// Circuit proving the knowledge of its public inputs, `witness_i * 1 = input_i`.
+	struct InputsCircuit<F> {
+		inputs: Vec<F>,
+	}
+	impl<F: PrimeField> ConstraintSynthesizer<F> for InputsCircuit<F> {
+		fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+			for value in self.inputs {
+				let input = cs.new_input_variable(|| Ok(value))?;
+				let witness = cs.new_witness_variable(|| Ok(value))?;
+				cs.enforce_constraint(lc!() + witness, lc!() + Variable::One, lc!() + input)?;
+			}
+			Ok(())
+		}
+	}
+
+	/// Prove a batch of `inputs` and `commitment`, returns the serialized verifying key and proof.
+	fn prove<E: Groth16Pairing>(
+		inputs: &PublicInputs,
+		commitment: &OutputCommitment,
+	) -> (Vec<u8>, Vec<u8>) {
+		let mut rng = test_rng();
+		let inputs = public_inputs::<E::ScalarField>(inputs, commitment).unwrap();
+
+		let (pk, vk) = <Groth16<E> as SNARK<E::ScalarField>>::circuit_specific_setup(
+			InputsCircuit { inputs: inputs.clone() },
+			&mut rng,
+		)
+		.unwrap();
+		let proof =
+			<Groth16<E> as SNARK<E::ScalarField>>::prove(&pk, InputsCircuit { inputs }, &mut rng)
+				.unwrap();
+
+		let (mut vk_bytes, mut proof_bytes) = (Vec::new(), Vec::new());
+		vk.serialize_compressed(&mut vk_bytes).unwrap();
+		proof.serialize_compressed(&mut proof_bytes).unwrap();
+		(vk_bytes, proof_bytes)
+	}
+
+	/// Check the proof of the batch is verified, and rejected for other batches.
+	fn check_verify<E: Groth16Pairing>() {
+		let old_state_root = H256::repeat_byte(1);
+		let inputs = PublicInputs {
+			old_state_root: old_state_root.as_bytes(),
+			l1_operations_commitment: [5u8; 32],
+			block_number: 9,
+		};
+		let commitment = [7u8; 32];
+		let (vk, proof) = prove::<E>(&inputs, &commitment);
+
+		let verify = |inputs: &PublicInputs, proof: &[u8], commitment: &OutputCommitment| {
+			Groth16Verifier::<E>::verify(&[], &vk, inputs, proof, &[], commitment)
+		};
+		assert_eq!(verify(&inputs, &proof, &commitment), Ok(()));
+		// other commitment
+		assert_eq!(verify(&inputs, &proof, &[8u8; 32]), Err(VerifyError::VerifyError));
+		// other old state root
+		assert_eq!(
+			verify(
+				&PublicInputs { old_state_root: H256::repeat_byte(2).as_bytes(), ..inputs },
+				&proof,
+				&commitment
+			),
+			Err(VerifyError::VerifyError)
+		);
+		// other L1 operations hash chain
+		assert_eq!(
+			verify(
+				&PublicInputs { l1_operations_commitment: [6u8; 32], ..inputs },
+				&proof,
+				&commitment
+			),
+			Err(VerifyError::VerifyError)
+		);
+		// other block number
+		assert_eq!(
+			verify(&PublicInputs { block_number: 10, ..inputs }, &proof, &commitment),
+			Err(VerifyError::VerifyError)
+		);
+		// invalid proof
+		assert_eq!(verify(&inputs, &proof[1..], &commitment), Err(VerifyError::ParseError));
+		// invalid verifying key
+		assert_eq!(
+			Groth16Verifier::<E>::verify(&[], &vk[1..], &inputs, &proof, &[], &commitment),
+			Err(VerifyError::ParseError)
+		);
+	}
+
+	/// Test Groth16 Verifier on BN254 should work.
+	#[test]
+	fn bn254_works() {
+		check_verify::<ark_bn254::Bn254>();
+	}
+
+	/// Test Groth16 Verifier on BLS12-381 should work.
+	#[test]
+	fn bls12_381_works() {
+		check_verify::<ark_bls12_381::Bls12_381>();
+	}
+}
@@ -20,35 +20,38 @@ use miden::{
 };
 use sp_std::vec::Vec;
 
+/// The modulus of Miden's base field, `2^64 - 2^32 + 1`.
+const FIELD_MODULUS: u64 = 0xFFFF_FFFF_0000_0001;
+
 /// Verifier for Miden program.
 ///
 /// <https://github.com/0xPolygonMiden/miden-vm/tree/main/miden>
 pub struct MidenVerifier;
 impl Verifier for MidenVerifier {
+	const ZKVM_TYPE: ZkvmType = ZkvmType::Miden;
+
 	/// Verify miden program execution
 	///
-	/// The `old_state_root` as the public inputs.
-	/// The stack included in `outputs` include new_state_root, operations and l1_operations_pos.
+	/// The `inputs` as the public inputs, see [`public_inputs_to_stack_inputs`].
+	/// The stack included in `outputs` must start with the 4 elements of `output_commitment`,
+	/// see [`output_commitment_to_elements`], the rest of the stack is not checked.
+	#[allow(unused_variables)]
 	fn verify(
 		program_hash: &[u8],
-		old_state_root: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
 		proof: &[u8],
 		outputs: &[u8],
+		output_commitment: &OutputCommitment,
 	) -> Result<(), VerifyError> {
-
-        log::info!("MidenVerifier: \n{:?}\n{:?}\n{:?}\n{:?}\n", program_hash, old_state_root, outputs.len(), proof.len());
-        log::info!("outputs: {:?}", outputs);
-        log::info!("proof start: {:?}", &proof[..10]);
-        log::info!("proof end: {:?}", &proof[proof.len()-10..]);
-
 		// check program_hash is valid.
 		let program_hash =
 			Digest::read_from_bytes(program_hash).map_err(|_| VerifyError::ParseError)?;
 		let program_info = ProgramInfo::new(program_hash, Kernel::default());
 		let proof = ExecutionProof::from_bytes(proof).map_err(|_| VerifyError::ParseError)?;
 
-		// stack inputs deserialize from old_state_root
-		let miden_inputs = raw_inputs_to_stack_inputs(old_state_root)?;
+		// stack inputs of the batch
+		let miden_inputs = public_inputs_to_stack_inputs(inputs)?;
 
 		// outputs deserialize.
 		let miden_outputs;
@@ -74,35 +77,61 @@ impl Verifier for MidenVerifier {
 			miden_outputs = StackOutputs::from_elements(stack, overflow_addrs);
 		}
 
-		// println!("program_info: {:?}", program_info);
-		// println!("StackOutputs: {:?}", miden_outputs);
-
-		let verify_result = miden::verify(program_info, miden_inputs, miden_outputs, proof);
-        log::info!("verify_result: {:?}", verify_result);
+		// the top of the output stack must be the commitment of the batch.
+		let commitment = output_commitment_to_elements(output_commitment);
+		if miden_outputs.stack().get(..commitment.len()) != Some(&commitment[..]) {
+			return Err(VerifyError::OutputMismatch)
+		}
 
-        verify_result.map_err(|_| VerifyError::VerifyError)?;
+		miden::verify(program_info, miden_inputs, miden_outputs, proof)
+			.map_err(|_| VerifyError::VerifyError)?;
 
 		Ok(())
 	}
 }
 
-/// Convert bytes to Miden's `StackInputs`.
-pub fn raw_inputs_to_stack_inputs(raw_data: &[u8]) -> Result<StackInputs, VerifyError> {
-	if raw_data.len() != 32 {
+/// Convert the public inputs of a batch to Miden's `StackInputs`.
+///
+/// The values of the stack inputs, in the order of `StackInputs::try_from_values` (the last one
+/// ends on the top of the stack), are:
+/// - `old_state_root`, split into little-endian `u64` words, its length must be a multiple of 8,
+/// - the 4 elements of `l1_operations_commitment`, see [`output_commitment_to_elements`],
+/// - `block_number`.
+///
+/// Every value must be a canonical field element, i.e. less than the field modulus, so the state
+/// root of a Miden zkapp must be made of canonical words, e.g. the root of
+/// `RescuePrimeStateTree`.
+pub fn public_inputs_to_stack_inputs(inputs: &PublicInputs) -> Result<StackInputs, VerifyError> {
+	if inputs.old_state_root.is_empty() || inputs.old_state_root.len() % 8 != 0 {
 		return Err(VerifyError::ParseError)
 	}
 
-	let miden_inputs;
-	{
-		let mut inputs_reader = SliceReader::new(raw_data);
-		let mut stack = Vec::new();
-		for _ in 0..4 {
-			stack.push(inputs_reader.read_u64().map_err(|_| VerifyError::ParseError)?)
-		}
-		miden_inputs = StackInputs::try_from_values(stack).map_err(|_| VerifyError::ParseError)?;
+	let mut stack = Vec::new();
+	let mut inputs_reader = SliceReader::new(inputs.old_state_root);
+	for _ in 0..inputs.old_state_root.len() / 8 {
+		stack.push(inputs_reader.read_u64().map_err(|_| VerifyError::ParseError)?)
+	}
+	stack.extend(output_commitment_to_elements(&inputs.l1_operations_commitment));
+	stack.push(inputs.block_number);
+
+	if stack.iter().any(|value| *value >= FIELD_MODULUS) {
+		return Err(VerifyError::ParseError)
 	}
+	StackInputs::try_from_values(stack).map_err(|_| VerifyError::ParseError)
+}
 
-	Ok(miden_inputs)
+/// Convert an output commitment to 4 Miden field elements.
+///
+/// The commitment is split into 4 little-endian `u64` limbs, each reduced modulo the field
+/// modulus, the first limb is expected on the top of the output stack.
+pub fn output_commitment_to_elements(commitment: &OutputCommitment) -> [u64; 4] {
+	let mut elements = [0u64; 4];
+	for (element, limb) in elements.iter_mut().zip(commitment.chunks_exact(8)) {
+		let mut bytes = [0u8; 8];
+		bytes.copy_from_slice(limb);
+		*element = u64::from_le_bytes(bytes) % FIELD_MODULUS;
+	}
+	elements
 }
 
 #[cfg(test)]
@@ -111,33 +140,112 @@ mod tests {
 	use miden::{prove, utils::Serializable, Assembler, MemAdviceProvider, ProofOptions};
 	use sp_runtime::testing::H256;
 
+	/// Build a program leaving the elements of `commitment` on the top of the stack.
+	fn commitment_program(commitment: &OutputCommitment) -> miden::Program {
+		let elements = output_commitment_to_elements(commitment);
+		let source = format!(
+			"begin dropw push.{} push.{} push.{} push.{} end",
+			elements[3], elements[2], elements[1], elements[0]
+		);
+		Assembler::default().compile(&source).unwrap()
+	}
+
+	/// Public inputs of a batch from `old_state_root`.
+	fn batch_inputs(old_state_root: &H256) -> PublicInputs {
+		PublicInputs {
+			old_state_root: old_state_root.as_bytes(),
+			l1_operations_commitment: [3u8; 32],
+			block_number: 5,
+		}
+	}
+
 	/// Test Miden Verifier should work.
 	#[test]
 	fn it_works() {
-		let assembler = Assembler::default();
-		let program = assembler.compile("begin push.3 push.5 add end").unwrap();
+		let commitment = [7u8; 32];
+		let program = commitment_program(&commitment);
 
 		let old_state_root = H256::repeat_byte(1);
+		let inputs = batch_inputs(&old_state_root);
 
-		let inputs = raw_inputs_to_stack_inputs(old_state_root.as_bytes()).unwrap();
-
-        // println!("miden verifier inputs: {:?}", inputs);
+		let stack_inputs = public_inputs_to_stack_inputs(&inputs).unwrap();
 
 		let (outputs, proof) =
-			prove(&program, inputs, MemAdviceProvider::default(), ProofOptions::default()).unwrap();
-
-		// println!("program_info: {:?}", ProgramInfo::from(program.clone()));
-		// println!("StackOutputs: {:?}", outputs);
+			prove(&program, stack_inputs, MemAdviceProvider::default(), ProofOptions::default())
+				.unwrap();
 
-		assert_eq!(*outputs.stack().first().unwrap(), 8);
+		assert_eq!(outputs.stack()[..4], output_commitment_to_elements(&commitment));
 		assert_eq!(
 			MidenVerifier::verify(
 				&program.hash().as_bytes(),
-				old_state_root.as_bytes(),
+				&[],
+				&inputs,
 				&proof.to_bytes(),
-				&outputs.to_bytes()
+				&outputs.to_bytes(),
+				&commitment,
 			),
 			Ok(())
 		);
 	}
+
+	/// Test Miden Verifier rejects outputs which don't match the commitment.
+	#[test]
+	fn output_mismatch() {
+		let program = commitment_program(&[7u8; 32]);
+
+		let old_state_root = H256::repeat_byte(1);
+		let inputs = batch_inputs(&old_state_root);
+
+		let stack_inputs = public_inputs_to_stack_inputs(&inputs).unwrap();
+
+		let (outputs, proof) =
+			prove(&program, stack_inputs, MemAdviceProvider::default(), ProofOptions::default())
+				.unwrap();
+
+		assert_eq!(
+			MidenVerifier::verify(
+				&program.hash().as_bytes(),
+				&[],
+				&inputs,
+				&proof.to_bytes(),
+				&outputs.to_bytes(),
+				&[8u8; 32],
+			),
+			Err(VerifyError::OutputMismatch)
+		);
+	}
+
+	/// Test the public inputs are converted to canonical stack inputs.
+	#[test]
+	fn public_inputs_to_stack_inputs_works() {
+		let old_state_root = H256::repeat_byte(1);
+		let inputs = batch_inputs(&old_state_root);
+		let stack = |inputs: &PublicInputs| {
+			public_inputs_to_stack_inputs(inputs).map(|stack_inputs| stack_inputs.values().to_vec())
+		};
+
+		let mut values = vec![0x0101_0101_0101_0101; 4];
+		values.extend(output_commitment_to_elements(&inputs.l1_operations_commitment));
+		values.push(5);
+		assert_eq!(
+			stack(&inputs),
+			Ok(StackInputs::try_from_values(values).unwrap().values().to_vec())
+		);
+
+		// the state root isn't made of u64 words
+		assert_eq!(
+			stack(&PublicInputs { old_state_root: &old_state_root[..31], ..inputs }),
+			Err(VerifyError::ParseError)
+		);
+		// non-canonical words of the state root
+		assert_eq!(
+			stack(&PublicInputs { old_state_root: H256::repeat_byte(0xff).as_bytes(), ..inputs }),
+			Err(VerifyError::ParseError)
+		);
+		// non-canonical block number
+		assert_eq!(
+			stack(&PublicInputs { block_number: FIELD_MODULUS, ..inputs }),
+			Err(VerifyError::ParseError)
+		);
+	}
 }
@@ -12,41 +12,215 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
+use crate::ZkvmType;
+use ark_ff::PrimeField;
+use sp_std::vec::Vec;
+
 /// Zk program proof verify errors
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum VerifyError {
 	ParseError,
 	VerifyError,
+	/// The public outputs don't match the output commitment.
+	OutputMismatch,
+	/// The verifier requires a verifying key, but it's empty.
+	NoVerifyingKey,
+	/// No verifier of the zkvm type is enabled.
+	UnsupportedZkvm,
+}
+
+/// Commitment of one batch's `ProofOutput`, see `ProofOutput::commitment`.
+pub type OutputCommitment = [u8; 32];
+
+/// The public inputs of one batch's execution.
+///
+/// Every verifier binds the proof to all of them, e.g. the Miden verifier passes them to the
+/// program as the stack inputs, see [`public_inputs_to_stack_inputs`], the Groth16 and PLONK
+/// verifiers as the public inputs of the circuit, see [`public_inputs`], and the RISC Zero
+/// verifier checks them in the journal, see `risc_zero_check_journal`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PublicInputs<'a> {
+	/// The state root of the zkapp before the execution.
+	pub old_state_root: &'a [u8],
+	/// The hash chain of the L1 operations consumed by the batch and the previous ones, see
+	/// `L1OperationsHash`.
+	pub l1_operations_commitment: OutputCommitment,
+	/// The block number of the zkapp's last batch (or registration), `LastBatchAt`.
+	pub block_number: u64,
+}
+
+#[cfg(test)]
+impl<'a> PublicInputs<'a> {
+	/// Public inputs of `old_state_root`, without L1 operations at block 0.
+	pub fn from_state_root(old_state_root: &'a [u8]) -> Self {
+		PublicInputs { old_state_root, l1_operations_commitment: [0u8; 32], block_number: 0 }
+	}
+}
+
+/// The number of the public inputs of a SNARK circuit, see [`public_inputs`].
+const FIELD_PUBLIC_INPUTS_LEN: usize = 7;
+
+/// Convert the `inputs` and `output_commitment` to the public inputs of a SNARK circuit (e.g.
+/// Groth16 and PLONK) on the scalar field `F`.
+///
+/// Each of the 32 bytes values is split into 2 big-endian 128-bit limbs, the high limb first,
+/// so the inputs are `[old_state_root_hi, old_state_root_lo, l1_operations_commitment_hi,
+/// l1_operations_commitment_lo, block_number, commitment_hi, commitment_lo]` and always less
+/// than the scalar field modulus.
+pub fn public_inputs<F: PrimeField>(
+	inputs: &PublicInputs,
+	output_commitment: &OutputCommitment,
+) -> Result<Vec<F>, VerifyError> {
+	if inputs.old_state_root.len() != 32 {
+		return Err(VerifyError::ParseError)
+	}
+
+	let limbs = |value: &[u8]| -> Vec<F> {
+		value.chunks_exact(16).map(F::from_be_bytes_mod_order).collect()
+	};
+	let mut field_inputs = limbs(inputs.old_state_root);
+	field_inputs.extend(limbs(&inputs.l1_operations_commitment));
+	field_inputs.push(F::from(inputs.block_number));
+	field_inputs.extend(limbs(output_commitment));
+	Ok(field_inputs)
 }
 
 /// Zk program proof verifier trait
 pub trait Verifier {
+	/// The zkvm type of the zkapps verified by the verifier.
+	const ZKVM_TYPE: ZkvmType;
+
 	/// Verify zk-program execution
-	/// As one zk-program, should use old_state_root as the public inputs,
+	/// As one zk-program, should use `inputs` (old_state_root etc.) as the public inputs,
 	/// user txs(L1_operations and L2 transactions) and state tree as secret inputs,
 	/// the outputs of the zk-program's execution should include new_state_root,
 	/// operations and l1_operations_pos (the number of the l1_operations included)
+	///
+	/// The `outputs` are raw public outputs of the execution, the verifier must check they
+	/// include `output_commitment`, which is recomputed by the pallet from the batch submitted.
+	///
+	/// The `verifying_key` is registered on-chain by the zkapp's owner, empty if not registered,
+	/// it's only used by the verifiers whose circuits are not identified by `program_hash`.
 	fn verify(
 		program_hash: &[u8],
-		old_state_root: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
 		proof: &[u8],
 		outputs: &[u8],
+		output_commitment: &OutputCommitment,
 	) -> Result<(), VerifyError>;
+
+	/// Check the verifying key can be used by the verifier, called when it's uploaded.
+	///
+	/// The verifiers which don't use a verifying key accept any key.
+	#[allow(unused_variables)]
+	fn check_verifying_key(verifying_key: &[u8]) -> Result<(), VerifyError> {
+		Ok(())
+	}
 }
 
+mod groth16_verifier;
 mod miden_verifier;
+mod plonk_verifier;
+#[cfg(feature = "risc-zero")]
+mod risc_zero_verifier;
+
+pub use groth16_verifier::{
+	Groth16Bls12_381Verifier, Groth16Bn254Verifier, Groth16Pairing, Groth16Verifier,
+};
+pub use miden_verifier::{
+	output_commitment_to_elements, public_inputs_to_stack_inputs, MidenVerifier,
+};
+pub use plonk_verifier::{PlonkProof, PlonkVerifier, PlonkVerifyingKey};
+#[cfg(feature = "risc-zero")]
+pub use risc_zero_verifier::{check_journal as risc_zero_check_journal, RiscZeroVerifier};
 
-pub use miden_verifier::{ MidenVerifier, raw_inputs_to_stack_inputs };
+/// The verifiers enabled by the runtime, see `Config::Verifiers`.
+///
+/// Implemented for tuples of `Verifier`s, e.g. `(MidenVerifier, Groth16Bn254Verifier)`, the
+/// first verifier of the zkapp's zkvm type is used.
+pub trait Verifiers {
+	/// Whether a verifier of `zkvm_type` is enabled.
+	fn is_supported(zkvm_type: &ZkvmType) -> bool;
 
-/// One Fake verifier for testing.
+	/// Check the verifying key with the verifier of `zkvm_type`, see
+	/// `Verifier::check_verifying_key`.
+	fn check_verifying_key(zkvm_type: &ZkvmType, verifying_key: &[u8]) -> Result<(), VerifyError>;
+
+	/// Verify zk-program execution with the verifier of `zkvm_type`, see `Verifier::verify`.
+	fn verify(
+		zkvm_type: &ZkvmType,
+		program_hash: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
+		proof: &[u8],
+		outputs: &[u8],
+		output_commitment: &OutputCommitment,
+	) -> Result<(), VerifyError>;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(8)]
+#[tuple_types_custom_trait_bound(Verifier)]
+impl Verifiers for Tuple {
+	fn is_supported(zkvm_type: &ZkvmType) -> bool {
+		for_tuples!( #(
+			if *zkvm_type == Tuple::ZKVM_TYPE {
+				return true
+			}
+		)* );
+		false
+	}
+
+	fn check_verifying_key(zkvm_type: &ZkvmType, verifying_key: &[u8]) -> Result<(), VerifyError> {
+		for_tuples!( #(
+			if *zkvm_type == Tuple::ZKVM_TYPE {
+				return Tuple::check_verifying_key(verifying_key)
+			}
+		)* );
+		Err(VerifyError::UnsupportedZkvm)
+	}
+
+	fn verify(
+		zkvm_type: &ZkvmType,
+		program_hash: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
+		proof: &[u8],
+		outputs: &[u8],
+		output_commitment: &OutputCommitment,
+	) -> Result<(), VerifyError> {
+		for_tuples!( #(
+			if *zkvm_type == Tuple::ZKVM_TYPE {
+				return Tuple::verify(
+					program_hash,
+					verifying_key,
+					inputs,
+					proof,
+					outputs,
+					output_commitment,
+				)
+			}
+		)* );
+		Err(VerifyError::UnsupportedZkvm)
+	}
+}
+
+/// One Fake verifier for testing, doesn't verify the proof.
+///
+/// It must not be enabled in `Config::Verifiers` of production runtimes, only for tests and
+/// benchmarks.
 pub struct FakeVerifier;
 impl Verifier for FakeVerifier {
+	const ZKVM_TYPE: ZkvmType = ZkvmType::Fake;
+
 	#[allow(unused_variables)]
 	fn verify(
 		program_hash: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
 		proof: &[u8],
-		old_state_root: &[u8],
 		outputs: &[u8],
+		output_commitment: &OutputCommitment,
 	) -> Result<(), VerifyError> {
 		Ok(())
 	}
@@ -0,0 +1,663 @@
+//  Copyright 2022 Open Rollup Lab
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use super::*;
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{
+	pairing::Pairing,
+	short_weierstrass::{Affine, SWCurveConfig},
+	AffineRepr, CurveGroup,
+};
+use ark_ff::{BigInteger, FftField, Field, One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use sp_runtime::traits::{Hash as HashT, Keccak256};
+
+/// Verifying key of a PLONK circuit, the same as the verification key exported by snarkjs.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkVerifyingKey {
+	/// The domain size of the circuit is `2^power`.
+	pub power: u32,
+	/// The number of the public inputs.
+	pub n_public: u32,
+	/// The coset shift of the `b` wires.
+	pub k1: Fr,
+	/// The coset shift of the `c` wires.
+	pub k2: Fr,
+	/// The generator of the domain.
+	pub w: Fr,
+	/// Commitments of the selector polynomials.
+	pub qm: G1Affine,
+	pub ql: G1Affine,
+	pub qr: G1Affine,
+	pub qo: G1Affine,
+	pub qc: G1Affine,
+	/// Commitments of the permutation polynomials.
+	pub s1: G1Affine,
+	pub s2: G1Affine,
+	pub s3: G1Affine,
+	/// `[tau]_2` of the KZG setup.
+	pub x_2: G2Affine,
+}
+impl PlonkVerifyingKey {
+	/// Whether all the points are on the curve and in the prime order subgroup.
+	pub fn check_points(&self) -> bool {
+		[&self.qm, &self.ql, &self.qr, &self.qo, &self.qc, &self.s1, &self.s2, &self.s3]
+			.into_iter()
+			.all(is_valid_point) &&
+			is_valid_point(&self.x_2)
+	}
+
+	/// Whether `w` is the generator of the domain of size `2^power`, and `k1`, `k2` shift the
+	/// domain to distinct cosets, as the permutation argument requires.
+	pub fn check_domain(&self) -> bool {
+		if self.power > Fr::TWO_ADICITY {
+			return false
+		}
+		let n = 1u64 << self.power;
+		let in_domain = |x: Fr| x.pow([n]).is_one();
+		Fr::get_root_of_unity(n) == Some(self.w) &&
+			!self.k1.is_zero() &&
+			!self.k2.is_zero() &&
+			!in_domain(self.k1) &&
+			!in_domain(self.k2) &&
+			!in_domain(self.k1 / self.k2)
+	}
+}
+
+/// Proof of a PLONK circuit, the same as the proof generated by snarkjs.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PlonkProof {
+	/// Commitments of the wire polynomials.
+	pub a: G1Affine,
+	pub b: G1Affine,
+	pub c: G1Affine,
+	/// Commitment of the permutation grand product polynomial.
+	pub z: G1Affine,
+	/// Commitments of the split quotient polynomial.
+	pub t1: G1Affine,
+	pub t2: G1Affine,
+	pub t3: G1Affine,
+	/// Opening proofs at `xi` and `xi * w`.
+	pub wxi: G1Affine,
+	pub wxiw: G1Affine,
+	/// Evaluations at `xi`, except `eval_zw` at `xi * w`.
+	pub eval_a: Fr,
+	pub eval_b: Fr,
+	pub eval_c: Fr,
+	pub eval_s1: Fr,
+	pub eval_s2: Fr,
+	pub eval_zw: Fr,
+}
+impl PlonkProof {
+	/// Whether all the points are on the curve and in the prime order subgroup.
+	pub fn check_points(&self) -> bool {
+		[&self.a, &self.b, &self.c, &self.z, &self.t1, &self.t2, &self.t3, &self.wxi, &self.wxiw]
+			.into_iter()
+			.all(is_valid_point)
+	}
+}
+
+/// Whether `point` is on the curve and in the prime order subgroup, e.g. a point of the
+/// verifying key can't be used for small subgroup attacks.
+fn is_valid_point<P: SWCurveConfig>(point: &Affine<P>) -> bool {
+	point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()
+}
+
+/// Verifier for PLONK proofs with KZG commitments on the BN254 curve, e.g. circom circuits
+/// proved by snarkjs.
+///
+/// The verifying key is registered on-chain per zkapp, the `verifying_key` and `proof` are
+/// [`PlonkVerifyingKey`] and [`PlonkProof`] compressed as arkworks' `CanonicalSerialize`.
+/// The challenges are derived with the Keccak transcript of snarkjs.
+///
+/// Only the vanilla PLONK gates and permutation argument of snarkjs are verified. Halo2-KZG
+/// proofs (custom gates, lookups and the Blake2b/Poseidon transcripts) are out of scope of this
+/// verifier, they need a dedicated backend with its own `ZkvmType`.
+///
+/// <https://eprint.iacr.org/2019/953>
+pub struct PlonkVerifier;
+impl PlonkVerifier {
+	/// Deserialize and check the compressed verifying key, all its points must be on the curve
+	/// and in the prime order subgroup, and its domain and cosets must be valid.
+	pub fn parse_verifying_key(verifying_key: &[u8]) -> Result<PlonkVerifyingKey, VerifyError> {
+		let mut reader = verifying_key;
+		let vk =
+			PlonkVerifyingKey::deserialize_with_mode(&mut reader, Compress::Yes, Validate::Yes)
+				.map_err(|_| VerifyError::ParseError)?;
+		// the circuit must have exactly the public inputs of `public_inputs`.
+		if !reader.is_empty() ||
+			!vk.check_points() ||
+			!vk.check_domain() ||
+			vk.n_public as usize != FIELD_PUBLIC_INPUTS_LEN
+		{
+			return Err(VerifyError::ParseError)
+		}
+		Ok(vk)
+	}
+}
+impl Verifier for PlonkVerifier {
+	const ZKVM_TYPE: ZkvmType = ZkvmType::Plonk;
+
+	/// Verify PLONK proof
+	///
+	/// The `program_hash` is not used, the circuit is identified by `verifying_key`.
+	/// The public inputs of the circuit are the `inputs` and `output_commitment`, see
+	/// [`public_inputs`], the `outputs` are not used.
+	#[allow(unused_variables)]
+	fn verify(
+		program_hash: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
+		proof: &[u8],
+		outputs: &[u8],
+		output_commitment: &OutputCommitment,
+	) -> Result<(), VerifyError> {
+		if verifying_key.is_empty() {
+			return Err(VerifyError::NoVerifyingKey)
+		}
+		let vk = Self::parse_verifying_key(verifying_key)?;
+		let proof = PlonkProof::deserialize_with_mode(proof, Compress::Yes, Validate::Yes)
+			.ok()
+			.filter(PlonkProof::check_points)
+			.ok_or(VerifyError::ParseError)?;
+		let inputs = public_inputs::<Fr>(inputs, output_commitment)?;
+
+		if check_proof(&vk, &proof, &inputs).unwrap_or(false) {
+			Ok(())
+		} else {
+			Err(VerifyError::VerifyError)
+		}
+	}
+
+	fn check_verifying_key(verifying_key: &[u8]) -> Result<(), VerifyError> {
+		Self::parse_verifying_key(verifying_key).map(|_| ())
+	}
+}
+
+/// Keccak transcript of snarkjs, points and scalars are added as 32 bytes big-endian values.
+#[derive(Default)]
+struct Transcript(Vec<u8>);
+impl Transcript {
+	fn add_point(&mut self, point: &G1Affine) {
+		match point.xy() {
+			Some((x, y)) => {
+				self.0.extend(x.into_bigint().to_bytes_be());
+				self.0.extend(y.into_bigint().to_bytes_be());
+			},
+			None => self.0.extend([0u8; 64]),
+		}
+	}
+
+	fn add_scalar(&mut self, scalar: &Fr) {
+		self.0.extend(scalar.into_bigint().to_bytes_be());
+	}
+
+	/// Hash the data added into a challenge, and reset the transcript.
+	fn challenge(&mut self) -> Fr {
+		let hash = Keccak256::hash(&self.0);
+		self.0.clear();
+		Fr::from_be_bytes_mod_order(hash.as_bytes())
+	}
+}
+
+/// Fiat-Shamir challenges of a proof.
+struct Challenges {
+	beta: Fr,
+	gamma: Fr,
+	alpha: Fr,
+	xi: Fr,
+	v: [Fr; 5],
+	u: Fr,
+}
+impl Challenges {
+	fn new(vk: &PlonkVerifyingKey, proof: &PlonkProof, inputs: &[Fr]) -> Self {
+		let mut transcript = Transcript::default();
+
+		for point in [&vk.qm, &vk.ql, &vk.qr, &vk.qo, &vk.qc, &vk.s1, &vk.s2, &vk.s3] {
+			transcript.add_point(point);
+		}
+		for input in inputs {
+			transcript.add_scalar(input);
+		}
+		for point in [&proof.a, &proof.b, &proof.c] {
+			transcript.add_point(point);
+		}
+		let beta = transcript.challenge();
+
+		transcript.add_scalar(&beta);
+		let gamma = transcript.challenge();
+
+		transcript.add_scalar(&beta);
+		transcript.add_scalar(&gamma);
+		transcript.add_point(&proof.z);
+		let alpha = transcript.challenge();
+
+		transcript.add_scalar(&alpha);
+		for point in [&proof.t1, &proof.t2, &proof.t3] {
+			transcript.add_point(point);
+		}
+		let xi = transcript.challenge();
+
+		transcript.add_scalar(&xi);
+		for eval in [
+			&proof.eval_a,
+			&proof.eval_b,
+			&proof.eval_c,
+			&proof.eval_s1,
+			&proof.eval_s2,
+			&proof.eval_zw,
+		] {
+			transcript.add_scalar(eval);
+		}
+		let v1 = transcript.challenge();
+		let v = [v1, v1.pow([2]), v1.pow([3]), v1.pow([4]), v1.pow([5])];
+
+		transcript.add_point(&proof.wxi);
+		transcript.add_point(&proof.wxiw);
+		let u = transcript.challenge();
+
+		Self { beta, gamma, alpha, xi, v, u }
+	}
+}
+
+/// Check the proof with the public inputs, `None` if a division by zero happens.
+fn check_proof(vk: &PlonkVerifyingKey, proof: &PlonkProof, inputs: &[Fr]) -> Option<bool> {
+	let Challenges { beta, gamma, alpha, xi, v, u } = Challenges::new(vk, proof, inputs);
+	let (a, b, c) = (proof.eval_a, proof.eval_b, proof.eval_c);
+	let (s1, s2, zw) = (proof.eval_s1, proof.eval_s2, proof.eval_zw);
+
+	// evaluations of the vanishing polynomial and the Lagrange polynomials at `xi`.
+	let n = 1u64 << vk.power;
+	let xin = xi.pow([n]);
+	let zh = xin - Fr::one();
+	let mut lagrange = Vec::with_capacity(inputs.len().max(1));
+	let mut w = Fr::one();
+	for _ in 0..inputs.len().max(1) {
+		lagrange.push(w * zh * (Fr::from(n) * (xi - w)).inverse()?);
+		w *= vk.w;
+	}
+
+	// evaluation of the public inputs polynomial.
+	let pi = -inputs.iter().zip(&lagrange).map(|(input, l)| *input * l).sum::<Fr>();
+
+	// the constant part of the linearisation polynomial.
+	let alpha2 = alpha.square();
+	let e3a = a + beta * s1 + gamma;
+	let e3b = b + beta * s2 + gamma;
+	let r0 = pi - lagrange[0] * alpha2 - e3a * e3b * (c + gamma) * zw * alpha;
+
+	// commitment of the linearisation polynomial, plus `u` times the commitment of `z`.
+	let g1 = |point: &G1Affine| G1Projective::from(*point);
+	let d1 = g1(&vk.qm) * (a * b) + g1(&vk.ql) * a + g1(&vk.qr) * b + g1(&vk.qo) * c + g1(&vk.qc);
+	let beta_xi = beta * xi;
+	let d2a = (a + beta_xi + gamma) *
+		(b + beta_xi * vk.k1 + gamma) *
+		(c + beta_xi * vk.k2 + gamma) *
+		alpha;
+	let d2 = g1(&proof.z) * (d2a + lagrange[0] * alpha2 + u);
+	let d3 = g1(&vk.s3) * (e3a * e3b * alpha * beta * zw);
+	let d4 = (g1(&proof.t1) + g1(&proof.t2) * xin + g1(&proof.t3) * xin.square()) * zh;
+	let d = d1 + d2 - d3 - d4;
+
+	// batched commitment and evaluation of the polynomials opened at `xi`.
+	let f = d +
+		g1(&proof.a) * v[0] +
+		g1(&proof.b) * v[1] +
+		g1(&proof.c) * v[2] +
+		g1(&vk.s1) * v[3] +
+		g1(&vk.s2) * v[4];
+	let e = G1Projective::from(G1Affine::generator()) *
+		(-r0 + v[0] * a + v[1] * b + v[2] * c + v[3] * s1 + v[4] * s2 + u * zw);
+
+	// check the openings at `xi` and `xi * w` in one pairing.
+	let a1 = -(g1(&proof.wxi) + g1(&proof.wxiw) * u);
+	let b1 = g1(&proof.wxi) * xi + g1(&proof.wxiw) * (u * xi * vk.w) + f - e;
+	let pairing =
+		Bn254::multi_pairing([a1.into_affine(), b1.into_affine()], [vk.x_2, G2Affine::generator()]);
+	Some(pairing.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ark_bn254::{Fq, Fq2};
+	use ark_poly::{
+		univariate::DensePolynomial, DenseUVPolynomial, EvaluationDomain, Evaluations, Polynomial,
+		Radix2EvaluationDomain,
+	};
+	use ark_std::{test_rng, UniformRand};
+	use sp_runtime::testing::H256;
+
+	type Poly = DensePolynomial<Fr>;
+
+	/// The domain size of the test circuit is `2^POWER`.
+	const POWER: u32 = 4;
+
+	/// Commit a polynomial with the known `tau` of the test setup.
+	fn commit(tau: &Fr, poly: &Poly) -> G1Affine {
+		(G1Affine::generator() * poly.evaluate(tau)).into_affine()
+	}
+
+	/// Multiply `poly` by the scalar `s`.
+	fn scale(poly: &Poly, s: Fr) -> Poly {
+		Poly::from_coefficients_vec(poly.coeffs.iter().map(|coeff| *coeff * s).collect())
+	}
+
+	/// Divide `poly` by `X - z`, the remainder is dropped.
+	fn divide_by_linear(poly: &Poly, z: Fr) -> Poly {
+		let coeffs = &poly.coeffs;
+		let mut quotient = vec![Fr::zero(); coeffs.len().saturating_sub(1)];
+		let mut carry = Fr::zero();
+		for i in (1..coeffs.len()).rev() {
+			carry = coeffs[i] + carry * z;
+			quotient[i - 1] = carry;
+		}
+		Poly::from_coefficients_vec(quotient)
+	}
+
+	/// Divide `poly` by `X^n - 1`, the remainder must be zero.
+	fn divide_by_vanishing(poly: &Poly, n: usize) -> Poly {
+		let mut remainder = poly.coeffs.clone();
+		let mut quotient = vec![Fr::zero(); remainder.len().saturating_sub(n)];
+		for i in (n..remainder.len()).rev() {
+			let coeff = remainder[i];
+			quotient[i - n] += coeff;
+			remainder[i - n] += coeff;
+			remainder[i] = Fr::zero();
+		}
+		assert!(remainder.iter().all(|coeff| coeff.is_zero()));
+		Poly::from_coefficients_vec(quotient)
+	}
+
+	/// Prove a batch of `inputs` and `commitment`, returns the serialized verifying key and proof.
+	///
+	/// The circuit has a gate per public input, a multiplication gate `3 * 4 = 12`, and copies
+	/// the `a` wire of the multiplication gate to the next gate.
+	fn prove(inputs: &PublicInputs, commitment: &OutputCommitment) -> (Vec<u8>, Vec<u8>) {
+		let mut rng = test_rng();
+		let tau = Fr::rand(&mut rng);
+		let n = 1usize << POWER;
+		let domain = Radix2EvaluationDomain::<Fr>::new(n).unwrap();
+		let w = domain.element(1);
+		let (k1, k2) = (Fr::from(2u64), Fr::from(3u64));
+		let interpolate =
+			|values: Vec<Fr>| Evaluations::from_vec_and_domain(values, domain).interpolate();
+		let x = Poly::from_coefficients_vec(vec![Fr::zero(), Fr::one()]);
+		let constant = |value: Fr| Poly::from_coefficients_vec(vec![value]);
+
+		// the circuit and its witness.
+		let inputs = public_inputs::<Fr>(inputs, commitment).unwrap();
+		let m = inputs.len();
+		let (mut qm, mut ql, mut qo) =
+			(vec![Fr::zero(); n], vec![Fr::zero(); n], vec![Fr::zero(); n]);
+		let (mut a, mut b, mut c) = (vec![Fr::zero(); n], vec![Fr::zero(); n], vec![Fr::zero(); n]);
+		for (i, input) in inputs.iter().enumerate() {
+			ql[i] = Fr::one();
+			a[i] = *input;
+		}
+		qm[m] = Fr::one();
+		qo[m] = -Fr::one();
+		a[m] = Fr::from(3u64);
+		b[m] = Fr::from(4u64);
+		c[m] = Fr::from(12u64);
+		a[m + 1] = a[m];
+		let mut sigma1: Vec<Fr> = domain.elements().collect();
+		sigma1.swap(m, m + 1);
+		let sigma2: Vec<Fr> = domain.elements().map(|e| k1 * e).collect();
+		let sigma3: Vec<Fr> = domain.elements().map(|e| k2 * e).collect();
+		let mut pi = vec![Fr::zero(); n];
+		for (i, input) in inputs.iter().enumerate() {
+			pi[i] = -*input;
+		}
+		let mut l1 = vec![Fr::zero(); n];
+		l1[0] = Fr::one();
+
+		let (qm, ql, qo) = (interpolate(qm), interpolate(ql), interpolate(qo));
+		let (qr, qc) = (Poly::zero(), Poly::zero());
+		let (s1, s2, s3) =
+			(interpolate(sigma1.clone()), interpolate(sigma2.clone()), interpolate(sigma3.clone()));
+		let (pi, l1) = (interpolate(pi), interpolate(l1));
+
+		let vk = PlonkVerifyingKey {
+			power: POWER,
+			n_public: inputs.len() as u32,
+			k1,
+			k2,
+			w,
+			qm: commit(&tau, &qm),
+			ql: commit(&tau, &ql),
+			qr: commit(&tau, &qr),
+			qo: commit(&tau, &qo),
+			qc: commit(&tau, &qc),
+			s1: commit(&tau, &s1),
+			s2: commit(&tau, &s2),
+			s3: commit(&tau, &s3),
+			x_2: (G2Affine::generator() * tau).into_affine(),
+		};
+
+		// round 1, commit the wires.
+		let (a_poly, b_poly, c_poly) =
+			(interpolate(a.clone()), interpolate(b.clone()), interpolate(c.clone()));
+		let mut proof = PlonkProof {
+			a: commit(&tau, &a_poly),
+			b: commit(&tau, &b_poly),
+			c: commit(&tau, &c_poly),
+			z: G1Affine::zero(),
+			t1: G1Affine::zero(),
+			t2: G1Affine::zero(),
+			t3: G1Affine::zero(),
+			wxi: G1Affine::zero(),
+			wxiw: G1Affine::zero(),
+			eval_a: Fr::zero(),
+			eval_b: Fr::zero(),
+			eval_c: Fr::zero(),
+			eval_s1: Fr::zero(),
+			eval_s2: Fr::zero(),
+			eval_zw: Fr::zero(),
+		};
+		let Challenges { beta, gamma, .. } = Challenges::new(&vk, &proof, &inputs);
+
+		// round 2, commit the permutation grand product.
+		let mut z = vec![Fr::one(); n];
+		for (i, e) in domain.elements().enumerate().take(n - 1) {
+			let num = (a[i] + beta * e + gamma) *
+				(b[i] + beta * k1 * e + gamma) *
+				(c[i] + beta * k2 * e + gamma);
+			let den = (a[i] + beta * sigma1[i] + gamma) *
+				(b[i] + beta * sigma2[i] + gamma) *
+				(c[i] + beta * sigma3[i] + gamma);
+			z[i + 1] = z[i] * num * den.inverse().unwrap();
+		}
+		let z = interpolate(z);
+		proof.z = commit(&tau, &z);
+		let Challenges { alpha, .. } = Challenges::new(&vk, &proof, &inputs);
+
+		// round 3, commit the split quotient polynomial.
+		let mut w_power = Fr::one();
+		let zw_poly = Poly::from_coefficients_vec(
+			z.coeffs
+				.iter()
+				.map(|coeff| {
+					let shifted = *coeff * w_power;
+					w_power *= w;
+					shifted
+				})
+				.collect(),
+		);
+		let gate = &(&(&qm * &(&a_poly * &b_poly)) + &(&ql * &a_poly)) + &(&(&qo * &c_poly) + &pi);
+		let num = &(&(&a_poly + &scale(&x, beta)) + &constant(gamma)) *
+			&(&(&(&b_poly + &scale(&x, beta * k1)) + &constant(gamma)) *
+				&(&(&c_poly + &scale(&x, beta * k2)) + &constant(gamma)));
+		let den = &(&(&a_poly + &scale(&s1, beta)) + &constant(gamma)) *
+			&(&(&(&b_poly + &scale(&s2, beta)) + &constant(gamma)) *
+				&(&(&c_poly + &scale(&s3, beta)) + &constant(gamma)));
+		let permutation = scale(&(&(&num * &z) - &(&den * &zw_poly)), alpha);
+		let first = scale(&(&(&z - &constant(Fr::one())) * &l1), alpha.square());
+		let t = divide_by_vanishing(&(&(&gate + &permutation) + &first), n);
+		let chunk = |i: usize| {
+			Poly::from_coefficients_slice(
+				t.coeffs.get(i * n..((i + 1) * n).min(t.coeffs.len())).unwrap_or(&[]),
+			)
+		};
+		let (t1, t2, t3) = (chunk(0), chunk(1), chunk(2));
+		assert!(t.coeffs.len() <= 3 * n);
+		proof.t1 = commit(&tau, &t1);
+		proof.t2 = commit(&tau, &t2);
+		proof.t3 = commit(&tau, &t3);
+		let Challenges { xi, .. } = Challenges::new(&vk, &proof, &inputs);
+
+		// round 4, evaluate the polynomials.
+		proof.eval_a = a_poly.evaluate(&xi);
+		proof.eval_b = b_poly.evaluate(&xi);
+		proof.eval_c = c_poly.evaluate(&xi);
+		proof.eval_s1 = s1.evaluate(&xi);
+		proof.eval_s2 = s2.evaluate(&xi);
+		proof.eval_zw = z.evaluate(&(xi * w));
+		let Challenges { v, .. } = Challenges::new(&vk, &proof, &inputs);
+
+		// round 5, open the linearisation polynomial at `xi` and `z` at `xi * w`.
+		let (ea, eb, ec) = (proof.eval_a, proof.eval_b, proof.eval_c);
+		let (es1, es2, ezw) = (proof.eval_s1, proof.eval_s2, proof.eval_zw);
+		let xin = xi.pow([n as u64]);
+		let zh = xin - Fr::one();
+		let beta_xi = beta * xi;
+		let d2a = (ea + beta_xi + gamma) *
+			(eb + beta_xi * k1 + gamma) *
+			(ec + beta_xi * k2 + gamma) *
+			alpha;
+		let d3 = (ea + beta * es1 + gamma) * (eb + beta * es2 + gamma) * alpha * beta * ezw;
+		let r = &(&(&scale(&qm, ea * eb) + &scale(&ql, ea)) +
+			&(&scale(&qo, ec) + &scale(&z, d2a + l1.evaluate(&xi) * alpha.square()))) -
+			&(&scale(&s3, d3) +
+				&scale(&(&(&t1 + &scale(&t2, xin)) + &scale(&t3, xin.square())), zh));
+		let opened = &(&(&r + &scale(&a_poly, v[0])) +
+			&(&scale(&b_poly, v[1]) + &scale(&c_poly, v[2]))) +
+			&(&scale(&s1, v[3]) + &scale(&s2, v[4]));
+		proof.wxi = commit(&tau, &divide_by_linear(&opened, xi));
+		proof.wxiw = commit(&tau, &divide_by_linear(&z, xi * w));
+
+		let (mut vk_bytes, mut proof_bytes) = (Vec::new(), Vec::new());
+		vk.serialize_compressed(&mut vk_bytes).unwrap();
+		proof.serialize_compressed(&mut proof_bytes).unwrap();
+		(vk_bytes, proof_bytes)
+	}
+
+	/// Test PLONK Verifier should work.
+	#[test]
+	fn it_works() {
+		let old_state_root = H256::repeat_byte(1);
+		let inputs = PublicInputs {
+			old_state_root: old_state_root.as_bytes(),
+			l1_operations_commitment: [5u8; 32],
+			block_number: 9,
+		};
+		let commitment = [7u8; 32];
+		let (vk, proof) = prove(&inputs, &commitment);
+
+		let verify = |inputs: &PublicInputs, proof: &[u8], commitment: &OutputCommitment| {
+			PlonkVerifier::verify(&[], &vk, inputs, proof, &[], commitment)
+		};
+		assert_eq!(verify(&inputs, &proof, &commitment), Ok(()));
+		// other commitment
+		assert_eq!(verify(&inputs, &proof, &[8u8; 32]), Err(VerifyError::VerifyError));
+		// other old state root
+		assert_eq!(
+			verify(
+				&PublicInputs { old_state_root: H256::repeat_byte(2).as_bytes(), ..inputs },
+				&proof,
+				&commitment
+			),
+			Err(VerifyError::VerifyError)
+		);
+		// other L1 operations hash chain
+		assert_eq!(
+			verify(
+				&PublicInputs { l1_operations_commitment: [6u8; 32], ..inputs },
+				&proof,
+				&commitment
+			),
+			Err(VerifyError::VerifyError)
+		);
+		// other block number
+		assert_eq!(
+			verify(&PublicInputs { block_number: 10, ..inputs }, &proof, &commitment),
+			Err(VerifyError::VerifyError)
+		);
+		// invalid proof
+		assert_eq!(verify(&inputs, &proof[1..], &commitment), Err(VerifyError::ParseError));
+		// no verifying key
+		assert_eq!(
+			PlonkVerifier::verify(&[], &[], &inputs, &proof, &[], &commitment),
+			Err(VerifyError::NoVerifyingKey)
+		);
+	}
+
+	/// Test verifying keys with points off the curve or out of the subgroup should be rejected.
+	#[test]
+	fn invalid_points_rejected() {
+		let inputs = PublicInputs::from_state_root(H256::repeat_byte(1).as_bytes());
+		let (vk, _) = prove(&inputs, &[7u8; 32]);
+		let vk = PlonkVerifier::parse_verifying_key(&vk).unwrap();
+		assert!(vk.check_points());
+
+		// a point off the curve
+		let off_curve = G1Affine::new_unchecked(Fq::one(), Fq::one());
+		assert!(!off_curve.is_on_curve());
+		assert!(!PlonkVerifyingKey { qm: off_curve, ..vk.clone() }.check_points());
+
+		// a point on the curve but out of the prime order subgroup of G2
+		let off_subgroup = (1u64..)
+			.filter_map(|x| {
+				G2Affine::get_point_from_x_unchecked(Fq2::new(Fq::from(x), Fq::zero()), false)
+			})
+			.find(|point| !point.is_in_correct_subgroup_assuming_on_curve())
+			.unwrap();
+		let invalid_vk = PlonkVerifyingKey { x_2: off_subgroup, ..vk };
+		assert!(!invalid_vk.check_points());
+		let mut invalid_vk_bytes = Vec::new();
+		invalid_vk.serialize_compressed(&mut invalid_vk_bytes).unwrap();
+		assert_eq!(
+			PlonkVerifier::check_verifying_key(&invalid_vk_bytes),
+			Err(VerifyError::ParseError)
+		);
+	}
+
+	/// Test verifying keys with an invalid domain generator or coset shifts should be rejected.
+	#[test]
+	fn invalid_domain_rejected() {
+		let inputs = PublicInputs::from_state_root(H256::repeat_byte(1).as_bytes());
+		let (vk, _) = prove(&inputs, &[7u8; 32]);
+		let vk = PlonkVerifier::parse_verifying_key(&vk).unwrap();
+		assert!(vk.check_domain());
+
+		let w = vk.w;
+		for invalid_vk in [
+			// the generator of a smaller domain
+			PlonkVerifyingKey { w: w.square(), ..vk.clone() },
+			PlonkVerifyingKey { power: Fr::TWO_ADICITY + 1, ..vk.clone() },
+			// a shift in the domain, or two shifts to the same coset
+			PlonkVerifyingKey { k1: w, ..vk.clone() },
+			PlonkVerifyingKey { k1: Fr::zero(), ..vk.clone() },
+			PlonkVerifyingKey { k2: vk.k1 * w, ..vk.clone() },
+		] {
+			assert!(!invalid_vk.check_domain());
+			let mut invalid_vk_bytes = Vec::new();
+			invalid_vk.serialize_compressed(&mut invalid_vk_bytes).unwrap();
+			assert_eq!(
+				PlonkVerifier::check_verifying_key(&invalid_vk_bytes),
+				Err(VerifyError::ParseError)
+			);
+		}
+	}
+}
@@ -0,0 +1,230 @@
+//  Copyright 2022 Open Rollup Lab
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use super::*;
+use risc0_zkvm::{sha::Digest, Receipt};
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+
+/// Verifier for RISC Zero receipts.
+///
+/// The zkapp's `program_hash` is the image ID of the guest program, and the `proof` is the
+/// receipt serialized by `risc0_zkvm::serde::to_vec`, as little-endian `u32` words.
+///
+/// The guest must commit `old_state_root ++ l1_operations_commitment ++ SCALE(block_number)
+/// ++ SCALE(ProofOutput)` to the journal, see [`check_journal`].
+///
+/// <https://dev.risczero.com/api/zkvm/receipts>
+pub struct RiscZeroVerifier;
+impl Verifier for RiscZeroVerifier {
+	const ZKVM_TYPE: ZkvmType = ZkvmType::RiscZero;
+
+	/// Verify RISC Zero receipt
+	///
+	/// The `verifying_key` and `outputs` are not used, the public outputs are the journal of the
+	/// receipt.
+	#[allow(unused_variables)]
+	fn verify(
+		program_hash: &[u8],
+		verifying_key: &[u8],
+		inputs: &PublicInputs,
+		proof: &[u8],
+		outputs: &[u8],
+		output_commitment: &OutputCommitment,
+	) -> Result<(), VerifyError> {
+		let image_id: [u8; 32] = program_hash.try_into().map_err(|_| VerifyError::ParseError)?;
+		let receipt = decode_receipt(proof)?;
+
+		check_journal(&receipt.journal.bytes, inputs, output_commitment)?;
+
+		receipt.verify(Digest::from(image_id)).map_err(|_| VerifyError::VerifyError)
+	}
+}
+
+/// Decode a receipt from the bytes of its `u32` words.
+fn decode_receipt(proof: &[u8]) -> Result<Receipt, VerifyError> {
+	if proof.len() % 4 != 0 {
+		return Err(VerifyError::ParseError)
+	}
+	let words: Vec<u32> = proof
+		.chunks_exact(4)
+		.map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+		.collect();
+	risc0_zkvm::serde::from_slice(&words).map_err(|_| VerifyError::ParseError)
+}
+
+/// Check the journal is `old_state_root ++ l1_operations_commitment ++ SCALE(block_number) ++
+/// SCALE(ProofOutput)` of the `inputs`, and the `ProofOutput` is the one of `output_commitment`.
+pub fn check_journal(
+	journal: &[u8],
+	inputs: &PublicInputs,
+	output_commitment: &OutputCommitment,
+) -> Result<(), VerifyError> {
+	let journal_inputs = [
+		inputs.old_state_root,
+		&inputs.l1_operations_commitment,
+		&inputs.block_number.to_le_bytes(),
+	]
+	.concat();
+	if journal.len() < journal_inputs.len() {
+		return Err(VerifyError::OutputMismatch)
+	}
+	let (journal_inputs_part, proof_output) = journal.split_at(journal_inputs.len());
+	if journal_inputs_part != journal_inputs ||
+		BlakeTwo256::hash(proof_output).to_fixed_bytes() != *output_commitment
+	{
+		return Err(VerifyError::OutputMismatch)
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Operation, ProofOutput};
+	use codec::Encode;
+	use sp_runtime::testing::H256;
+
+	/// Build the journal of a batch.
+	fn journal(
+		inputs: &PublicInputs,
+		proof_output: &ProofOutput<Operation<u64, u64, H256>, H256>,
+	) -> Vec<u8> {
+		[
+			inputs.old_state_root,
+			&inputs.l1_operations_commitment,
+			&inputs.block_number.encode(),
+			&proof_output.encode(),
+		]
+		.concat()
+	}
+
+	/// Test the journal of the batch should be accepted.
+	#[test]
+	fn check_journal_works() {
+		let old_state_root = H256::repeat_byte(1);
+		let proof_output = ProofOutput {
+			operations: vec![Operation::Transfer(1, 2, 3)],
+			state_root: H256::repeat_byte(2),
+			l1_operations_pos: 0,
+		};
+		let inputs = PublicInputs {
+			old_state_root: old_state_root.as_bytes(),
+			l1_operations_commitment: [5u8; 32],
+			block_number: 9,
+		};
+		let journal = journal(&inputs, &proof_output);
+		let commitment = proof_output.commitment();
+
+		assert_eq!(check_journal(&journal, &inputs, &commitment), Ok(()));
+		// other old state root
+		assert_eq!(
+			check_journal(
+				&journal,
+				&PublicInputs { old_state_root: H256::repeat_byte(3).as_bytes(), ..inputs },
+				&commitment
+			),
+			Err(VerifyError::OutputMismatch)
+		);
+		// other L1 operations hash chain
+		assert_eq!(
+			check_journal(
+				&journal,
+				&PublicInputs { l1_operations_commitment: [6u8; 32], ..inputs },
+				&commitment
+			),
+			Err(VerifyError::OutputMismatch)
+		);
+		// other block number
+		assert_eq!(
+			check_journal(&journal, &PublicInputs { block_number: 10, ..inputs }, &commitment),
+			Err(VerifyError::OutputMismatch)
+		);
+		// other commitment
+		assert_eq!(check_journal(&journal, &inputs, &[8u8; 32]), Err(VerifyError::OutputMismatch));
+		// truncated journal
+		assert_eq!(
+			check_journal(&journal[..16], &inputs, &commitment),
+			Err(VerifyError::OutputMismatch)
+		);
+	}
+
+	/// Test invalid receipts should be rejected.
+	#[test]
+	fn invalid_receipt() {
+		let old_state_root = H256::repeat_byte(1);
+		let verify = |program_hash: &[u8], proof: &[u8]| {
+			RiscZeroVerifier::verify(
+				program_hash,
+				&[],
+				&PublicInputs::from_state_root(old_state_root.as_bytes()),
+				proof,
+				&[],
+				&[7u8; 32],
+			)
+		};
+
+		assert_eq!(verify(&[1u8; 32], &[1, 2, 3]), Err(VerifyError::ParseError));
+		assert_eq!(verify(&[1u8; 32], &[1, 2, 3, 4]), Err(VerifyError::ParseError));
+		// the image id must be 32 bytes
+		assert_eq!(verify(&[1u8; 31], &[1, 2, 3, 4]), Err(VerifyError::ParseError));
+	}
+
+	/// Test a receipt proved by the RISC Zero prover should be verified against its image ID and
+	/// journal.
+	///
+	/// The fixture is generated off-chain, as the prover and the guest toolchain aren't available
+	/// to the tests: prove a guest which commits the journal of a batch (see [`check_journal`]),
+	/// and save its image ID as `image_id` (32 bytes) and the receipt as `receipt` (the `u32`
+	/// words of `risc0_zkvm::serde::to_vec`, little-endian) in `src/verifier/fixtures/risc_zero`.
+	#[test]
+	#[ignore = "needs a receipt fixture generated by the RISC Zero prover"]
+	fn fixture_receipt_works() {
+		let fixture = |name: &str| {
+			std::fs::read(
+				std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+					.join("src/verifier/fixtures/risc_zero")
+					.join(name),
+			)
+			.unwrap()
+		};
+		let (image_id, proof) = (fixture("image_id"), fixture("receipt"));
+		let receipt = decode_receipt(&proof).unwrap();
+		let journal = &receipt.journal.bytes;
+		let (journal_inputs, proof_output) = journal.split_at(72);
+		let inputs = PublicInputs {
+			old_state_root: &journal_inputs[..32],
+			l1_operations_commitment: journal_inputs[32..64].try_into().unwrap(),
+			block_number: u64::from_le_bytes(journal_inputs[64..].try_into().unwrap()),
+		};
+		let commitment = BlakeTwo256::hash(proof_output).to_fixed_bytes();
+		let verify = |image_id: &[u8], inputs: &PublicInputs, commitment: &OutputCommitment| {
+			RiscZeroVerifier::verify(image_id, &[], inputs, &proof, &[], commitment)
+		};
+
+		assert_eq!(verify(&image_id, &inputs, &commitment), Ok(()));
+		// other image id
+		assert_eq!(verify(&[0u8; 32], &inputs, &commitment), Err(VerifyError::VerifyError));
+		// other block number
+		assert_eq!(
+			verify(
+				&image_id,
+				&PublicInputs { block_number: inputs.block_number + 1, ..inputs },
+				&commitment
+			),
+			Err(VerifyError::OutputMismatch)
+		);
+		// other commitment
+		assert_eq!(verify(&image_id, &inputs, &[8u8; 32]), Err(VerifyError::OutputMismatch));
+	}
+}
@@ -48,24 +48,51 @@ use sp_std::marker::PhantomData;
 pub trait WeightInfo {
     fn zkapp_register() -> Weight;
     fn add_asset_support() -> Weight;
-    fn change_submitter() -> Weight;
-    fn set_inactive() -> Weight;
+    fn set_fee_policy() -> Weight;
+    fn set_metadata(s: u32) -> Weight;
+    fn clear_metadata() -> Weight;
+    fn propose_upgrade(k: u32) -> Weight;
+    fn enact_upgrade() -> Weight;
+    fn set_verifying_key(k: u32) -> Weight;
+    fn change_submitter(s: u32) -> Weight;
+    fn add_submitter() -> Weight;
+    fn remove_submitter() -> Weight;
+    fn set_submitter_rotation() -> Weight;
+    fn set_inactive(s: u32) -> Weight;
+    fn transfer_ownership() -> Weight;
+    fn accept_ownership() -> Weight;
+    fn force_set_inactive(s: u32) -> Weight;
+    fn force_change_submitter(s: u32) -> Weight;
+    fn remove_asset_support() -> Weight;
+    fn force_transfer_ownership() -> Weight;
+    fn force_clear_metadata() -> Weight;
+    fn bond_submitter() -> Weight;
+    fn withdraw_unbonded() -> Weight;
     fn deposit() -> Weight;
     fn withdraw() -> Weight;
+    fn force_withdraw() -> Weight;
     fn move_asset() -> Weight;
-    fn exit() -> Weight;
+    fn exit(l: u32) -> Weight;
+    fn exit_with_proof(p: u32, l: u32) -> Weight;
+    fn refund_l1_deposits(l: u32) -> Weight;
     fn submit_batch(ops_len: u32) -> Weight;
+    fn commit_batch(ops_len: u32) -> Weight;
+    fn execute_batch(ops_len: u32) -> Weight;
+    fn claim_withdrawal() -> Weight;
+    fn check_liveness() -> Weight;
 }
 
 /// Weight functions for `pallet_open_rollup`.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup NextZkappId (r:1 w:1)
+	// Storage: OpenRollup Zkapps (r:0 w:1)
+	// Storage: OpenRollup LastBatchAt (r:0 w:1)
 	fn zkapp_register() -> Weight {
 		// Minimum execution time: 26_576 nanoseconds.
 		Weight::from_ref_time(27_203_000_u64)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
-			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
 	fn add_asset_support() -> Weight {
@@ -74,60 +101,343 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup Metadata (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn set_metadata(s: u32) -> Weight {
+		// Minimum execution time: 31_560 nanoseconds.
+		Weight::from_ref_time(32_771_000_u64)
+			.saturating_add(Weight::from_ref_time(3_000_u64).saturating_mul(s as u64))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup Metadata (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn clear_metadata() -> Weight {
+		// Minimum execution time: 24_310 nanoseconds.
+		Weight::from_ref_time(25_102_000_u64)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup PendingUpgrades (r:0 w:1)
+	fn propose_upgrade(k: u32) -> Weight {
+		// Minimum execution time: 23_942 nanoseconds.
+		Weight::from_ref_time(24_780_000_u64)
+			.saturating_add(Weight::from_ref_time(1_372_u64).saturating_mul(k as u64))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
-	fn change_submitter() -> Weight {
+	// Storage: OpenRollup PendingUpgrades (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:0)
+	// Storage: OpenRollup ForcedWithdrawals (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:0 w:1)
+	fn enact_upgrade() -> Weight {
+		// Minimum execution time: 30_216 nanoseconds.
+		Weight::from_ref_time(31_358_000_u64)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:0 w:1)
+	fn set_fee_policy() -> Weight {
+		// Minimum execution time: 24_318 nanoseconds.
+		Weight::from_ref_time(25_062_000_u64)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:1 w:1)
+	// Storage: OpenRollup PendingUpgrades (r:0 w:1)
+	fn set_verifying_key(k: u32) -> Weight {
+		// Minimum execution time: 25_341 nanoseconds.
+		Weight::from_ref_time(26_118_000_u64)
+			.saturating_add(Weight::from_ref_time(1_372_u64).saturating_mul(k as u64))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	fn change_submitter(s: u32) -> Weight {
 		// Minimum execution time: 23_980 nanoseconds.
 		Weight::from_ref_time(24_816_000_u64)
+			.saturating_add(Weight::from_ref_time(6_117_000_u64).saturating_mul(s as u64))
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(s as u64)))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(s as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	fn add_submitter() -> Weight {
+		// Minimum execution time: 21_406 nanoseconds.
+		Weight::from_ref_time(22_183_000_u64)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
-	fn set_inactive() -> Weight {
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	fn remove_submitter() -> Weight {
+		// Minimum execution time: 26_519 nanoseconds.
+		Weight::from_ref_time(27_348_000_u64)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup SubmitterRotations (r:0 w:1)
+	fn set_submitter_rotation() -> Weight {
+		// Minimum execution time: 18_742 nanoseconds.
+		Weight::from_ref_time(19_460_000_u64)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	fn set_inactive(s: u32) -> Weight {
 		// Minimum execution time: 24_124 nanoseconds.
 		Weight::from_ref_time(27_062_000_u64)
+			.saturating_add(Weight::from_ref_time(6_254_000_u64).saturating_mul(s as u64))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(s as u64)))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(s as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup PendingOwners (r:0 w:1)
+	fn transfer_ownership() -> Weight {
+		// Minimum execution time: 19_852 nanoseconds.
+		Weight::from_ref_time(20_617_000_u64)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup PendingOwners (r:1 w:1)
+	fn accept_ownership() -> Weight {
+		// Minimum execution time: 24_309 nanoseconds.
+		Weight::from_ref_time(25_148_000_u64)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:0 w:1)
+	fn force_set_inactive(s: u32) -> Weight {
+		// Minimum execution time: 22_871 nanoseconds.
+		Weight::from_ref_time(25_406_000_u64)
+			.saturating_add(Weight::from_ref_time(6_198_000_u64).saturating_mul(s as u64))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(s as u64)))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(s as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	fn force_change_submitter(s: u32) -> Weight {
+		// Minimum execution time: 22_935 nanoseconds.
+		Weight::from_ref_time(23_794_000_u64)
+			.saturating_add(Weight::from_ref_time(6_085_000_u64).saturating_mul(s as u64))
 			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(s as u64)))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(s as u64)))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
+	fn remove_asset_support() -> Weight {
+		// Minimum execution time: 21_518 nanoseconds.
+		Weight::from_ref_time(22_340_000_u64)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup PendingOwners (r:0 w:1)
+	fn force_transfer_ownership() -> Weight {
+		// Minimum execution time: 20_764 nanoseconds.
+		Weight::from_ref_time(21_455_000_u64)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup Metadata (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn force_clear_metadata() -> Weight {
+		// Minimum execution time: 23_874 nanoseconds.
+		Weight::from_ref_time(24_655_000_u64)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn bond_submitter() -> Weight {
+		// Minimum execution time: 31_210 nanoseconds.
+		Weight::from_ref_time(32_047_000_u64)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn withdraw_unbonded() -> Weight {
+		// Minimum execution time: 28_455 nanoseconds.
+		Weight::from_ref_time(29_318_000_u64)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
 	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
 	fn deposit() -> Weight {
 		// Minimum execution time: 67_083 nanoseconds.
 		Weight::from_ref_time(68_999_000_u64)
-			.saturating_add(T::DbWeight::get().reads(5_u64))
-			.saturating_add(T::DbWeight::get().writes(5_u64))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
 	}
-	// Storage: OpenRollup Zkapps (r:1 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:1 w:0)
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
 	fn withdraw() -> Weight {
 		// Minimum execution time: 29_619 nanoseconds.
 		Weight::from_ref_time(30_187_000_u64)
-			.saturating_add(T::DbWeight::get().reads(2_u64))
-			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
 	}
-	// Storage: OpenRollup Zkapps (r:2 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:1 w:0)
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawals (r:0 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn force_withdraw() -> Weight {
+		// Minimum execution time: 33_702 nanoseconds.
+		Weight::from_ref_time(34_915_000_u64)
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:2 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
 	fn move_asset() -> Weight {
 		// Minimum execution time: 33_833 nanoseconds.
 		Weight::from_ref_time(37_397_000_u64)
-			.saturating_add(T::DbWeight::get().reads(3_u64))
-			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:0)
 	// Storage: OpenRollup ZkappsExit (r:1 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:1 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:1)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustodyItems (r:1 w:1)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
 	// Storage: System Account (r:1 w:1)
-	fn exit() -> Weight {
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup ExitRefundCursors (r:0 w:1)
+	fn exit(l: u32) -> Weight {
 		// Minimum execution time: 69_844 nanoseconds.
 		Weight::from_ref_time(77_761_000_u64)
-			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(Weight::from_ref_time(4_312_000_u64).saturating_mul(l as u64))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(l as u64)))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(l as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup ZkappsExit (r:1 w:1)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:1)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup ExitRefundCursors (r:0 w:1)
+	fn exit_with_proof(p: u32, l: u32) -> Weight {
+		// Minimum execution time: 75_102 nanoseconds.
+		Weight::from_ref_time(76_480_000_u64)
+			.saturating_add(Weight::from_ref_time(1_981_000_u64).saturating_mul(p as u64))
+			.saturating_add(Weight::from_ref_time(4_312_000_u64).saturating_mul(l as u64))
+			.saturating_add(T::DbWeight::get().reads(10_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(l as u64)))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(l as u64)))
+	}
+	// Storage: OpenRollup ExitRefundCursors (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn refund_l1_deposits(l: u32) -> Weight {
+		// Minimum execution time: 41_627 nanoseconds.
+		Weight::from_ref_time(43_105_000_u64)
+			.saturating_add(Weight::from_ref_time(4_312_000_u64).saturating_mul(l as u64))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(l as u64)))
 			.saturating_add(T::DbWeight::get().writes(5_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(l as u64)))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:2 w:2)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:0)
+	// Storage: OpenRollup SubmitterRotations (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:1 w:0)
+	// Storage: OpenRollup PendingBatches (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup PendingWithdrawals (r:2 w:2)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawals (r:1 w:1)
+	// Storage: OpenRollup ZkappsBalances (r:2 w:2)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:2 w:2)
+	// Storage: OpenRollup ZkappsCustodyItems (r:0 w:1)
 	// Storage: Uniques Asset (r:1 w:1)
 	// Storage: Uniques Class (r:1 w:0)
 	// Storage: Uniques Account (r:0 w:2)
@@ -136,19 +446,88 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 		// Minimum execution time: 85_676 nanoseconds.
 		Weight::from_ref_time(87_229_000_u64)
             .saturating_add(Weight::from_ref_time(5_000_000_u64).saturating_mul(ops_len as u64))
-			.saturating_add(T::DbWeight::get().reads(5_u64))
-			.saturating_add(T::DbWeight::get().writes(7_u64))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((8_u64).saturating_mul(ops_len as u64)))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+			.saturating_add(T::DbWeight::get().writes((9_u64).saturating_mul(ops_len as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:0)
+	// Storage: OpenRollup SubmitterRotations (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:1 w:0)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup PendingWithdrawals (r:2 w:2)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawals (r:1 w:1)
+	fn commit_batch(ops_len: u32) -> Weight {
+		// Minimum execution time: 61_903 nanoseconds.
+		Weight::from_ref_time(63_150_000_u64)
+			.saturating_add(Weight::from_ref_time(1_127_000_u64).saturating_mul(ops_len as u64))
+			.saturating_add(T::DbWeight::get().reads(11_u64))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(ops_len as u64)))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(ops_len as u64)))
+	}
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:0 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup ZkappsBalances (r:2 w:2)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:2 w:2)
+	// Storage: OpenRollup ZkappsCustodyItems (r:0 w:1)
+	// Storage: Uniques Asset (r:1 w:1)
+	// Storage: Uniques Class (r:1 w:0)
+	// Storage: Uniques Account (r:0 w:2)
+	// Storage: Uniques ItemPriceOf (r:0 w:1)
+	fn execute_batch(ops_len: u32) -> Weight {
+		// Minimum execution time: 32_418 nanoseconds.
+		Weight::from_ref_time(33_876_000_u64)
+			.saturating_add(Weight::from_ref_time(5_000_000_u64).saturating_mul(ops_len as u64))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((7_u64).saturating_mul(ops_len as u64)))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+			.saturating_add(T::DbWeight::get().writes((8_u64).saturating_mul(ops_len as u64)))
+	}
+	// Storage: OpenRollup PendingWithdrawals (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn claim_withdrawal() -> Weight {
+		// Minimum execution time: 45_371 nanoseconds.
+		Weight::from_ref_time(46_782_000_u64)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	// Storage: OpenRollup LastBatchAt (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	fn check_liveness() -> Weight {
+		// Minimum execution time: 38_614 nanoseconds.
+		Weight::from_ref_time(39_902_000_u64)
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
 	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup NextZkappId (r:1 w:1)
+	// Storage: OpenRollup Zkapps (r:0 w:1)
+	// Storage: OpenRollup LastBatchAt (r:0 w:1)
 	fn zkapp_register() -> Weight {
 		// Minimum execution time: 26_576 nanoseconds.
 		Weight::from_ref_time(27_203_000_u64)
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
-			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
 	fn add_asset_support() -> Weight {
@@ -157,60 +536,343 @@ impl WeightInfo for () {
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup Metadata (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn set_metadata(s: u32) -> Weight {
+		// Minimum execution time: 31_560 nanoseconds.
+		Weight::from_ref_time(32_771_000_u64)
+			.saturating_add(Weight::from_ref_time(3_000_u64).saturating_mul(s as u64))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup Metadata (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn clear_metadata() -> Weight {
+		// Minimum execution time: 24_310 nanoseconds.
+		Weight::from_ref_time(25_102_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup PendingUpgrades (r:0 w:1)
+	fn propose_upgrade(k: u32) -> Weight {
+		// Minimum execution time: 23_942 nanoseconds.
+		Weight::from_ref_time(24_780_000_u64)
+			.saturating_add(Weight::from_ref_time(1_372_u64).saturating_mul(k as u64))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
-	fn change_submitter() -> Weight {
+	// Storage: OpenRollup PendingUpgrades (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:0)
+	// Storage: OpenRollup ForcedWithdrawals (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:0 w:1)
+	fn enact_upgrade() -> Weight {
+		// Minimum execution time: 30_216 nanoseconds.
+		Weight::from_ref_time(31_358_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:0 w:1)
+	fn set_fee_policy() -> Weight {
+		// Minimum execution time: 24_318 nanoseconds.
+		Weight::from_ref_time(25_062_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:1 w:1)
+	// Storage: OpenRollup PendingUpgrades (r:0 w:1)
+	fn set_verifying_key(k: u32) -> Weight {
+		// Minimum execution time: 25_341 nanoseconds.
+		Weight::from_ref_time(26_118_000_u64)
+			.saturating_add(Weight::from_ref_time(1_372_u64).saturating_mul(k as u64))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	fn change_submitter(s: u32) -> Weight {
 		// Minimum execution time: 23_980 nanoseconds.
 		Weight::from_ref_time(24_816_000_u64)
+			.saturating_add(Weight::from_ref_time(6_117_000_u64).saturating_mul(s as u64))
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(s as u64)))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(s as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	fn add_submitter() -> Weight {
+		// Minimum execution time: 21_406 nanoseconds.
+		Weight::from_ref_time(22_183_000_u64)
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
-	fn set_inactive() -> Weight {
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	fn remove_submitter() -> Weight {
+		// Minimum execution time: 26_519 nanoseconds.
+		Weight::from_ref_time(27_348_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup SubmitterRotations (r:0 w:1)
+	fn set_submitter_rotation() -> Weight {
+		// Minimum execution time: 18_742 nanoseconds.
+		Weight::from_ref_time(19_460_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	fn set_inactive(s: u32) -> Weight {
 		// Minimum execution time: 24_124 nanoseconds.
 		Weight::from_ref_time(27_062_000_u64)
+			.saturating_add(Weight::from_ref_time(6_254_000_u64).saturating_mul(s as u64))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(s as u64)))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(s as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup PendingOwners (r:0 w:1)
+	fn transfer_ownership() -> Weight {
+		// Minimum execution time: 19_852 nanoseconds.
+		Weight::from_ref_time(20_617_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup PendingOwners (r:1 w:1)
+	fn accept_ownership() -> Weight {
+		// Minimum execution time: 24_309 nanoseconds.
+		Weight::from_ref_time(25_148_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup LastBatchAt (r:0 w:1)
+	fn force_set_inactive(s: u32) -> Weight {
+		// Minimum execution time: 22_871 nanoseconds.
+		Weight::from_ref_time(25_406_000_u64)
+			.saturating_add(Weight::from_ref_time(6_198_000_u64).saturating_mul(s as u64))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(s as u64)))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(s as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	fn force_change_submitter(s: u32) -> Weight {
+		// Minimum execution time: 22_935 nanoseconds.
+		Weight::from_ref_time(23_794_000_u64)
+			.saturating_add(Weight::from_ref_time(6_085_000_u64).saturating_mul(s as u64))
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(s as u64)))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(s as u64)))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
+	fn remove_asset_support() -> Weight {
+		// Minimum execution time: 21_518 nanoseconds.
+		Weight::from_ref_time(22_340_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup PendingOwners (r:0 w:1)
+	fn force_transfer_ownership() -> Weight {
+		// Minimum execution time: 20_764 nanoseconds.
+		Weight::from_ref_time(21_455_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup Metadata (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn force_clear_metadata() -> Weight {
+		// Minimum execution time: 23_874 nanoseconds.
+		Weight::from_ref_time(24_655_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn bond_submitter() -> Weight {
+		// Minimum execution time: 31_210 nanoseconds.
+		Weight::from_ref_time(32_047_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	fn withdraw_unbonded() -> Weight {
+		// Minimum execution time: 28_455 nanoseconds.
+		Weight::from_ref_time(29_318_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
 	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
 	fn deposit() -> Weight {
 		// Minimum execution time: 67_083 nanoseconds.
 		Weight::from_ref_time(68_999_000_u64)
-			.saturating_add(RocksDbWeight::get().reads(5_u64))
-			.saturating_add(RocksDbWeight::get().writes(5_u64))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
 	}
-	// Storage: OpenRollup Zkapps (r:1 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:1 w:0)
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
 	fn withdraw() -> Weight {
 		// Minimum execution time: 29_619 nanoseconds.
 		Weight::from_ref_time(30_187_000_u64)
-			.saturating_add(RocksDbWeight::get().reads(2_u64))
-			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
 	}
-	// Storage: OpenRollup Zkapps (r:2 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:1 w:0)
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawals (r:0 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn force_withdraw() -> Weight {
+		// Minimum execution time: 33_702 nanoseconds.
+		Weight::from_ref_time(34_915_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	// Storage: OpenRollup Zkapps (r:2 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup FeePolicies (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:0 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
 	fn move_asset() -> Weight {
 		// Minimum execution time: 33_833 nanoseconds.
 		Weight::from_ref_time(37_397_000_u64)
-			.saturating_add(RocksDbWeight::get().reads(3_u64))
-			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:0)
 	// Storage: OpenRollup ZkappsExit (r:1 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:1 w:0)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:1)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustodyItems (r:1 w:1)
 	// Storage: Assets Asset (r:1 w:1)
 	// Storage: Assets Account (r:2 w:2)
 	// Storage: System Account (r:1 w:1)
-	fn exit() -> Weight {
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup ExitRefundCursors (r:0 w:1)
+	fn exit(l: u32) -> Weight {
 		// Minimum execution time: 69_844 nanoseconds.
 		Weight::from_ref_time(77_761_000_u64)
-			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(Weight::from_ref_time(4_312_000_u64).saturating_mul(l as u64))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(l as u64)))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(l as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:0)
+	// Storage: OpenRollup ZkappsExit (r:1 w:1)
+	// Storage: OpenRollup ZkappsBalances (r:1 w:1)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup ExitRefundCursors (r:0 w:1)
+	fn exit_with_proof(p: u32, l: u32) -> Weight {
+		// Minimum execution time: 75_102 nanoseconds.
+		Weight::from_ref_time(76_480_000_u64)
+			.saturating_add(Weight::from_ref_time(1_981_000_u64).saturating_mul(p as u64))
+			.saturating_add(Weight::from_ref_time(4_312_000_u64).saturating_mul(l as u64))
+			.saturating_add(RocksDbWeight::get().reads(10_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(l as u64)))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(l as u64)))
+	}
+	// Storage: OpenRollup ExitRefundCursors (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn refund_l1_deposits(l: u32) -> Weight {
+		// Minimum execution time: 41_627 nanoseconds.
+		Weight::from_ref_time(43_105_000_u64)
+			.saturating_add(Weight::from_ref_time(4_312_000_u64).saturating_mul(l as u64))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(l as u64)))
 			.saturating_add(RocksDbWeight::get().writes(5_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(l as u64)))
 	}
 	// Storage: OpenRollup Zkapps (r:1 w:1)
-	// Storage: OpenRollup ZkappsAccounts (r:2 w:2)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:0)
+	// Storage: OpenRollup SubmitterRotations (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:1 w:0)
+	// Storage: OpenRollup PendingBatches (r:1 w:0)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup PendingWithdrawals (r:2 w:2)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawals (r:1 w:1)
+	// Storage: OpenRollup ZkappsBalances (r:2 w:2)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:2 w:2)
+	// Storage: OpenRollup ZkappsCustodyItems (r:0 w:1)
 	// Storage: Uniques Asset (r:1 w:1)
 	// Storage: Uniques Class (r:1 w:0)
 	// Storage: Uniques Account (r:0 w:2)
@@ -219,7 +881,74 @@ impl WeightInfo for () {
 		// Minimum execution time: 85_676 nanoseconds.
 		Weight::from_ref_time(87_229_000_u64)
             .saturating_add(Weight::from_ref_time(5_000_000_u64).saturating_mul(ops_len as u64))
-			.saturating_add(RocksDbWeight::get().reads(5_u64))
-			.saturating_add(RocksDbWeight::get().writes(7_u64))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((8_u64).saturating_mul(ops_len as u64)))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+			.saturating_add(RocksDbWeight::get().writes((9_u64).saturating_mul(ops_len as u64)))
+	}
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:0)
+	// Storage: OpenRollup SubmitterRotations (r:1 w:0)
+	// Storage: OpenRollup VerifyingKeys (r:1 w:0)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup L1OperationFees (r:1 w:1)
+	// Storage: OpenRollup PendingWithdrawals (r:2 w:2)
+	// Storage: OpenRollup ForcedWithdrawalsQueue (r:1 w:1)
+	// Storage: OpenRollup ForcedWithdrawals (r:1 w:1)
+	fn commit_batch(ops_len: u32) -> Weight {
+		// Minimum execution time: 61_903 nanoseconds.
+		Weight::from_ref_time(63_150_000_u64)
+			.saturating_add(Weight::from_ref_time(1_127_000_u64).saturating_mul(ops_len as u64))
+			.saturating_add(RocksDbWeight::get().reads(11_u64))
+			.saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(ops_len as u64)))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(ops_len as u64)))
+	}
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:1)
+	// Storage: OpenRollup L1OperationsConsumedHash (r:0 w:1)
+	// Storage: OpenRollup L1Operations (r:1 w:1)
+	// Storage: OpenRollup ZkappsBalances (r:2 w:2)
+	// Storage: OpenRollup ZkappsItems (r:1 w:1)
+	// Storage: OpenRollup ZkappsCustody (r:2 w:2)
+	// Storage: OpenRollup ZkappsCustodyItems (r:0 w:1)
+	// Storage: Uniques Asset (r:1 w:1)
+	// Storage: Uniques Class (r:1 w:0)
+	// Storage: Uniques Account (r:0 w:2)
+	// Storage: Uniques ItemPriceOf (r:0 w:1)
+	fn execute_batch(ops_len: u32) -> Weight {
+		// Minimum execution time: 32_418 nanoseconds.
+		Weight::from_ref_time(33_876_000_u64)
+			.saturating_add(Weight::from_ref_time(5_000_000_u64).saturating_mul(ops_len as u64))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((7_u64).saturating_mul(ops_len as u64)))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+			.saturating_add(RocksDbWeight::get().writes((8_u64).saturating_mul(ops_len as u64)))
+	}
+	// Storage: OpenRollup PendingWithdrawals (r:1 w:1)
+	// Storage: Assets Asset (r:1 w:1)
+	// Storage: Assets Account (r:2 w:2)
+	fn claim_withdrawal() -> Weight {
+		// Minimum execution time: 45_371 nanoseconds.
+		Weight::from_ref_time(46_782_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	// Storage: OpenRollup LastBatchAt (r:1 w:1)
+	// Storage: OpenRollup L1OperationsQueue (r:1 w:0)
+	// Storage: OpenRollup L1Operations (r:1 w:0)
+	// Storage: OpenRollup Zkapps (r:1 w:1)
+	// Storage: OpenRollup SubmitterBonds (r:1 w:1)
+	// Storage: OpenRollup Unbondings (r:1 w:1)
+	// Storage: System Account (r:1 w:1)
+	// Storage: OpenRollup PendingBatches (r:1 w:1)
+	fn check_liveness() -> Weight {
+		// Minimum execution time: 38_614 nanoseconds.
+		Weight::from_ref_time(39_902_000_u64)
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
 	}
 }